@@ -0,0 +1,246 @@
+//! Portable single-file archives of a GG config set.
+//!
+//! Archives carry a config between machines that aren't enrolled in sync (a LAN
+//! venue PC, a USB stick). The file starts with a magic tag and a format version,
+//! followed by a zstd-compressed JSON container holding a manifest, the database
+//! files and an optional HMAC-SHA256 signature over the manifest. The manifest
+//! records each file's hash, so the signature covers the files as well.
+//! Archives conventionally use the `.ggsync` extension.
+
+use crate::backup::{sha256_hex, BackupFile};
+use crate::diff::decode_hex;
+use crate::providers::{ConfigSnapshot, SyncMeta};
+use crate::safety::sqlite_user_version;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine as _;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::BTreeMap;
+
+const MAGIC: &[u8; 8] = b"GGSYNCAR";
+const FORMAT_VERSION: u16 = 1;
+const ZSTD_LEVEL: i32 = 19;
+
+/// Describes the config set inside an archive.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveManifest {
+    pub created: DateTime<Utc>,
+    /// Device the config was taken from.
+    pub source_device: String,
+    /// When the config was last modified on the source device.
+    pub last_modified: DateTime<Utc>,
+    pub files: Vec<BackupFile>,
+    pub app_version: String,
+    /// SQLite `user_version` of the GG database, if set.
+    pub gg_schema_version: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Container {
+    manifest: ArchiveManifest,
+    /// Base64-encoded file contents by name.
+    files: BTreeMap<String, String>,
+    /// Hex HMAC-SHA256 of the JSON-encoded manifest.
+    signature: Option<String>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ArchiveError {
+    #[error("Not a config archive")]
+    NotAnArchive,
+    #[error("Archive format version {0} is not supported")]
+    UnsupportedVersion(u16),
+    #[error("Archive is corrupt: {0}")]
+    Corrupt(String),
+    #[error("Archive is not signed, but a signing key is configured")]
+    Unsigned,
+    #[error("Archive signature does not match the configured key")]
+    BadSignature,
+}
+
+/// Encode a snapshot as an archive, signed if `key` is given.
+pub fn write_archive(snapshot: &ConfigSnapshot, key: Option<&[u8]>) -> Vec<u8> {
+    let mut contents = vec![("database.db", &snapshot.db)];
+    if let Some(shm) = &snapshot.db_shm {
+        contents.push(("database.db-shm", shm));
+    }
+    if let Some(wal) = &snapshot.db_wal {
+        contents.push(("database.db-wal", wal));
+    }
+
+    let manifest = ArchiveManifest {
+        created: Utc::now(),
+        source_device: snapshot.meta.device_name.clone(),
+        last_modified: snapshot.meta.last_modified,
+        files: contents
+            .iter()
+            .map(|(name, data)| BackupFile {
+                name: name.to_string(),
+                size: data.len() as u64,
+                compressed_size: None,
+                sha256: sha256_hex(data),
+            })
+            .collect(),
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        gg_schema_version: sqlite_user_version(&snapshot.db),
+    };
+    let container = Container {
+        signature: key.map(|key| sign(key, &manifest)),
+        files: contents
+            .iter()
+            .map(|(name, data)| (name.to_string(), BASE64.encode(data)))
+            .collect(),
+        manifest,
+    };
+
+    let json = serde_json::to_vec(&container).expect("archive container serializes");
+    let body = zstd::encode_all(json.as_slice(), ZSTD_LEVEL).expect("in-memory zstd encode");
+    let mut archive = Vec::with_capacity(MAGIC.len() + 2 + body.len());
+    archive.extend_from_slice(MAGIC);
+    archive.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+    archive.extend_from_slice(&body);
+    archive
+}
+
+/// Decode an archive and verify its file hashes. When `key` is given the archive
+/// must carry a matching signature.
+pub fn read_archive(
+    data: &[u8],
+    key: Option<&[u8]>,
+) -> Result<(ConfigSnapshot, ArchiveManifest), ArchiveError> {
+    let header_len = MAGIC.len() + 2;
+    if data.len() < header_len || &data[..MAGIC.len()] != MAGIC {
+        return Err(ArchiveError::NotAnArchive);
+    }
+    let version = u16::from_le_bytes([data[MAGIC.len()], data[MAGIC.len() + 1]]);
+    if version != FORMAT_VERSION {
+        return Err(ArchiveError::UnsupportedVersion(version));
+    }
+    let json =
+        zstd::decode_all(&data[header_len..]).map_err(|e| ArchiveError::Corrupt(e.to_string()))?;
+    let container: Container =
+        serde_json::from_slice(&json).map_err(|e| ArchiveError::Corrupt(e.to_string()))?;
+
+    if let Some(key) = key {
+        let signature = container
+            .signature
+            .as_deref()
+            .ok_or(ArchiveError::Unsigned)?;
+        if !verify(key, &container.manifest, signature) {
+            return Err(ArchiveError::BadSignature);
+        }
+    }
+
+    let mut files = BTreeMap::new();
+    for file in &container.manifest.files {
+        let encoded = container
+            .files
+            .get(&file.name)
+            .ok_or_else(|| ArchiveError::Corrupt(format!("missing {}", file.name)))?;
+        let bytes = BASE64
+            .decode(encoded)
+            .map_err(|e| ArchiveError::Corrupt(format!("{}: {}", file.name, e)))?;
+        if sha256_hex(&bytes) != file.sha256 {
+            return Err(ArchiveError::Corrupt(format!(
+                "hash mismatch for {}",
+                file.name
+            )));
+        }
+        files.insert(file.name.as_str(), bytes);
+    }
+
+    let manifest = container.manifest.clone();
+    let snapshot = ConfigSnapshot {
+        db: files
+            .remove("database.db")
+            .ok_or_else(|| ArchiveError::Corrupt("missing database.db".to_string()))?,
+        db_shm: files.remove("database.db-shm"),
+        db_wal: files.remove("database.db-wal"),
+        meta: SyncMeta {
+            last_modified: manifest.last_modified,
+            device_name: manifest.source_device.clone(),
+        },
+    };
+    Ok((snapshot, manifest))
+}
+
+fn mac(key: &[u8], manifest: &ArchiveManifest) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(&serde_json::to_vec(manifest).expect("manifest serializes"));
+    mac
+}
+
+fn sign(key: &[u8], manifest: &ArchiveManifest) -> String {
+    format!("{:x}", mac(key, manifest).finalize().into_bytes())
+}
+
+fn verify(key: &[u8], manifest: &ArchiveManifest, signature: &str) -> bool {
+    let Some(expected) = decode_hex(signature) else {
+        return false;
+    };
+    mac(key, manifest).verify_slice(&expected).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot() -> ConfigSnapshot {
+        ConfigSnapshot {
+            db: b"SQLite format 3\0db".to_vec(),
+            db_shm: None,
+            db_wal: Some(b"wal".to_vec()),
+            meta: SyncMeta {
+                last_modified: Utc::now(),
+                device_name: "venue-pc".into(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let archive = write_archive(&snapshot(), None);
+
+        let (restored, manifest) = read_archive(&archive, None).unwrap();
+
+        assert_eq!(restored.db, snapshot().db);
+        assert_eq!(restored.db_wal.as_deref(), Some(&b"wal"[..]));
+        assert!(restored.db_shm.is_none());
+        assert_eq!(manifest.source_device, "venue-pc");
+        assert_eq!(manifest.files.len(), 2);
+    }
+
+    #[test]
+    fn test_signature_is_enforced_when_key_configured() {
+        let signed = write_archive(&snapshot(), Some(b"secret"));
+        let unsigned = write_archive(&snapshot(), None);
+
+        assert!(read_archive(&signed, Some(b"secret")).is_ok());
+        assert!(read_archive(&signed, None).is_ok());
+        assert!(matches!(
+            read_archive(&signed, Some(b"other")),
+            Err(ArchiveError::BadSignature)
+        ));
+        assert!(matches!(
+            read_archive(&unsigned, Some(b"secret")),
+            Err(ArchiveError::Unsigned)
+        ));
+    }
+
+    #[test]
+    fn test_rejects_foreign_and_future_files() {
+        assert!(matches!(
+            read_archive(b"PK\x03\x04", None),
+            Err(ArchiveError::NotAnArchive)
+        ));
+
+        let mut archive = write_archive(&snapshot(), None);
+        archive[MAGIC.len()] = 9;
+        assert!(matches!(
+            read_archive(&archive, None),
+            Err(ArchiveError::UnsupportedVersion(9))
+        ));
+    }
+}
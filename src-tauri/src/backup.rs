@@ -1,89 +1,1172 @@
 //! Timestamped backup manager with configurable retention.
+//!
+//! File contents live in a content-addressed store (`blobs/<ab>/<sha256>.zst`),
+//! zstd-compressed and keyed by the hash of the uncompressed data, so identical
+//! files are stored once no matter how many backups reference them.
+//! Each backup is a directory named `<label>-<ULID>`, holding only a
+//! `manifest.json` that describes why, when and where it was taken and which
+//! blobs make it up. Backups from before the store keep their files inline, and
+//! older timestamp-named backups (`<label>-<%Y-%m-%dT%H-%M-%S>`) remain readable.
+//! Results of integrity verification are kept in `verification.json` at the root.
 
-use chrono::Utc;
+use crate::config::RetentionPolicy;
+use crate::providers::{ConfigSnapshot, SyncMeta};
+use crate::safety::{check_disk_space, sqlite_user_version, SafetyCheck, DEFAULT_SPACE_MARGIN};
+use crate::snapshot_db::check_integrity;
+use chrono::{DateTime, Datelike, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use ulid::Ulid;
+
+/// Files that make up a GG config set, in the order they are written.
+pub const DB_FILES: &[&str] = &["database.db", "database.db-shm", "database.db-wal"];
+
+const MANIFEST_FILE: &str = "manifest.json";
+const BLOB_DIR: &str = "blobs";
+const LAST_RESTORE_FILE: &str = "last_restore.json";
+const VERIFICATION_FILE: &str = "verification.json";
+const ZSTD_LEVEL: i32 = 3;
+const LEGACY_NAME_FORMAT: &str = "%Y-%m-%dT%H-%M-%S";
+const MAX_LABEL_LEN: usize = 32;
+
+/// Backup IDs are monotonic within the process, so backups taken in the same
+/// millisecond still get distinct, ordered IDs.
+static ID_GENERATOR: Mutex<Option<ulid::Generator>> = Mutex::new(None);
 
 pub struct BackupManager {
     backup_dir: PathBuf,
     max_backups: usize,
+    retention: RetentionPolicy,
+    /// Scheduled backups are pruned separately, with their own limits.
+    max_scheduled: usize,
+    scheduled_retention: RetentionPolicy,
+    /// Maximum bytes the backup directory may use, if limited.
+    budget: Option<u64>,
+    /// Free space to keep on the backup volume on top of each write.
+    space_margin: u64,
+    /// Recorded as the source device in each manifest.
+    device_name: String,
+    /// Held while blobs are stored, backups pruned or garbage collected, so a
+    /// collection never sees the blobs of a backup whose manifest isn't written yet.
+    store_lock: Mutex<()>,
+}
+
+/// Why a backup was taken.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BackupReason {
+    PrePull,
+    PrePush,
+    Manual,
+    PreRestore,
+    /// Imported from an archive file.
+    Imported,
+    /// Taken by the backup scheduler.
+    Scheduled,
+    /// Saved into a named config set.
+    ConfigSet,
+}
+
+impl BackupReason {
+    /// Label used as the backup directory prefix.
+    pub fn label(&self) -> &'static str {
+        match self {
+            BackupReason::PrePull => "pre-pull",
+            BackupReason::PrePush => "pre-push",
+            BackupReason::Manual => "manual",
+            BackupReason::PreRestore => "pre-restore",
+            BackupReason::Imported => "imported",
+            BackupReason::Scheduled => "scheduled",
+            BackupReason::ConfigSet => "config-set",
+        }
+    }
+}
+
+/// Metadata stored as `manifest.json` in every backup directory.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupManifest {
+    pub reason: BackupReason,
+    pub created: DateTime<Utc>,
+    /// Device the backed-up files were taken from.
+    pub source_device: String,
+    /// Device a pull came from, for pre-pull backups.
+    pub remote_device: Option<String>,
+    pub files: Vec<BackupFile>,
+    pub app_version: String,
+    /// SQLite `user_version` of the GG database, if set.
+    pub gg_schema_version: Option<u32>,
+    /// Pinned backups are never pruned.
+    #[serde(default)]
+    pub pinned: bool,
+    /// Free-form user annotation.
+    #[serde(default)]
+    pub note: Option<String>,
+    /// Whether the backup has been uploaded to the sync provider.
+    #[serde(default)]
+    pub replicated: bool,
+    /// Name of the config set this backup holds, for the newest save of a set.
+    #[serde(default)]
+    pub config_set: Option<String>,
+}
+
+/// A single file recorded in a backup manifest.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupFile {
+    pub name: String,
+    /// Original (uncompressed) size in bytes.
+    pub size: u64,
+    /// Size on disk after compression; `None` for uncompressed backups.
+    #[serde(default)]
+    pub compressed_size: Option<u64>,
+    pub sha256: String,
 }
 
 impl BackupManager {
     pub fn new(backup_dir: PathBuf, max_backups: usize) -> Self {
-        Self { backup_dir, max_backups }
+        Self {
+            backup_dir,
+            max_backups,
+            retention: RetentionPolicy::default(),
+            max_scheduled: max_backups,
+            scheduled_retention: RetentionPolicy::default(),
+            budget: None,
+            space_margin: DEFAULT_SPACE_MARGIN,
+            device_name: "unknown".to_string(),
+            store_lock: Mutex::new(()),
+        }
+    }
+
+    /// Set the device name recorded in backup manifests.
+    pub fn with_device_name(mut self, device_name: String) -> Self {
+        self.device_name = device_name;
+        self
+    }
+
+    /// Set the retention policy applied when pruning.
+    pub fn with_retention(mut self, retention: RetentionPolicy) -> Self {
+        self.retention = retention;
+        self
+    }
+
+    /// Set the limits applied to scheduled backups when pruning.
+    pub fn with_scheduled_retention(
+        mut self,
+        max_backups: usize,
+        retention: RetentionPolicy,
+    ) -> Self {
+        self.max_scheduled = max_backups;
+        self.scheduled_retention = retention;
+        self
+    }
+
+    /// Limit the total size of the backup directory. `None` means unlimited.
+    pub fn with_budget(mut self, budget: Option<u64>) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    /// Override the free-space margin used by the disk-space preflight.
+    pub fn with_space_margin(mut self, margin: u64) -> Self {
+        self.space_margin = margin;
+        self
+    }
+
+    /// Check that a backup of `source_dir` fits on the backup volume.
+    /// When space is tight, unpinned backups are pruned in the order the budget
+    /// prunes them (the newest is always kept), but only if that frees enough
+    /// space; otherwise nothing is deleted and the shortage is reported.
+    pub fn preflight(&self, source_dir: &Path) -> std::io::Result<SafetyCheck> {
+        let required = db_files_size(source_dir)?;
+        let check = check_disk_space(&self.backup_dir, required, self.space_margin);
+        let SafetyCheck::InsufficientSpace {
+            required: needed,
+            available,
+        } = check
+        else {
+            return Ok(check);
+        };
+        let backups = self.list_backups()?;
+        let (victims, freed) = self.plan_pruning(&backups, needed - available)?;
+        if freed < needed - available {
+            log::warn!(
+                "Low disk space: pruning would free only {} of {} bytes; keeping all backups",
+                freed,
+                needed - available
+            );
+            return Ok(check);
+        }
+        let _store = self.lock_store();
+        for old in victims {
+            log::warn!("Low disk space: pruning backup {}", old.name);
+            fs::remove_dir_all(&old.path)?;
+        }
+        self.collect_garbage()?;
+        Ok(check_disk_space(
+            &self.backup_dir,
+            required,
+            self.space_margin,
+        ))
     }
 
     /// Create a timestamped backup of the given files.
+    /// `remote_device` records where a pull came from.
     /// Returns the path to the backup directory.
-    pub fn create_backup(&self, source_dir: &Path, label: &str) -> std::io::Result<PathBuf> {
-        let timestamp = Utc::now().format("%Y-%m-%dT%H-%M-%S");
-        let backup_name = format!("{}-{}", label, timestamp);
+    pub fn create_backup(
+        &self,
+        source_dir: &Path,
+        reason: BackupReason,
+        remote_device: Option<&str>,
+    ) -> std::io::Result<PathBuf> {
+        if let SafetyCheck::InsufficientSpace {
+            required,
+            available,
+        } = self.preflight(source_dir)?
+        {
+            return Err(insufficient_space_error(required, available));
+        }
+
+        let mut files = Vec::new();
+        for name in DB_FILES {
+            match fs::read(source_dir.join(name)) {
+                Ok(data) => files.push((*name, data)),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        self.write_backup(&files, reason, &self.device_name, remote_device)
+    }
+
+    /// Like `create_backup`, but returns `None` without writing anything when the
+    /// files are identical to those of the newest backup.
+    pub fn create_backup_if_changed(
+        &self,
+        source_dir: &Path,
+        reason: BackupReason,
+    ) -> std::io::Result<Option<PathBuf>> {
+        let latest = self.list_backups()?.into_iter().find_map(|b| b.manifest);
+        if let Some(latest) = latest {
+            if matches_manifest(source_dir, &latest)? {
+                return Ok(None);
+            }
+        }
+        self.create_backup(source_dir, reason, None).map(Some)
+    }
+
+    /// Store an in-memory snapshot (e.g. an imported archive) as a backup.
+    /// The snapshot's device is recorded as the source device.
+    pub fn create_backup_from_snapshot(
+        &self,
+        snapshot: &ConfigSnapshot,
+        reason: BackupReason,
+    ) -> std::io::Result<PathBuf> {
+        let check = check_disk_space(&self.backup_dir, snapshot.total_size(), self.space_margin);
+        if let SafetyCheck::InsufficientSpace {
+            required,
+            available,
+        } = check
+        {
+            return Err(insufficient_space_error(required, available));
+        }
+
+        let mut files = vec![("database.db", snapshot.db.clone())];
+        if let Some(shm) = &snapshot.db_shm {
+            files.push(("database.db-shm", shm.clone()));
+        }
+        if let Some(wal) = &snapshot.db_wal {
+            files.push(("database.db-wal", wal.clone()));
+        }
+        self.write_backup(&files, reason, &snapshot.meta.device_name, None)
+    }
+
+    fn write_backup(
+        &self,
+        contents: &[(&str, Vec<u8>)],
+        reason: BackupReason,
+        source_device: &str,
+        remote_device: Option<&str>,
+    ) -> std::io::Result<PathBuf> {
+        let created = Utc::now();
+        let backup_name = format!("{}-{}", sanitize_label(reason.label()), next_backup_id());
         let backup_path = self.backup_dir.join(&backup_name);
-        fs::create_dir_all(&backup_path)?;
+        fs::create_dir_all(&self.backup_dir)?;
+        let _store = self.lock_store();
+        fs::create_dir(&backup_path)?;
 
-        // Copy all database.db* files
-        for entry in fs::read_dir(source_dir)? {
-            let entry = entry?;
-            let name = entry.file_name();
-            let name_str = name.to_string_lossy();
-            if name_str.starts_with("database.db") {
-                fs::copy(entry.path(), backup_path.join(&name))?;
+        let written = self
+            .store_files(contents)
+            .and_then(|(files, gg_schema_version)| {
+                // The manifest is written last, so its presence marks a complete backup
+                let manifest = BackupManifest {
+                    reason,
+                    created,
+                    source_device: source_device.to_string(),
+                    remote_device: remote_device.map(str::to_string),
+                    files,
+                    app_version: env!("CARGO_PKG_VERSION").to_string(),
+                    gg_schema_version,
+                    pinned: false,
+                    note: None,
+                    replicated: false,
+                    config_set: None,
+                };
+                write_manifest(&backup_path, &manifest)
+            });
+        if let Err(e) = written {
+            // A directory without a manifest would be listed as the newest backup
+            if let Err(cleanup) = fs::remove_dir_all(&backup_path) {
+                log::warn!(
+                    "Could not remove incomplete backup {}: {}",
+                    backup_name,
+                    cleanup
+                );
             }
+            return Err(e);
         }
 
         self.prune_old_backups()?;
         Ok(backup_path)
     }
 
-    /// List all backups, newest first.
+    /// Store the database files as blobs. Returns their manifest entries and
+    /// the GG schema version of the main database.
+    fn store_files(
+        &self,
+        contents: &[(&str, Vec<u8>)],
+    ) -> std::io::Result<(Vec<BackupFile>, Option<u32>)> {
+        let mut files = Vec::new();
+        let mut gg_schema_version = None;
+        for (name, data) in contents {
+            if *name == "database.db" {
+                gg_schema_version = sqlite_user_version(data);
+            }
+            let (sha256, compressed_size) = self.store_blob(data)?;
+            files.push(BackupFile {
+                name: name.to_string(),
+                size: data.len() as u64,
+                compressed_size: Some(compressed_size),
+                sha256,
+            });
+        }
+        Ok((files, gg_schema_version))
+    }
+
+    /// List all backups, newest first by creation time.
     pub fn list_backups(&self) -> std::io::Result<Vec<BackupEntry>> {
         let mut entries = Vec::new();
         if !self.backup_dir.exists() {
             return Ok(entries);
         }
+        let mut verifications = self.read_verifications();
         for entry in fs::read_dir(&self.backup_dir)? {
             let entry = entry?;
-            if entry.file_type()?.is_dir() {
+            if entry.file_type()?.is_dir() && entry.file_name() != BLOB_DIR {
                 let name = entry.file_name().to_string_lossy().to_string();
-                let modified = entry.metadata()?.modified()?;
+                let manifest = read_manifest(&entry.path());
+                let created = match manifest.as_ref().map(|m| m.created) {
+                    Some(created) => created,
+                    None => match created_from_name(&name) {
+                        Some(created) => created,
+                        None => DateTime::<Utc>::from(entry.metadata()?.modified()?),
+                    },
+                };
                 entries.push(BackupEntry {
+                    verification: verifications.remove(&name),
                     name,
+                    manifest,
                     path: entry.path(),
-                    created: modified,
+                    created,
+                    size: 0,
+                    exclusive_size: 0,
                 });
             }
         }
-        entries.sort_by(|a, b| b.created.cmp(&a.created));
+        entries.sort_by(|a, b| (b.created, &b.name).cmp(&(a.created, &a.name)));
         Ok(entries)
     }
 
-    /// Restore a backup to the target directory.
-    pub fn restore_backup(&self, backup_path: &Path, target_dir: &Path) -> std::io::Result<()> {
-        for entry in fs::read_dir(backup_path)? {
-            let entry = entry?;
-            let name = entry.file_name();
-            fs::copy(entry.path(), target_dir.join(&name))?;
+    /// All backups, newest first, with each backup's disk usage and the total
+    /// size of the backup directory.
+    pub fn list_with_usage(&self) -> std::io::Result<BackupList> {
+        let mut backups = self.list_backups()?;
+        self.fill_disk_usage(&mut backups)?;
+        let total_size = if self.backup_dir.exists() {
+            dir_size(&self.backup_dir)?
+        } else {
+            0
+        };
+        Ok(BackupList {
+            backups,
+            total_size,
+        })
+    }
+
+    /// Fill in each entry's on-disk size. A blob shared between backups counts
+    /// towards the `size` of each, but only towards the `exclusive_size` of a
+    /// backup that is its sole user.
+    fn fill_disk_usage(&self, entries: &mut [BackupEntry]) -> std::io::Result<()> {
+        let hashes: Vec<HashSet<String>> = entries
+            .iter()
+            .map(|e| {
+                e.manifest
+                    .iter()
+                    .flat_map(|m| &m.files)
+                    .map(|f| f.sha256.clone())
+                    .collect()
+            })
+            .collect();
+        let mut refs: HashMap<&str, usize> = HashMap::new();
+        for hash in hashes.iter().flatten() {
+            *refs.entry(hash.as_str()).or_insert(0) += 1;
+        }
+        for (entry, hashes) in entries.iter_mut().zip(&hashes) {
+            let inline = dir_size(&entry.path)?;
+            entry.size = inline;
+            entry.exclusive_size = inline;
+            for hash in hashes {
+                let blob = self.blob_size(hash)?;
+                entry.size += blob;
+                if refs[hash.as_str()] == 1 {
+                    entry.exclusive_size += blob;
+                }
+            }
         }
         Ok(())
     }
 
+    /// Look up a backup by name. Only names of existing backups are accepted,
+    /// so user input can never address a path outside the backup directory.
+    pub fn find_backup(&self, name: &str) -> std::io::Result<BackupEntry> {
+        self.list_backups()?
+            .into_iter()
+            .find(|b| b.name == name)
+            .ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    format!("Backup '{}' not found", name),
+                )
+            })
+    }
+
+    /// Pin or unpin a backup. Pinned backups are never pruned.
+    pub fn set_pinned(&self, name: &str, pinned: bool) -> std::io::Result<()> {
+        self.update_manifest(name, |m| m.pinned = pinned)
+    }
+
+    /// Attach a note to a backup, or clear it with `None`.
+    pub fn set_note(&self, name: &str, note: Option<String>) -> std::io::Result<()> {
+        let note = note.filter(|n| !n.trim().is_empty());
+        self.update_manifest(name, |m| m.note = note)
+    }
+
+    /// Record that a backup has been uploaded to the sync provider.
+    pub fn set_replicated(&self, name: &str) -> std::io::Result<()> {
+        self.update_manifest(name, |m| m.replicated = true)
+    }
+
+    /// Save the config in `source_dir` as the named config set. The set's
+    /// previous save is released to normal retention; the new one is pinned.
+    pub fn save_config_set(&self, source_dir: &Path, name: &str) -> std::io::Result<PathBuf> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "Config set name is empty",
+            ));
+        }
+        let previous = self.find_config_set(name).ok();
+        let path = self.create_backup(source_dir, BackupReason::ConfigSet, None)?;
+        let backup_name = path.file_name().unwrap_or_default().to_string_lossy();
+        self.update_manifest(&backup_name, |m| {
+            m.config_set = Some(name.to_string());
+            m.pinned = true;
+        })?;
+        if let Some(previous) = previous {
+            self.release_config_set(&previous.name)?;
+        }
+        Ok(path)
+    }
+
+    /// The newest save of each config set, ordered by name.
+    pub fn config_sets(&self) -> std::io::Result<Vec<BackupEntry>> {
+        let mut sets: Vec<BackupEntry> = self
+            .list_backups()?
+            .into_iter()
+            .filter(|b| b.config_set().is_some())
+            .collect();
+        sets.sort_by(|a, b| a.config_set().cmp(&b.config_set()));
+        Ok(sets)
+    }
+
+    /// The backup holding a config set. Names match case-insensitively.
+    pub fn find_config_set(&self, name: &str) -> std::io::Result<BackupEntry> {
+        self.config_sets()?
+            .into_iter()
+            .find(|b| {
+                b.config_set()
+                    .is_some_and(|set| set.eq_ignore_ascii_case(name.trim()))
+            })
+            .ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    format!("Config set '{}' not found", name),
+                )
+            })
+    }
+
+    /// Remove a config set. Its backup is kept but falls under normal retention.
+    pub fn delete_config_set(&self, name: &str) -> std::io::Result<()> {
+        let entry = self.find_config_set(name)?;
+        self.release_config_set(&entry.name)
+    }
+
+    fn release_config_set(&self, backup_name: &str) -> std::io::Result<()> {
+        self.update_manifest(backup_name, |m| {
+            m.config_set = None;
+            m.pinned = false;
+        })
+    }
+
+    /// Backups of the selected classes that haven't been replicated yet, newest
+    /// first. "Daily" is the newest backup of each completed (UTC) day, so each
+    /// day is uploaded once.
+    pub fn replication_candidates(
+        &self,
+        pinned: bool,
+        daily: bool,
+        now: DateTime<Utc>,
+    ) -> std::io::Result<Vec<BackupEntry>> {
+        let today = now.date_naive();
+        let mut days = HashSet::new();
+        let mut candidates = Vec::new();
+        for backup in self.list_backups()? {
+            let date = backup.created.date_naive();
+            let newest_of_day = date < today && days.insert(date);
+            let Some(manifest) = &backup.manifest else {
+                continue;
+            };
+            if !manifest.replicated && ((pinned && manifest.pinned) || (daily && newest_of_day)) {
+                candidates.push(backup);
+            }
+        }
+        Ok(candidates)
+    }
+
+    fn update_manifest(
+        &self,
+        name: &str,
+        update: impl FnOnce(&mut BackupManifest),
+    ) -> std::io::Result<()> {
+        let entry = self.find_backup(name)?;
+        let mut manifest = entry.manifest.ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                format!(
+                    "Backup '{}' predates manifests and can't be annotated",
+                    name
+                ),
+            )
+        })?;
+        update(&mut manifest);
+        write_manifest(&entry.path, &manifest)
+    }
+
+    /// Read one database file from a backup, or `None` if the backup doesn't have it.
+    /// Resolves (and decompresses) blobs for store-backed backups and inline files
+    /// for older ones.
+    pub fn read_file(&self, backup_path: &Path, name: &str) -> std::io::Result<Option<Vec<u8>>> {
+        let listed =
+            read_manifest(backup_path).and_then(|m| m.files.into_iter().find(|f| f.name == name));
+        if let Some(file) = &listed {
+            if let Some(data) = self.read_blob(&file.sha256)? {
+                return Ok(Some(data));
+            }
+        }
+        match fs::read(backup_path.join(name)) {
+            Ok(data) => Ok(Some(data)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound && listed.is_some() => {
+                Err(std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    format!("Backup is missing the data for {}", name),
+                ))
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Load a backup as a snapshot, verifying every file against the manifest.
+    /// Returns `InvalidData` if the backup is incomplete or a hash doesn't match.
+    pub fn load_snapshot(&self, entry: &BackupEntry) -> std::io::Result<ConfigSnapshot> {
+        let mut files = HashMap::new();
+        for name in DB_FILES {
+            if let Some(data) = self.read_file(&entry.path, name)? {
+                files.insert(*name, data);
+            }
+        }
+        if let Some(manifest) = &entry.manifest {
+            for file in &manifest.files {
+                let intact = files
+                    .get(file.name.as_str())
+                    .is_some_and(|data| sha256_hex(data) == file.sha256);
+                if !intact {
+                    return Err(corrupt_backup_error(&entry.name, &file.name));
+                }
+            }
+        }
+        let db = files
+            .remove("database.db")
+            .ok_or_else(|| corrupt_backup_error(&entry.name, "database.db"))?;
+        Ok(ConfigSnapshot {
+            db,
+            db_shm: files.remove("database.db-shm"),
+            db_wal: files.remove("database.db-wal"),
+            meta: SyncMeta {
+                last_modified: entry.manifest.as_ref().map_or(entry.created, |m| m.created),
+                device_name: entry
+                    .manifest
+                    .as_ref()
+                    .map_or_else(|| "unknown".to_string(), |m| m.source_device.clone()),
+            },
+        })
+    }
+
+    /// Remember the last restore so it can be undone.
+    pub fn record_restore(&self, restored: &str, undo_backup: Option<&str>) -> std::io::Result<()> {
+        let record = RestoreRecord {
+            restored: restored.to_string(),
+            undo_backup: undo_backup.map(str::to_string),
+            at: Utc::now(),
+        };
+        let json = serde_json::to_string_pretty(&record)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        fs::create_dir_all(&self.backup_dir)?;
+        fs::write(self.backup_dir.join(LAST_RESTORE_FILE), json)
+    }
+
+    /// The most recent restore, if any.
+    pub fn last_restore(&self) -> Option<RestoreRecord> {
+        let json = fs::read_to_string(self.backup_dir.join(LAST_RESTORE_FILE)).ok()?;
+        serde_json::from_str(&json).ok()
+    }
+
+    /// Check a backup end to end: every blob is present and matches its recorded
+    /// hash, and the database passes SQLite's integrity check.
+    pub fn verify_backup(&self, entry: &BackupEntry) -> BackupHealth {
+        let snapshot = match self.load_snapshot(entry) {
+            Ok(snapshot) => snapshot,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return BackupHealth::Incomplete(e.to_string())
+            }
+            Err(e) => return BackupHealth::Corrupt(e.to_string()),
+        };
+        match check_integrity(&snapshot) {
+            Ok(()) => BackupHealth::Ok,
+            Err(problem) => BackupHealth::Corrupt(problem),
+        }
+    }
+
+    /// Verify every backup and record the results, which `list_backups` reports.
+    pub fn verify_backups(&self) -> std::io::Result<VerifySummary> {
+        let mut summary = VerifySummary::default();
+        let mut results = HashMap::new();
+        for entry in self.list_backups()? {
+            let health = self.verify_backup(&entry);
+            summary.checked += 1;
+            match &health {
+                BackupHealth::Ok => summary.ok += 1,
+                BackupHealth::Incomplete(why) => {
+                    log::warn!("Backup {} is incomplete: {}", entry.name, why);
+                    summary.incomplete.push(entry.name.clone());
+                }
+                BackupHealth::Corrupt(why) => {
+                    log::error!("Backup {} is corrupt: {}", entry.name, why);
+                    summary.corrupt.push(entry.name.clone());
+                }
+            }
+            results.insert(
+                entry.name,
+                Verification {
+                    checked: Utc::now(),
+                    health,
+                },
+            );
+        }
+        let json = serde_json::to_string_pretty(&results)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        fs::create_dir_all(&self.backup_dir)?;
+        fs::write(self.backup_dir.join(VERIFICATION_FILE), json)?;
+        Ok(summary)
+    }
+
+    /// Results of the last verification run, by backup name.
+    fn read_verifications(&self) -> HashMap<String, Verification> {
+        fs::read_to_string(self.backup_dir.join(VERIFICATION_FILE))
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
     fn prune_old_backups(&self) -> std::io::Result<()> {
         let backups = self.list_backups()?;
-        if backups.len() > self.max_backups {
-            for old in &backups[self.max_backups..] {
+        let now = Utc::now();
+        // Scheduled and other backups are retained independently
+        let mut keep = HashSet::new();
+        let classes = [
+            (true, &self.scheduled_retention, self.max_scheduled),
+            (false, &self.retention, self.max_backups),
+        ];
+        for (scheduled, policy, max) in classes {
+            let indices: Vec<usize> = (0..backups.len())
+                .filter(|&i| backups[i].is_scheduled() == scheduled)
+                .collect();
+            let class: Vec<BackupEntry> = indices.iter().map(|&i| backups[i].clone()).collect();
+            keep.extend(
+                retained(&class, policy, max, now)
+                    .into_iter()
+                    .map(|j| indices[j]),
+            );
+        }
+        let mut removed = false;
+        for (i, old) in backups.iter().enumerate() {
+            if !keep.contains(&i) {
                 fs::remove_dir_all(&old.path)?;
+                removed = true;
             }
         }
+        if removed {
+            self.collect_garbage()?;
+        }
+        self.enforce_budget()
+    }
+
+    /// Remove backups until the backup directory fits the byte budget.
+    /// Scheduled backups go first, then the rest, oldest first within each;
+    /// pinned backups and the newest backup are never removed.
+    fn enforce_budget(&self) -> std::io::Result<()> {
+        let Some(budget) = self.budget else {
+            return Ok(());
+        };
+        let mut total = dir_size(&self.backup_dir)?;
+        if total <= budget {
+            return Ok(());
+        }
+        let backups = self.list_backups()?;
+        let (victims, freed) = self.plan_pruning(&backups, total - budget)?;
+        for old in &victims {
+            log::info!(
+                "Backups exceed the {} byte budget: pruning {}",
+                budget,
+                old.name
+            );
+            fs::remove_dir_all(&old.path)?;
+        }
+        if !victims.is_empty() {
+            self.collect_garbage()?;
+        }
+        total -= freed.min(total);
+        if total > budget {
+            log::warn!(
+                "Backups still use {} bytes, over the {} byte budget",
+                total,
+                budget
+            );
+        }
         Ok(())
     }
+
+    /// Backups to remove, in pruning order, to free at least `target` bytes, and
+    /// the bytes removing them frees. A shared blob only counts once every
+    /// backup using it is removed. If the target can't be reached, every
+    /// candidate is returned with the total it would free.
+    fn plan_pruning<'a>(
+        &self,
+        backups: &'a [BackupEntry],
+        target: u64,
+    ) -> std::io::Result<(Vec<&'a BackupEntry>, u64)> {
+        let mut refs: HashMap<&str, usize> = HashMap::new();
+        for backup in backups {
+            for hash in backup.blob_hashes() {
+                *refs.entry(hash).or_insert(0) += 1;
+            }
+        }
+        let mut victims = Vec::new();
+        let mut freed = 0;
+        for old in prune_candidates(backups) {
+            if freed >= target {
+                break;
+            }
+            freed += dir_size(&old.path)?;
+            for hash in old.blob_hashes() {
+                let count = refs.get_mut(hash).expect("counted above");
+                *count -= 1;
+                if *count == 0 {
+                    freed += self.blob_size(hash)?;
+                }
+            }
+            victims.push(old);
+        }
+        Ok((victims, freed))
+    }
+
+    /// Path of a compressed blob.
+    fn blob_path(&self, hash: &str) -> PathBuf {
+        self.backup_dir
+            .join(BLOB_DIR)
+            .join(&hash[..2])
+            .join(format!("{}.zst", hash))
+    }
+
+    /// Path of an uncompressed blob written before compression was introduced.
+    fn raw_blob_path(&self, hash: &str) -> PathBuf {
+        self.backup_dir.join(BLOB_DIR).join(&hash[..2]).join(hash)
+    }
+
+    /// Size on disk of a blob, or 0 if it isn't stored.
+    fn blob_size(&self, hash: &str) -> std::io::Result<u64> {
+        for path in [self.blob_path(hash), self.raw_blob_path(hash)] {
+            match fs::metadata(path) {
+                Ok(meta) => return Ok(meta.len()),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(0)
+    }
+
+    /// Read a blob by hash, decompressing it if needed. `None` if it isn't stored.
+    fn read_blob(&self, hash: &str) -> std::io::Result<Option<Vec<u8>>> {
+        match fs::read(self.blob_path(hash)) {
+            Ok(compressed) => return zstd::decode_all(compressed.as_slice()).map(Some),
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e),
+            Err(_) => {}
+        }
+        match fs::read(self.raw_blob_path(hash)) {
+            Ok(data) => Ok(Some(data)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Store `data` compressed in the blob store if it isn't there already.
+    /// Returns its hash and the compressed size on disk.
+    fn store_blob(&self, data: &[u8]) -> std::io::Result<(String, u64)> {
+        let hash = sha256_hex(data);
+        let path = self.blob_path(&hash);
+        if let Ok(meta) = fs::metadata(&path) {
+            return Ok((hash, meta.len()));
+        }
+        let compressed = zstd::encode_all(data, ZSTD_LEVEL)?;
+        let dir = path.parent().expect("blob path has a parent");
+        fs::create_dir_all(dir)?;
+        let tmp = dir.join(format!("{}.tmp", hash));
+        fs::write(&tmp, &compressed)?;
+        fs::rename(&tmp, &path)?;
+        Ok((hash, compressed.len() as u64))
+    }
+
+    /// Number of backups referencing each blob.
+    fn blob_refcounts(&self) -> std::io::Result<HashMap<String, usize>> {
+        let mut refs = HashMap::new();
+        for backup in self.list_backups()? {
+            for file in backup.manifest.iter().flat_map(|m| &m.files) {
+                *refs.entry(file.sha256.clone()).or_insert(0) += 1;
+            }
+        }
+        Ok(refs)
+    }
+
+    fn lock_store(&self) -> std::sync::MutexGuard<'_, ()> {
+        self.store_lock.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Delete blobs no backup references any more. Returns the number removed.
+    /// Callers must hold the store lock (see `lock_store`).
+    fn collect_garbage(&self) -> std::io::Result<usize> {
+        let blob_root = self.backup_dir.join(BLOB_DIR);
+        if !blob_root.exists() {
+            return Ok(0);
+        }
+        let refs = self.blob_refcounts()?;
+        let mut removed = 0;
+        for shard in fs::read_dir(&blob_root)? {
+            let shard = shard?;
+            if !shard.file_type()?.is_dir() {
+                continue;
+            }
+            for blob in fs::read_dir(shard.path())? {
+                let blob = blob?;
+                let file_name = blob.file_name().to_string_lossy().to_string();
+                // `<hash>.zst`, a raw `<hash>`, or a leftover `<hash>.tmp`
+                let hash = file_name.split('.').next().unwrap_or_default();
+                let stale_tmp = file_name.ends_with(".tmp");
+                if stale_tmp || refs.get(hash).copied().unwrap_or(0) == 0 {
+                    fs::remove_file(blob.path())?;
+                    removed += 1;
+                }
+            }
+        }
+        Ok(removed)
+    }
+}
+
+/// Indices of the backups a retention policy keeps. `backups` must be newest first.
+///
+/// Kept: pinned backups, the newest backup, up to `max_recent` backups from the
+/// last `keep_all_hours`, and the newest backup of each day and of each week
+/// within the daily and weekly windows.
+fn retained(
+    backups: &[BackupEntry],
+    policy: &RetentionPolicy,
+    max_recent: usize,
+    now: DateTime<Utc>,
+) -> HashSet<usize> {
+    let mut keep = HashSet::new();
+    let mut recent = 0;
+    let mut days = HashSet::new();
+    let mut weeks = HashSet::new();
+    for (i, backup) in backups.iter().enumerate() {
+        let age = now - backup.created;
+        if i == 0 || backup.is_pinned() {
+            keep.insert(i);
+        }
+        if age < Duration::hours(policy.keep_all_hours as i64) && recent < max_recent {
+            recent += 1;
+            keep.insert(i);
+        }
+        let date = backup.created.date_naive();
+        if age < Duration::days(policy.daily_days as i64) && days.insert(date) {
+            keep.insert(i);
+        }
+        let week = date.iso_week();
+        if age < Duration::weeks(policy.weekly_weeks as i64)
+            && weeks.insert((week.year(), week.week()))
+        {
+            keep.insert(i);
+        }
+    }
+    keep
+}
+
+/// Backups that may be pruned for space, in the order to prune them: scheduled
+/// backups first, then the rest, oldest first within each. Pinned backups and
+/// the newest backup are never candidates. `backups` must be newest first.
+fn prune_candidates(backups: &[BackupEntry]) -> Vec<&BackupEntry> {
+    let mut candidates: Vec<&BackupEntry> =
+        backups.iter().skip(1).filter(|b| !b.is_pinned()).collect();
+    candidates.sort_by_key(|b| (!b.is_scheduled(), b.created));
+    candidates
+}
+
+/// Total size of the database files in `dir`.
+fn db_files_size(dir: &Path) -> std::io::Result<u64> {
+    let mut total = 0;
+    for name in DB_FILES {
+        match fs::metadata(dir.join(name)) {
+            Ok(meta) => total += meta.len(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(total)
+}
+
+/// A new unique backup ID.
+fn next_backup_id() -> Ulid {
+    let mut generator = ID_GENERATOR.lock().unwrap_or_else(|e| e.into_inner());
+    generator
+        .get_or_insert_with(ulid::Generator::new)
+        .generate()
+        .unwrap_or_else(|_| Ulid::new())
+}
+
+/// Make a label safe for a directory name: lowercase ASCII letters and digits
+/// separated by single dashes, at most `MAX_LABEL_LEN` characters.
+fn sanitize_label(label: &str) -> String {
+    let mut clean = String::new();
+    for c in label.chars() {
+        if c.is_ascii_alphanumeric() {
+            clean.push(c.to_ascii_lowercase());
+        } else if !clean.is_empty() && !clean.ends_with('-') {
+            clean.push('-');
+        }
+    }
+    clean.truncate(MAX_LABEL_LEN);
+    let clean = clean.trim_end_matches('-');
+    if clean.is_empty() {
+        "backup".to_string()
+    } else {
+        clean.to_string()
+    }
+}
+
+/// Creation time encoded in a backup directory name: the timestamp of a ULID
+/// name, or the time of a legacy `<label>-<%Y-%m-%dT%H-%M-%S>` name.
+fn created_from_name(name: &str) -> Option<DateTime<Utc>> {
+    let (_, suffix) = name.rsplit_once('-')?;
+    if let Ok(id) = Ulid::from_string(suffix) {
+        return Some(DateTime::<Utc>::from(id.datetime()));
+    }
+    let stamp = name.get(name.len().checked_sub(19)?..)?;
+    chrono::NaiveDateTime::parse_from_str(stamp, LEGACY_NAME_FORMAT)
+        .ok()
+        .map(|t| t.and_utc())
+}
+
+/// Total size of the files under `dir`.
+fn dir_size(dir: &Path) -> std::io::Result<u64> {
+    let mut total = 0;
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            total += dir_size(&entry.path())?;
+        } else if file_type.is_file() {
+            total += entry.metadata()?.len();
+        }
+    }
+    Ok(total)
+}
+
+/// Whether the database files in `dir` are exactly those recorded in `manifest`.
+fn matches_manifest(dir: &Path, manifest: &BackupManifest) -> std::io::Result<bool> {
+    let mut current = Vec::new();
+    for name in DB_FILES {
+        match fs::read(dir.join(name)) {
+            Ok(data) => current.push((name.to_string(), sha256_hex(&data))),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+    }
+    let mut recorded: Vec<_> = manifest
+        .files
+        .iter()
+        .map(|f| (f.name.clone(), f.sha256.clone()))
+        .collect();
+    current.sort();
+    recorded.sort();
+    Ok(current == recorded)
+}
+
+/// Lowercase hex SHA-256 of `data`.
+pub fn sha256_hex(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
+
+fn write_manifest(backup_path: &Path, manifest: &BackupManifest) -> std::io::Result<()> {
+    let json = serde_json::to_string_pretty(manifest)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    fs::write(backup_path.join(MANIFEST_FILE), json)
 }
 
-#[derive(Debug, Clone)]
+/// Read a backup's manifest. Backups made before manifests existed have none.
+fn read_manifest(backup_path: &Path) -> Option<BackupManifest> {
+    let json = fs::read_to_string(backup_path.join(MANIFEST_FILE)).ok()?;
+    serde_json::from_str(&json).ok()
+}
+
+fn corrupt_backup_error(backup: &str, file: &str) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        format!("Backup '{}' is corrupt or incomplete: {}", backup, file),
+    )
+}
+
+fn insufficient_space_error(required: u64, available: u64) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::StorageFull,
+        format!(
+            "Not enough disk space: {} bytes required, {} available",
+            required, available
+        ),
+    )
+}
+
+/// The last restore and the pre-restore backup that undoes it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RestoreRecord {
+    pub restored: String,
+    /// `None` if there was no local config to back up before the restore.
+    pub undo_backup: Option<String>,
+    pub at: DateTime<Utc>,
+}
+
+/// Outcome of verifying a backup.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "status", content = "detail", rename_all = "kebab-case")]
+pub enum BackupHealth {
+    Ok,
+    /// Data referenced by the manifest is missing.
+    Incomplete(String),
+    /// Data is present but fails a hash or SQLite integrity check.
+    Corrupt(String),
+}
+
+/// When a backup was last verified, and the result.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Verification {
+    pub checked: DateTime<Utc>,
+    pub health: BackupHealth,
+}
+
+/// Totals from a `verify_backups` run.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct VerifySummary {
+    pub checked: usize,
+    pub ok: usize,
+    pub corrupt: Vec<String>,
+    pub incomplete: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub struct BackupEntry {
     pub name: String,
     pub path: PathBuf,
-    pub created: std::time::SystemTime,
+    pub created: DateTime<Utc>,
+    /// `None` for backups created before manifests were introduced.
+    pub manifest: Option<BackupManifest>,
+    /// `None` until the backup has been verified.
+    pub verification: Option<Verification>,
+    /// Bytes on disk this backup uses, including blobs shared with others.
+    /// Only filled in by `list_with_usage`; 0 otherwise.
+    pub size: u64,
+    /// Bytes that would be freed by deleting this backup.
+    pub exclusive_size: u64,
+}
+
+/// Backups with the disk usage of the whole backup directory.
+#[derive(Debug, Clone, Serialize)]
+pub struct BackupList {
+    pub backups: Vec<BackupEntry>,
+    pub total_size: u64,
+}
+
+impl BackupEntry {
+    pub fn is_pinned(&self) -> bool {
+        self.manifest.as_ref().is_some_and(|m| m.pinned)
+    }
+
+    pub fn is_scheduled(&self) -> bool {
+        self.manifest
+            .as_ref()
+            .is_some_and(|m| m.reason == BackupReason::Scheduled)
+    }
+
+    /// Distinct blobs the backup references.
+    fn blob_hashes(&self) -> HashSet<&str> {
+        self.manifest
+            .iter()
+            .flat_map(|m| &m.files)
+            .map(|f| f.sha256.as_str())
+            .collect()
+    }
+
+    pub fn config_set(&self) -> Option<&str> {
+        self.manifest.as_ref()?.config_set.as_deref()
+    }
 }
 
 #[cfg(test)]
@@ -109,12 +1192,208 @@ mod tests {
         let backup_dir = tmp.path().join("backups");
         let mgr = BackupManager::new(backup_dir, 20);
 
-        let backup_path = mgr.create_backup(&src, "sync").unwrap();
+        let backup_path = mgr.create_backup(&src, BackupReason::Manual, None).unwrap();
+
+        let read = |name| mgr.read_file(&backup_path, name).unwrap();
+        assert_eq!(read("database.db").unwrap(), b"main-db-content");
+        assert_eq!(read("database.db-shm").unwrap(), b"shm-content");
+        assert_eq!(read("database.db-wal").unwrap(), b"wal-content");
+        assert!(read("unrelated.txt").is_none());
+    }
+
+    fn blob_count(backup_dir: &Path) -> usize {
+        let root = backup_dir.join(BLOB_DIR);
+        if !root.exists() {
+            return 0;
+        }
+        fs::read_dir(root)
+            .unwrap()
+            .map(|shard| fs::read_dir(shard.unwrap().path()).unwrap().count())
+            .sum()
+    }
+
+    #[test]
+    fn test_identical_backups_share_blobs() {
+        let tmp = TempDir::new().unwrap();
+        let src = setup_source_dir(&tmp);
+        let backup_dir = tmp.path().join("backups");
+        let mgr = BackupManager::new(backup_dir.clone(), 20);
+
+        mgr.create_backup(&src, BackupReason::PrePush, None)
+            .unwrap();
+        mgr.create_backup(&src, BackupReason::PrePush, None)
+            .unwrap();
+
+        assert_eq!(mgr.list_backups().unwrap().len(), 2);
+        assert_eq!(blob_count(&backup_dir), 3);
+    }
+
+    #[test]
+    fn test_config_set_keeps_only_newest_save() {
+        let tmp = TempDir::new().unwrap();
+        let src = setup_source_dir(&tmp);
+        let mgr = BackupManager::new(tmp.path().join("backups"), 20);
+
+        let first = mgr.save_config_set(&src, "alice").unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        mgr.save_config_set(&src, "bob").unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        let second = mgr.save_config_set(&src, "Alice").unwrap();
+
+        let sets: Vec<String> = mgr
+            .config_sets()
+            .unwrap()
+            .iter()
+            .map(|b| b.config_set().unwrap().to_string())
+            .collect();
+        assert_eq!(sets, ["Alice", "bob"]);
+        let alice = mgr.find_config_set("alice").unwrap();
+        assert_eq!(alice.path, second);
+        assert!(alice.is_pinned());
+        let old = mgr
+            .find_backup(&first.file_name().unwrap().to_string_lossy())
+            .unwrap();
+        assert!(!old.is_pinned());
+
+        mgr.delete_config_set("bob").unwrap();
+        assert!(mgr.find_config_set("bob").is_err());
+    }
+
+    #[test]
+    fn test_garbage_collection_during_backups_keeps_their_blobs() {
+        let tmp = TempDir::new().unwrap();
+        let src = setup_source_dir(&tmp);
+        let mgr = BackupManager::new(tmp.path().join("backups"), 100);
+
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                for n in 0..20u8 {
+                    fs::write(src.join("database.db-wal"), vec![n; 1024]).unwrap();
+                    mgr.create_backup(&src, BackupReason::Manual, None).unwrap();
+                }
+            });
+            scope.spawn(|| {
+                for _ in 0..200 {
+                    let _store = mgr.lock_store();
+                    mgr.collect_garbage().unwrap();
+                }
+            });
+        });
+
+        for entry in mgr.list_backups().unwrap() {
+            assert!(
+                mgr.load_snapshot(&entry).is_ok(),
+                "{} lost blobs",
+                entry.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_prune_collects_unreferenced_blobs() {
+        let tmp = TempDir::new().unwrap();
+        let src = setup_source_dir(&tmp);
+        let backup_dir = tmp.path().join("backups");
+        let mgr = BackupManager::new(backup_dir.clone(), 1);
+
+        mgr.create_backup(&src, BackupReason::PrePush, None)
+            .unwrap();
+        fs::write(src.join("database.db"), b"changed-db").unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        mgr.create_backup(&src, BackupReason::PrePull, None)
+            .unwrap();
+
+        // The old database.db blob is gone; the shared sidecars remain
+        assert_eq!(mgr.list_backups().unwrap().len(), 1);
+        assert_eq!(blob_count(&backup_dir), 3);
+        assert!(!mgr.blob_path(&sha256_hex(b"main-db-content")).exists());
+    }
+
+    #[test]
+    fn test_blobs_are_compressed() {
+        let tmp = TempDir::new().unwrap();
+        let src = tmp.path().join("source");
+        fs::create_dir_all(&src).unwrap();
+        let db = b"SQLite format 3\0".repeat(1000);
+        fs::write(src.join("database.db"), &db).unwrap();
+        let mgr = BackupManager::new(tmp.path().join("backups"), 20);
+
+        let backup_path = mgr.create_backup(&src, BackupReason::Manual, None).unwrap();
+
+        let file = &mgr.list_backups().unwrap()[0]
+            .manifest
+            .clone()
+            .unwrap()
+            .files[0];
+        assert_eq!(file.size, db.len() as u64);
+        assert!(file.compressed_size.unwrap() < file.size);
+        let on_disk = fs::metadata(mgr.blob_path(&file.sha256)).unwrap().len();
+        assert_eq!(on_disk, file.compressed_size.unwrap());
+        assert_eq!(
+            mgr.read_file(&backup_path, "database.db").unwrap().unwrap(),
+            db
+        );
+    }
+
+    #[test]
+    fn test_read_uncompressed_blob() {
+        let tmp = TempDir::new().unwrap();
+        let mgr = BackupManager::new(tmp.path().join("backups"), 20);
+        let hash = sha256_hex(b"raw-db");
+        let raw = mgr.raw_blob_path(&hash);
+        fs::create_dir_all(raw.parent().unwrap()).unwrap();
+        fs::write(&raw, b"raw-db").unwrap();
+
+        assert_eq!(mgr.read_blob(&hash).unwrap().unwrap(), b"raw-db");
+    }
+
+    #[test]
+    fn test_load_inline_backup() {
+        let tmp = TempDir::new().unwrap();
+        let legacy = tmp.path().join("backups").join("sync-2026-02-16T14-30-00");
+        fs::create_dir_all(&legacy).unwrap();
+        fs::write(legacy.join("database.db"), b"legacy-db").unwrap();
+        let mgr = BackupManager::new(tmp.path().join("backups"), 20);
+
+        let entry = mgr.find_backup("sync-2026-02-16T14-30-00").unwrap();
+        let snapshot = mgr.load_snapshot(&entry).unwrap();
+        assert_eq!(snapshot.db, b"legacy-db");
+        assert!(snapshot.db_wal.is_none());
+    }
+
+    #[test]
+    fn test_create_backup_writes_manifest() {
+        let tmp = TempDir::new().unwrap();
+        let src = setup_source_dir(&tmp);
+        let backup_dir = tmp.path().join("backups");
+        let mgr = BackupManager::new(backup_dir, 20).with_device_name("desk-pc".into());
 
-        assert!(backup_path.join("database.db").exists());
-        assert!(backup_path.join("database.db-shm").exists());
-        assert!(backup_path.join("database.db-wal").exists());
-        assert!(!backup_path.join("unrelated.txt").exists());
+        mgr.create_backup(&src, BackupReason::PrePull, Some("laptop"))
+            .unwrap();
+
+        let list = mgr.list_backups().unwrap();
+        let manifest = list[0].manifest.as_ref().unwrap();
+        assert_eq!(manifest.reason, BackupReason::PrePull);
+        assert_eq!(manifest.source_device, "desk-pc");
+        assert_eq!(manifest.remote_device.as_deref(), Some("laptop"));
+        assert_eq!(manifest.app_version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(manifest.files.len(), 3);
+        assert_eq!(manifest.files[0].name, "database.db");
+        assert_eq!(manifest.files[0].size, b"main-db-content".len() as u64);
+        assert_eq!(manifest.files[0].sha256, sha256_hex(b"main-db-content"));
+    }
+
+    #[test]
+    fn test_list_backups_without_manifest() {
+        let tmp = TempDir::new().unwrap();
+        let legacy = tmp.path().join("backups").join("sync-2026-02-16T14-30-00");
+        fs::create_dir_all(&legacy).unwrap();
+        fs::write(legacy.join("database.db"), b"old").unwrap();
+        let mgr = BackupManager::new(tmp.path().join("backups"), 20);
+
+        let list = mgr.list_backups().unwrap();
+        assert_eq!(list.len(), 1);
+        assert!(list[0].manifest.is_none());
     }
 
     #[test]
@@ -124,14 +1403,16 @@ mod tests {
         let backup_dir = tmp.path().join("backups");
         let mgr = BackupManager::new(backup_dir, 20);
 
-        mgr.create_backup(&src, "first").unwrap();
+        mgr.create_backup(&src, BackupReason::PrePush, None)
+            .unwrap();
         std::thread::sleep(std::time::Duration::from_millis(50));
-        mgr.create_backup(&src, "second").unwrap();
+        mgr.create_backup(&src, BackupReason::PrePull, None)
+            .unwrap();
 
         let list = mgr.list_backups().unwrap();
         assert_eq!(list.len(), 2);
-        assert!(list[0].name.starts_with("second"));
-        assert!(list[1].name.starts_with("first"));
+        assert!(list[0].name.starts_with("pre-pull"));
+        assert!(list[1].name.starts_with("pre-push"));
     }
 
     #[test]
@@ -141,32 +1422,475 @@ mod tests {
         let backup_dir = tmp.path().join("backups");
         let mgr = BackupManager::new(backup_dir, 2);
 
-        mgr.create_backup(&src, "one").unwrap();
+        mgr.create_backup(&src, BackupReason::Manual, None).unwrap();
         std::thread::sleep(std::time::Duration::from_millis(50));
-        mgr.create_backup(&src, "two").unwrap();
+        mgr.create_backup(&src, BackupReason::PrePush, None)
+            .unwrap();
         std::thread::sleep(std::time::Duration::from_millis(50));
-        mgr.create_backup(&src, "three").unwrap();
+        mgr.create_backup(&src, BackupReason::PrePull, None)
+            .unwrap();
 
         let list = mgr.list_backups().unwrap();
         assert_eq!(list.len(), 2);
-        assert!(list[0].name.starts_with("three"));
-        assert!(list[1].name.starts_with("two"));
+        assert!(list[0].name.starts_with("pre-pull"));
+        assert!(list[1].name.starts_with("pre-push"));
     }
 
     #[test]
-    fn test_restore_backup() {
+    fn test_preflight_passes_with_small_margin() {
+        let tmp = TempDir::new().unwrap();
+        let src = setup_source_dir(&tmp);
+        let mgr = BackupManager::new(tmp.path().join("backups"), 20).with_space_margin(0);
+        assert_eq!(mgr.preflight(&src).unwrap(), SafetyCheck::Safe);
+    }
+
+    #[test]
+    fn test_create_backup_fails_without_space() {
         let tmp = TempDir::new().unwrap();
         let src = setup_source_dir(&tmp);
         let backup_dir = tmp.path().join("backups");
-        let mgr = BackupManager::new(backup_dir, 20);
+        let mgr = BackupManager::new(backup_dir.clone(), 20);
+        mgr.create_backup(&src, BackupReason::Manual, None).unwrap();
+
+        // A margin no real volume can satisfy prunes down to the newest backup, then fails
+        let mgr = BackupManager::new(backup_dir, 20).with_space_margin(u64::MAX / 2);
+        if crate::safety::available_space(tmp.path()).is_none() {
+            return; // free space can't be determined in this environment
+        }
+        let err = mgr
+            .create_backup(&src, BackupReason::Manual, None)
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::StorageFull);
+        assert_eq!(mgr.list_backups().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_failed_backup_leaves_no_directory() {
+        let tmp = TempDir::new().unwrap();
+        let src = setup_source_dir(&tmp);
+        let backup_dir = tmp.path().join("backups");
+        fs::create_dir_all(&backup_dir).unwrap();
+        // A file where the blob store should be makes every blob write fail
+        fs::write(backup_dir.join(BLOB_DIR), b"").unwrap();
+        let mgr = BackupManager::new(backup_dir.clone(), 20);
+
+        assert!(mgr.create_backup(&src, BackupReason::Manual, None).is_err());
+        assert!(mgr.list_backups().unwrap().is_empty());
+        assert_eq!(fs::read_dir(&backup_dir).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_preflight_keeps_backups_when_pruning_cannot_help() {
+        let tmp = TempDir::new().unwrap();
+        let src = setup_source_dir(&tmp);
+        let backup_dir = tmp.path().join("backups");
+        let mgr = BackupManager::new(backup_dir.clone(), 20);
+        for n in 0..3u8 {
+            fs::write(src.join("database.db"), vec![n; 4096]).unwrap();
+            mgr.create_backup(&src, BackupReason::Manual, None).unwrap();
+        }
+        let blobs = blob_count(&backup_dir);
+
+        let mgr = BackupManager::new(backup_dir.clone(), 20).with_space_margin(u64::MAX / 2);
+        if crate::safety::available_space(tmp.path()).is_none() {
+            return; // free space can't be determined in this environment
+        }
+        assert!(matches!(
+            mgr.preflight(&src).unwrap(),
+            SafetyCheck::InsufficientSpace { .. }
+        ));
+        assert_eq!(mgr.list_backups().unwrap().len(), 3);
+        assert_eq!(blob_count(&backup_dir), blobs);
+    }
+
+    #[test]
+    fn test_plan_pruning_counts_shared_blobs_once() {
+        let tmp = TempDir::new().unwrap();
+        let src = setup_source_dir(&tmp);
+        let mgr = BackupManager::new(tmp.path().join("backups"), 20);
+        mgr.create_backup(&src, BackupReason::Manual, None).unwrap();
+        mgr.create_backup(&src, BackupReason::Manual, None).unwrap();
+        fs::write(src.join("database.db"), b"changed-db").unwrap();
+        mgr.create_backup(&src, BackupReason::Manual, None).unwrap();
+        let backups = mgr.list_backups().unwrap();
+
+        // The two older backups share all their blobs with each other, and the
+        // shm/wal blobs with the newest, so removing one frees only its manifest
+        let manifest = |b: &BackupEntry| fs::metadata(b.path.join(MANIFEST_FILE)).unwrap().len();
+        let (victims, freed) = mgr.plan_pruning(&backups, 1).unwrap();
+        assert_eq!(victims.len(), 1);
+        assert_eq!(freed, manifest(victims[0]));
+
+        let (victims, freed) = mgr.plan_pruning(&backups, u64::MAX).unwrap();
+        assert_eq!(victims.len(), 2);
+        let old_db = mgr.blob_size(&sha256_hex(b"main-db-content")).unwrap();
+        assert_eq!(
+            freed,
+            manifest(&backups[1]) + manifest(&backups[2]) + old_db
+        );
+    }
+
+    fn entry_at(created: DateTime<Utc>, pinned: bool) -> BackupEntry {
+        let manifest = BackupManifest {
+            reason: BackupReason::PrePush,
+            created,
+            source_device: "pc".into(),
+            remote_device: None,
+            files: Vec::new(),
+            app_version: "0".into(),
+            gg_schema_version: None,
+            pinned,
+            note: None,
+            replicated: false,
+            config_set: None,
+        };
+        BackupEntry {
+            name: created.to_rfc3339(),
+            path: PathBuf::new(),
+            created,
+            manifest: Some(manifest),
+            verification: None,
+            size: 0,
+            exclusive_size: 0,
+        }
+    }
+
+    #[test]
+    fn test_retention_keeps_daily_and_weekly_representatives() {
+        let now = Utc::now();
+        let policy = RetentionPolicy {
+            keep_all_hours: 1,
+            daily_days: 3,
+            weekly_weeks: 6,
+        };
+        // Newest first: a burst in the last hour, then one backup every 5 days
+        let mut backups: Vec<_> = (0..10)
+            .map(|m| entry_at(now - Duration::minutes(m), false))
+            .collect();
+        backups.extend((1..8).map(|n| entry_at(now - Duration::days(5 * n), false)));
+
+        let keep = retained(&backups, &policy, 3, now);
+
+        // 3 from the burst, then one backup per older ISO week
+        assert!(keep.contains(&0) && keep.contains(&1) && keep.contains(&2));
+        assert!(!(3..10).any(|i| keep.contains(&i)));
+        let iso = |b: &BackupEntry| {
+            let w = b.created.date_naive().iso_week();
+            (w.year(), w.week())
+        };
+        let older_weeks: HashSet<_> = backups[10..]
+            .iter()
+            .map(iso)
+            .filter(|w| *w != iso(&backups[0]))
+            .collect();
+        let old_kept = (10..17).filter(|i| keep.contains(i)).count();
+        assert_eq!(old_kept, older_weeks.len());
+    }
+
+    #[test]
+    fn test_retention_never_drops_pinned() {
+        let now = Utc::now();
+        let policy = RetentionPolicy {
+            keep_all_hours: 0,
+            daily_days: 0,
+            weekly_weeks: 0,
+        };
+        let backups = vec![
+            entry_at(now, false),
+            entry_at(now - Duration::days(400), true),
+            entry_at(now - Duration::days(500), false),
+        ];
+
+        let keep = retained(&backups, &policy, 0, now);
+        assert_eq!(keep, HashSet::from([0, 1]));
+    }
 
-        let backup_path = mgr.create_backup(&src, "snap").unwrap();
+    #[test]
+    fn test_pin_and_note_survive_pruning() {
+        let tmp = TempDir::new().unwrap();
+        let src = setup_source_dir(&tmp);
+        let mgr = BackupManager::new(tmp.path().join("backups"), 1);
+
+        let pinned = mgr.create_backup(&src, BackupReason::Manual, None).unwrap();
+        let name = pinned.file_name().unwrap().to_string_lossy().to_string();
+        mgr.set_pinned(&name, true).unwrap();
+        mgr.set_note(&name, Some("before tournament".into()))
+            .unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        mgr.create_backup(&src, BackupReason::PrePush, None)
+            .unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        mgr.create_backup(&src, BackupReason::PrePull, None)
+            .unwrap();
+
+        let list = mgr.list_backups().unwrap();
+        assert_eq!(list.len(), 2);
+        let kept = mgr.find_backup(&name).unwrap().manifest.unwrap();
+        assert!(kept.pinned);
+        assert_eq!(kept.note.as_deref(), Some("before tournament"));
+    }
+
+    #[test]
+    fn test_find_backup_rejects_unknown_names() {
+        let tmp = TempDir::new().unwrap();
+        let mgr = BackupManager::new(tmp.path().join("backups"), 20);
+        let err = mgr.find_backup("../../etc").unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn test_load_snapshot() {
+        let tmp = TempDir::new().unwrap();
+        let src = setup_source_dir(&tmp);
+        let backup_dir = tmp.path().join("backups");
+        let mgr = BackupManager::new(backup_dir, 20).with_device_name("desk-pc".into());
+
+        mgr.create_backup(&src, BackupReason::Manual, None).unwrap();
 
         // Modify the source
         fs::write(src.join("database.db"), b"modified").unwrap();
 
-        // Restore
-        mgr.restore_backup(&backup_path, &src).unwrap();
-        assert_eq!(fs::read(src.join("database.db")).unwrap(), b"main-db-content");
+        let snapshot = mgr.load_snapshot(&mgr.list_backups().unwrap()[0]).unwrap();
+        assert_eq!(snapshot.db, b"main-db-content");
+        assert_eq!(snapshot.db_wal.unwrap(), b"wal-content");
+        assert_eq!(snapshot.meta.device_name, "desk-pc");
+    }
+
+    #[test]
+    fn test_load_snapshot_detects_corrupt_blob() {
+        let tmp = TempDir::new().unwrap();
+        let src = setup_source_dir(&tmp);
+        let mgr = BackupManager::new(tmp.path().join("backups"), 20);
+        mgr.create_backup(&src, BackupReason::Manual, None).unwrap();
+
+        let blob = mgr.blob_path(&sha256_hex(b"main-db-content"));
+        fs::write(
+            &blob,
+            zstd::encode_all(&b"tampered"[..], ZSTD_LEVEL).unwrap(),
+        )
+        .unwrap();
+
+        let err = mgr
+            .load_snapshot(&mgr.list_backups().unwrap()[0])
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_record_restore() {
+        let tmp = TempDir::new().unwrap();
+        let mgr = BackupManager::new(tmp.path().join("backups"), 20);
+        assert!(mgr.last_restore().is_none());
+
+        mgr.record_restore("manual-1", Some("pre-restore-2"))
+            .unwrap();
+
+        let record = mgr.last_restore().unwrap();
+        assert_eq!(record.restored, "manual-1");
+        assert_eq!(record.undo_backup.as_deref(), Some("pre-restore-2"));
+        assert!(mgr.list_backups().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_verify_backups_flags_corrupt_and_incomplete() {
+        let tmp = TempDir::new().unwrap();
+        let src = tmp.path().join("source");
+        fs::create_dir_all(&src).unwrap();
+        let mgr = BackupManager::new(tmp.path().join("backups"), 20);
+        let db = |sql| crate::snapshot_db::tests::snapshot_from_sql(sql).db;
+
+        fs::write(src.join("database.db"), db("CREATE TABLE a (x);")).unwrap();
+        let good = mgr.create_backup(&src, BackupReason::Manual, None).unwrap();
+        fs::write(src.join("database.db"), b"SQLite format 3\0 but not really").unwrap();
+        let bad = mgr.create_backup(&src, BackupReason::Manual, None).unwrap();
+        fs::write(src.join("database.db"), db("CREATE TABLE c (x);")).unwrap();
+        let missing = mgr.create_backup(&src, BackupReason::Manual, None).unwrap();
+        let hash = &read_manifest(&missing).unwrap().files[0].sha256;
+        fs::remove_file(mgr.blob_path(hash)).unwrap();
+
+        let summary = mgr.verify_backups().unwrap();
+
+        let name = |p: &Path| p.file_name().unwrap().to_string_lossy().to_string();
+        assert_eq!(summary.checked, 3);
+        assert_eq!(summary.ok, 1);
+        assert_eq!(summary.corrupt, vec![name(&bad)]);
+        assert_eq!(summary.incomplete, vec![name(&missing)]);
+        let listed = mgr.find_backup(&name(&good)).unwrap();
+        assert_eq!(listed.verification.unwrap().health, BackupHealth::Ok);
+    }
+
+    #[test]
+    fn test_create_backup_if_changed_skips_duplicates() {
+        let tmp = TempDir::new().unwrap();
+        let src = setup_source_dir(&tmp);
+        let mgr = BackupManager::new(tmp.path().join("backups"), 20);
+
+        assert!(mgr
+            .create_backup_if_changed(&src, BackupReason::Scheduled)
+            .unwrap()
+            .is_some());
+        assert!(mgr
+            .create_backup_if_changed(&src, BackupReason::Scheduled)
+            .unwrap()
+            .is_none());
+
+        fs::remove_file(src.join("database.db-shm")).unwrap();
+        assert!(mgr
+            .create_backup_if_changed(&src, BackupReason::Scheduled)
+            .unwrap()
+            .is_some());
+        assert_eq!(mgr.list_backups().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_scheduled_backups_are_pruned_separately() {
+        let tmp = TempDir::new().unwrap();
+        let src = setup_source_dir(&tmp);
+        let policy = RetentionPolicy {
+            keep_all_hours: 1,
+            daily_days: 0,
+            weekly_weeks: 0,
+        };
+        let mgr = BackupManager::new(tmp.path().join("backups"), 1)
+            .with_retention(policy.clone())
+            .with_scheduled_retention(2, policy);
+
+        mgr.create_backup(&src, BackupReason::Manual, None).unwrap();
+        for n in 0..3 {
+            fs::write(src.join("database.db"), format!("db-{}", n)).unwrap();
+            mgr.create_backup(&src, BackupReason::Scheduled, None)
+                .unwrap();
+        }
+
+        let backups = mgr.list_backups().unwrap();
+        let scheduled = backups.iter().filter(|b| b.is_scheduled()).count();
+        assert_eq!(scheduled, 2);
+        assert_eq!(backups.len(), 3);
+    }
+
+    #[test]
+    fn test_backups_in_the_same_second_do_not_collide() {
+        let tmp = TempDir::new().unwrap();
+        let src = setup_source_dir(&tmp);
+        let mgr = BackupManager::new(tmp.path().join("backups"), 20);
+
+        let first = mgr.create_backup(&src, BackupReason::Manual, None).unwrap();
+        let second = mgr.create_backup(&src, BackupReason::Manual, None).unwrap();
+
+        assert_ne!(first, second);
+        let names: Vec<_> = mgr
+            .list_backups()
+            .unwrap()
+            .into_iter()
+            .map(|b| b.path)
+            .collect();
+        assert_eq!(names, vec![second, first]);
+    }
+
+    #[test]
+    fn test_legacy_backup_names_remain_readable() {
+        let tmp = TempDir::new().unwrap();
+        let backup_dir = tmp.path().join("backups");
+        let legacy = backup_dir.join("pre-pull-2026-01-02T03-04-05");
+        fs::create_dir_all(&legacy).unwrap();
+        fs::write(legacy.join("database.db"), b"old-db").unwrap();
+        let mgr = BackupManager::new(backup_dir, 20);
+
+        let entry = mgr.find_backup("pre-pull-2026-01-02T03-04-05").unwrap();
+
+        assert_eq!(entry.created.to_rfc3339(), "2026-01-02T03:04:05+00:00");
+        assert_eq!(mgr.load_snapshot(&entry).unwrap().db, b"old-db");
+    }
+
+    #[test]
+    fn test_sanitize_label() {
+        assert_eq!(sanitize_label("pre-pull"), "pre-pull");
+        assert_eq!(sanitize_label("  My Backup: ../../etc!! "), "my-backup-etc");
+        assert_eq!(sanitize_label("///"), "backup");
+        assert!(sanitize_label(&"x".repeat(100)).len() <= MAX_LABEL_LEN);
+    }
+
+    #[test]
+    fn test_replication_candidates() {
+        let tmp = TempDir::new().unwrap();
+        let src = setup_source_dir(&tmp);
+        let mgr = BackupManager::new(tmp.path().join("backups"), 20);
+        let older = mgr.create_backup(&src, BackupReason::Manual, None).unwrap();
+        let newer = mgr.create_backup(&src, BackupReason::Manual, None).unwrap();
+        let name = |p: &Path| p.file_name().unwrap().to_string_lossy().to_string();
+        mgr.set_pinned(&name(&older), true).unwrap();
+        let tomorrow = Utc::now() + Duration::days(1);
+
+        let pinned = mgr.replication_candidates(true, false, Utc::now()).unwrap();
+        assert_eq!(pinned.len(), 1);
+        assert_eq!(pinned[0].name, name(&older));
+
+        // Only the newest backup of a completed day counts as daily
+        let daily = mgr.replication_candidates(false, true, tomorrow).unwrap();
+        assert_eq!(daily.len(), 1);
+        assert_eq!(daily[0].name, name(&newer));
+        assert!(mgr
+            .replication_candidates(false, true, Utc::now())
+            .unwrap()
+            .is_empty());
+
+        mgr.set_replicated(&name(&newer)).unwrap();
+        assert!(mgr
+            .replication_candidates(false, true, tomorrow)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_disk_usage_accounts_for_shared_blobs() {
+        let tmp = TempDir::new().unwrap();
+        let src = setup_source_dir(&tmp);
+        let mgr = BackupManager::new(tmp.path().join("backups"), 20);
+        mgr.create_backup(&src, BackupReason::Manual, None).unwrap();
+        fs::write(src.join("database.db"), b"changed-db").unwrap();
+        mgr.create_backup(&src, BackupReason::Manual, None).unwrap();
+
+        let listing = mgr.list_with_usage().unwrap();
+
+        let newest = &listing.backups[0];
+        let db_blob = mgr.blob_size(&sha256_hex(b"changed-db")).unwrap();
+        assert!(db_blob > 0);
+        assert!(newest.size > newest.exclusive_size);
+        assert_eq!(
+            newest.exclusive_size,
+            db_blob + fs::metadata(newest.path.join(MANIFEST_FILE)).unwrap().len()
+        );
+        let sum: u64 = listing.backups.iter().map(|b| b.exclusive_size).sum();
+        assert!(listing.total_size >= sum);
+    }
+
+    #[test]
+    fn test_budget_prunes_scheduled_backups_first() {
+        let tmp = TempDir::new().unwrap();
+        let src = tmp.path().join("source");
+        fs::create_dir_all(&src).unwrap();
+        let mgr = BackupManager::new(tmp.path().join("backups"), 20).with_budget(Some(1));
+        let write_db = |n: u8| fs::write(src.join("database.db"), vec![n; 4096]).unwrap();
+
+        write_db(1);
+        let manual = mgr.create_backup(&src, BackupReason::Manual, None).unwrap();
+        mgr.set_pinned(&manual.file_name().unwrap().to_string_lossy(), true)
+            .unwrap();
+        write_db(2);
+        mgr.create_backup(&src, BackupReason::Scheduled, None)
+            .unwrap();
+        write_db(3);
+        let newest = mgr
+            .create_backup(&src, BackupReason::PrePush, None)
+            .unwrap();
+
+        // Nothing fits in one byte, but pinned and newest backups survive
+        let paths: Vec<_> = mgr
+            .list_backups()
+            .unwrap()
+            .into_iter()
+            .map(|b| b.path)
+            .collect();
+        assert_eq!(paths, vec![newest, manual]);
     }
 }
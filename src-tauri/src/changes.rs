@@ -0,0 +1,235 @@
+//! Human-readable summaries of what changed between two config snapshots.
+//!
+//! Both snapshots are inspected and the inspections compared, so changes are
+//! described in GG terms ("Rival 3 · Profile FPS · dpi: 800 → 1600"). Tables
+//! the inspector can't interpret fall back to per-table row counts.
+
+use crate::diff::{diff_snapshots, TableDiff};
+use crate::inspector::{inspect_snapshot, DeviceInfo, Inspection, ProfileInfo};
+use crate::providers::ConfigSnapshot;
+use crate::snapshot_db::SnapshotDbError;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Describe the changes from `before` to `after`, one line per change.
+pub fn summarize_changes(
+    before: &ConfigSnapshot,
+    after: &ConfigSnapshot,
+) -> Result<Vec<String>, SnapshotDbError> {
+    let old = inspect_snapshot(before)?;
+    let new = inspect_snapshot(after)?;
+    let mut lines = Vec::new();
+    device_changes(&old, &new, &mut lines);
+    profile_changes(&old, &new, &mut lines);
+
+    let interpreted: BTreeSet<&str> = old
+        .tables
+        .iter()
+        .chain(&new.tables)
+        .filter(|t| t.interpreted)
+        .map(|t| t.name.as_str())
+        .collect();
+    for table in diff_snapshots(before, after)?.tables {
+        if !interpreted.contains(table.table.as_str()) {
+            lines.push(format!("{}: {}", table.table, row_counts(&table)));
+        }
+    }
+    Ok(lines)
+}
+
+fn device_changes(old: &Inspection, new: &Inspection, lines: &mut Vec<String>) {
+    let before = by_key(&old.devices, |d| (&d.table, &d.key));
+    let after = by_key(&new.devices, |d| (&d.table, &d.key));
+
+    for (id, device) in &after {
+        let Some(previous) = before.get(id) else {
+            lines.push(format!("new device '{}'", device_name(device)));
+            continue;
+        };
+        let name = device_name(device);
+        if previous.name != device.name {
+            lines.push(format!("{} renamed to '{}'", device_name(previous), name));
+        }
+        if previous.active_profile != device.active_profile {
+            lines.push(format!(
+                "{} · active profile: {} → {}",
+                name,
+                profile_name(old, previous.active_profile.as_deref()),
+                profile_name(new, device.active_profile.as_deref())
+            ));
+        }
+        value_changes(&name, &previous.details, &device.details, lines);
+    }
+    for (id, device) in &before {
+        if !after.contains_key(id) {
+            lines.push(format!("removed device '{}'", device_name(device)));
+        }
+    }
+}
+
+fn profile_changes(old: &Inspection, new: &Inspection, lines: &mut Vec<String>) {
+    let before = by_key(&old.profiles, |p| (&p.table, &p.key));
+    let after = by_key(&new.profiles, |p| (&p.table, &p.key));
+
+    for (id, profile) in &after {
+        let Some(previous) = before.get(id) else {
+            lines.push(format!(
+                "{}new profile '{}'",
+                device_prefix(new, profile),
+                profile.name.as_deref().unwrap_or(&profile.key)
+            ));
+            continue;
+        };
+        let prefix = format!(
+            "{}Profile {}",
+            device_prefix(new, profile),
+            previous.name.as_deref().unwrap_or(&previous.key)
+        );
+        if previous.name != profile.name {
+            lines.push(format!(
+                "{} renamed to '{}'",
+                prefix,
+                profile.name.as_deref().unwrap_or(&profile.key)
+            ));
+        }
+        value_changes(&prefix, &previous.settings, &profile.settings, lines);
+        for app in profile.apps.iter().filter(|a| !previous.apps.contains(a)) {
+            lines.push(format!("{} · linked to {}", prefix, app));
+        }
+        for app in previous.apps.iter().filter(|a| !profile.apps.contains(a)) {
+            lines.push(format!("{} · unlinked from {}", prefix, app));
+        }
+    }
+    for (id, profile) in &before {
+        if !after.contains_key(id) {
+            lines.push(format!(
+                "{}removed profile '{}'",
+                device_prefix(old, profile),
+                profile.name.as_deref().unwrap_or(&profile.key)
+            ));
+        }
+    }
+}
+
+fn value_changes(
+    prefix: &str,
+    before: &BTreeMap<String, serde_json::Value>,
+    after: &BTreeMap<String, serde_json::Value>,
+    lines: &mut Vec<String>,
+) {
+    let keys: BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+    for key in keys {
+        let (old, new) = (before.get(key), after.get(key));
+        if old != new {
+            lines.push(format!(
+                "{} · {}: {} → {}",
+                prefix,
+                key,
+                display(old),
+                display(new)
+            ));
+        }
+    }
+}
+
+fn by_key<'a, T>(
+    items: &'a [T],
+    key: impl Fn(&'a T) -> (&'a String, &'a String),
+) -> BTreeMap<(&'a String, &'a String), &'a T> {
+    items.iter().map(|item| (key(item), item)).collect()
+}
+
+fn device_name(device: &DeviceInfo) -> String {
+    device.name.clone().unwrap_or_else(|| device.key.clone())
+}
+
+/// "<device> · " for a profile linked to a device, otherwise empty.
+fn device_prefix(inspection: &Inspection, profile: &ProfileInfo) -> String {
+    profile
+        .device
+        .as_ref()
+        .and_then(|key| inspection.devices.iter().find(|d| &d.key == key))
+        .map(|device| format!("{} · ", device_name(device)))
+        .unwrap_or_default()
+}
+
+fn profile_name(inspection: &Inspection, key: Option<&str>) -> String {
+    let Some(key) = key else {
+        return "(none)".to_string();
+    };
+    inspection
+        .profiles
+        .iter()
+        .find(|p| p.key == key)
+        .and_then(|p| p.name.clone())
+        .unwrap_or_else(|| key.to_string())
+}
+
+fn display(value: Option<&serde_json::Value>) -> String {
+    match value {
+        None => "(unset)".to_string(),
+        Some(serde_json::Value::Null) => "(none)".to_string(),
+        Some(serde_json::Value::String(s)) => s.clone(),
+        Some(other) => other.to_string(),
+    }
+}
+
+fn row_counts(table: &TableDiff) -> String {
+    [
+        (table.added.len(), "added"),
+        (table.removed.len(), "removed"),
+        (table.changed.len(), "changed"),
+    ]
+    .iter()
+    .filter(|(n, _)| *n > 0)
+    .map(|(n, what)| format!("{} row(s) {}", n, what))
+    .collect::<Vec<_>>()
+    .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snapshot_db::tests::snapshot_from_sql;
+
+    const SCHEMA: &str = "
+        CREATE TABLE devices (id INTEGER PRIMARY KEY, name TEXT, active_profile_id INTEGER);
+        CREATE TABLE profiles (id INTEGER PRIMARY KEY, name TEXT, device_id INTEGER);
+        CREATE TABLE profile_settings (profile_id INTEGER, key TEXT, value TEXT,
+                                       PRIMARY KEY (profile_id, key));
+        CREATE TABLE telemetry (event TEXT);
+        INSERT INTO devices VALUES (1, 'Rival 3', 10);
+        INSERT INTO profiles VALUES (10, 'FPS', 1);";
+
+    #[test]
+    fn test_describes_changes_in_gg_terms() {
+        let before = snapshot_from_sql(&format!(
+            "{} INSERT INTO profile_settings VALUES (10, 'DPI stage 2', '800');",
+            SCHEMA
+        ));
+        let after = snapshot_from_sql(&format!(
+            "{} INSERT INTO profile_settings VALUES (10, 'DPI stage 2', '1600');
+             INSERT INTO profiles VALUES (11, 'Valorant', 1);
+             UPDATE devices SET active_profile_id = 11;
+             INSERT INTO telemetry VALUES ('x');",
+            SCHEMA
+        ));
+
+        let lines = summarize_changes(&before, &after).unwrap();
+
+        assert_eq!(
+            lines,
+            [
+                "Rival 3 · active profile: FPS → Valorant",
+                "Rival 3 · Profile FPS · DPI stage 2: 800 → 1600",
+                "Rival 3 · new profile 'Valorant'",
+                "telemetry: 1 row(s) added",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_identical_snapshots_have_no_changes() {
+        let snapshot = snapshot_from_sql(SCHEMA);
+        assert!(summarize_changes(&snapshot, &snapshot).unwrap().is_empty());
+    }
+}
@@ -9,14 +9,223 @@ pub struct AppConfig {
     pub steelseries_db_path: PathBuf,
     /// Path to local backup directory
     pub backup_dir: PathBuf,
-    /// Maximum number of backups to retain
+    /// Maximum number of recent backups to retain (see `retention`)
     pub max_backups: usize,
+    /// Maximum disk usage of the backup directory in MiB (0 = unlimited)
+    #[serde(default)]
+    pub backup_budget_mb: u64,
+    /// Grandfather-father-son retention applied when pruning backups
+    #[serde(default)]
+    pub retention: RetentionPolicy,
     /// Debounce duration in seconds
     pub debounce_secs: u64,
     /// Active sync provider
     pub provider: ProviderConfig,
     /// Device name for this machine (used in conflict labels)
     pub device_name: String,
+    /// Free space (MiB) to keep on a volume on top of what a write needs
+    #[serde(default = "default_space_margin_mb")]
+    pub disk_space_margin_mb: u64,
+    /// Controlled stop/restart of SteelSeries GG around a pull
+    #[serde(default)]
+    pub gg_restart: GgRestartConfig,
+    /// Verify all backups every N hours (0 disables scheduled verification)
+    #[serde(default)]
+    pub verify_interval_hours: u64,
+    /// Shared secret used to sign exported archives. When set, imported archives
+    /// must carry a matching signature.
+    #[serde(default)]
+    pub archive_signing_key: Option<String>,
+    /// Backups taken on a timer or when GG exits, independent of sync
+    #[serde(default)]
+    pub backup_schedule: BackupScheduleConfig,
+    /// Which backups to copy off-site through the sync provider
+    #[serde(default)]
+    pub replication: ReplicationConfig,
+    /// Restrict sync to selected profiles and devices
+    #[serde(default)]
+    pub selective_sync: SelectiveSyncConfig,
+    /// Machine-specific fields kept out of sync
+    #[serde(default)]
+    pub field_transforms: TransformConfig,
+    /// Translation of game executable paths between operating systems
+    #[serde(default)]
+    pub path_mapping: PathMappingConfig,
+    /// Shared-machine mode: restore a golden config every session, never push
+    #[serde(default)]
+    pub kiosk: KioskConfig,
+}
+
+/// Which backups survive pruning. Pinned backups are always kept.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetentionPolicy {
+    /// Keep every backup from the last N hours (up to `max_backups` of them)
+    pub keep_all_hours: u64,
+    /// Keep the newest backup of each day for this many days
+    pub daily_days: u64,
+    /// Keep the newest backup of each week for this many weeks
+    pub weekly_weeks: u64,
+}
+
+/// Scheduled backups. They are pruned as their own class, so frequent
+/// snapshots never push sync backups out of retention.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BackupScheduleConfig {
+    pub interval: ScheduleInterval,
+    /// Also take a backup whenever GG exits
+    pub on_gg_exit: bool,
+    /// Maximum number of recent scheduled backups to retain
+    pub max_backups: usize,
+    pub retention: RetentionPolicy,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScheduleInterval {
+    Off,
+    Hourly,
+    Daily,
+}
+
+impl ScheduleInterval {
+    pub fn duration(&self) -> Option<chrono::Duration> {
+        match self {
+            ScheduleInterval::Off => None,
+            ScheduleInterval::Hourly => Some(chrono::Duration::hours(1)),
+            ScheduleInterval::Daily => Some(chrono::Duration::days(1)),
+        }
+    }
+}
+
+/// Backup classes replicated to the sync provider. Both off by default.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReplicationConfig {
+    /// Upload pinned backups
+    pub pinned: bool,
+    /// Upload the newest backup of each day
+    pub daily: bool,
+}
+
+/// Profiles, devices and app configs to sync, by name or ID (case-insensitive).
+/// With both lists empty everything is synced. Otherwise only items matching
+/// `include` (or everything, if it is empty) that don't match `exclude` are
+/// pushed, and a pull merges only those items into the local config.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SelectiveSyncConfig {
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+}
+
+impl SelectiveSyncConfig {
+    pub fn is_active(&self) -> bool {
+        rules(&self.include).next().is_some() || rules(&self.exclude).next().is_some()
+    }
+
+    /// Whether an item with this name and key is synced.
+    pub fn selects(&self, name: Option<&str>, key: &str) -> bool {
+        let matches = |list: &[String]| {
+            rules(list).any(|rule| {
+                rule.eq_ignore_ascii_case(key)
+                    || name.is_some_and(|name| rule.eq_ignore_ascii_case(name))
+            })
+        };
+        (rules(&self.include).next().is_none() || matches(&self.include)) && !matches(&self.exclude)
+    }
+}
+
+/// Non-blank rules, trimmed.
+fn rules(list: &[String]) -> impl Iterator<Item = &str> {
+    list.iter()
+        .map(|rule| rule.trim())
+        .filter(|rule| !rule.is_empty())
+}
+
+/// Per-machine handling of database fields (see `transform`).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TransformConfig {
+    /// Columns stripped before push; this machine's values are kept on pull
+    pub machine_fields: Vec<FieldRef>,
+    /// Fixed values written after every pull
+    pub overrides: Vec<FieldOverride>,
+}
+
+impl TransformConfig {
+    pub fn is_active(&self) -> bool {
+        !(self.machine_fields.is_empty() && self.overrides.is_empty())
+    }
+}
+
+/// Rewriting of game paths from other operating systems on pull (see `path_mapping`).
+/// Off by default.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PathMappingConfig {
+    pub enabled: bool,
+    /// Known install locations of games on each OS
+    pub games: Vec<GameMapping>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GameMapping {
+    pub name: String,
+    #[serde(default)]
+    pub windows: Option<String>,
+    #[serde(default)]
+    pub macos: Option<String>,
+    #[serde(default)]
+    pub linux: Option<String>,
+}
+
+/// Kiosk mode for shared machines (see `kiosk`). While it is on and not
+/// unlocked with the override password, the golden backup is restored when the
+/// app starts and whenever GG exits, pushes are skipped and settings are read-only.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KioskConfig {
+    pub enabled: bool,
+    /// Name of the local backup to restore; pinned so it is never pruned
+    pub golden_backup: Option<String>,
+    /// Salted hash of the override password (see `kiosk::hash_password`).
+    /// Without one, kiosk mode can only be left by editing the config file.
+    pub override_password_hash: Option<String>,
+}
+
+/// A column in the GG database.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldRef {
+    pub table: String,
+    pub column: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldOverride {
+    #[serde(flatten)]
+    pub field: FieldRef,
+    /// Row key as shown in diffs (primary key values joined with `/`). Keyed
+    /// overrides skip tables without a primary key. Applies to every row when
+    /// unset.
+    #[serde(default)]
+    pub key: Option<String>,
+    pub value: serde_json::Value,
+}
+
+/// Opt-in settings for stopping GG before a pull and relaunching it afterwards.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GgRestartConfig {
+    /// Ask GG to exit before applying a pull, then relaunch it
+    pub enabled: bool,
+    /// Executable (or .app bundle on macOS) used to relaunch GG
+    pub executable_path: PathBuf,
+    /// Seconds to wait for GG to exit before aborting the pull
+    pub exit_timeout_secs: u64,
+    /// Force-kill GG if it ignores the exit request. Never done unless set.
+    pub force_kill_on_timeout: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -32,11 +241,55 @@ impl Default for AppConfig {
             steelseries_db_path: default_steelseries_path(),
             backup_dir: default_backup_path(),
             max_backups: 20,
+            backup_budget_mb: 0,
+            retention: RetentionPolicy::default(),
             debounce_secs: 3,
             provider: ProviderConfig::Folder {
                 sync_dir: default_sync_folder(),
             },
             device_name: hostname(),
+            disk_space_margin_mb: default_space_margin_mb(),
+            gg_restart: GgRestartConfig::default(),
+            verify_interval_hours: 0,
+            archive_signing_key: None,
+            backup_schedule: BackupScheduleConfig::default(),
+            replication: ReplicationConfig::default(),
+            selective_sync: SelectiveSyncConfig::default(),
+            field_transforms: TransformConfig::default(),
+            path_mapping: PathMappingConfig::default(),
+            kiosk: KioskConfig::default(),
+        }
+    }
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            keep_all_hours: 24,
+            daily_days: 7,
+            weekly_weeks: 4,
+        }
+    }
+}
+
+impl Default for BackupScheduleConfig {
+    fn default() -> Self {
+        Self {
+            interval: ScheduleInterval::Off,
+            on_gg_exit: false,
+            max_backups: 24,
+            retention: RetentionPolicy::default(),
+        }
+    }
+}
+
+impl Default for GgRestartConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            executable_path: default_gg_executable(),
+            exit_timeout_secs: 30,
+            force_kill_on_timeout: false,
         }
     }
 }
@@ -51,6 +304,20 @@ fn default_steelseries_path() -> PathBuf {
     }
 }
 
+fn default_space_margin_mb() -> u64 {
+    crate::safety::DEFAULT_SPACE_MARGIN / (1024 * 1024)
+}
+
+fn default_gg_executable() -> PathBuf {
+    if cfg!(target_os = "windows") {
+        PathBuf::from(r"C:\Program Files\SteelSeries\GG\SteelSeriesGG.exe")
+    } else if cfg!(target_os = "macos") {
+        PathBuf::from("/Applications/SteelSeries GG.app")
+    } else {
+        PathBuf::new()
+    }
+}
+
 fn default_backup_path() -> PathBuf {
     dirs::data_local_dir()
         .unwrap_or_else(|| PathBuf::from("."))
@@ -0,0 +1,554 @@
+//! Pairing of devices across machines.
+//!
+//! GG identifies a device by something machine-specific (a serial number, a
+//! USB instance path), so the same model can have a different key on every
+//! machine and profiles pulled from elsewhere don't attach to the local device.
+//! Devices pulled from another machine that aren't known locally are matched to
+//! a local device of the same model and suggested as pairings. Once the user
+//! confirms a pairing, the remote key and foreign keys referring to it are
+//! rewritten to the local key whenever a pull is applied.
+
+use crate::diff::{
+    key_part, primary_key, quote_ident, read_table, row_label, table_names, GgConcept,
+};
+use crate::providers::ConfigSnapshot;
+use crate::snapshot_db::{SnapshotDb, SnapshotDbError};
+use rusqlite::types::Value;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use ulid::Ulid;
+
+pub const DEVICE_MAP_FILE: &str = "device_map.json";
+
+/// Column names holding a device's model, preferred over its display name.
+const MODEL_COLUMNS: &[&str] = &["model", "product", "product_name"];
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DeviceMap {
+    pub pairings: Vec<DevicePairing>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PairingStatus {
+    /// Matched by model, waiting for the user.
+    Suggested,
+    Confirmed,
+    /// Not suggested again.
+    Rejected,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DevicePairing {
+    /// Device table the keys belong to.
+    pub table: String,
+    /// Key of the device on the other machine.
+    pub remote_key: String,
+    /// Key of the same device on this machine.
+    pub local_key: String,
+    pub model: Option<String>,
+    pub status: PairingStatus,
+}
+
+impl DeviceMap {
+    /// Add suggestions for remote devices that have no pairing yet, in any
+    /// status. Returns the suggestions that were added.
+    pub fn suggest(&mut self, suggestions: Vec<DevicePairing>) -> Vec<DevicePairing> {
+        let mut added = Vec::new();
+        for suggestion in suggestions {
+            let known = self
+                .pairings
+                .iter()
+                .any(|p| p.table == suggestion.table && p.remote_key == suggestion.remote_key);
+            if !known {
+                self.pairings.push(suggestion.clone());
+                added.push(suggestion);
+            }
+        }
+        added
+    }
+
+    pub fn has_confirmed(&self) -> bool {
+        self.pairings
+            .iter()
+            .any(|p| p.status == PairingStatus::Confirmed)
+    }
+}
+
+pub struct DeviceMapStore {
+    path: PathBuf,
+}
+
+impl DeviceMapStore {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// The saved map. A missing or unreadable file is an empty map.
+    pub fn load(&self) -> DeviceMap {
+        fs::read_to_string(&self.path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, map: &DeviceMap) -> std::io::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(map).map_err(std::io::Error::other)?;
+        let tmp = self.path.with_extension("json.tmp");
+        fs::write(&tmp, json)?;
+        fs::rename(tmp, &self.path)
+    }
+}
+
+struct Device {
+    table: String,
+    key: String,
+    model: Option<String>,
+}
+
+/// Pairings for devices in `remote` whose key `local` doesn't have, where
+/// exactly one local device of the same model is otherwise unaccounted for.
+pub fn suggest_pairings(
+    remote: &ConfigSnapshot,
+    local: &ConfigSnapshot,
+) -> Result<Vec<DevicePairing>, SnapshotDbError> {
+    let remote_devices = devices(remote)?;
+    let local_devices = devices(local)?;
+    let in_set = |set: &[Device], table: &str, key: &str| {
+        set.iter().any(|d| d.table == table && d.key == key)
+    };
+
+    let mut suggestions = Vec::new();
+    for device in &remote_devices {
+        let Some(model) = &device.model else {
+            continue;
+        };
+        if in_set(&local_devices, &device.table, &device.key) {
+            continue;
+        }
+        let candidates: Vec<&Device> = local_devices
+            .iter()
+            .filter(|d| {
+                d.table == device.table
+                    && d.model
+                        .as_deref()
+                        .is_some_and(|m| m.eq_ignore_ascii_case(model))
+                    && !in_set(&remote_devices, &d.table, &d.key)
+            })
+            .collect();
+        if let [local] = candidates.as_slice() {
+            suggestions.push(DevicePairing {
+                table: device.table.clone(),
+                remote_key: device.key.clone(),
+                local_key: local.key.clone(),
+                model: Some(model.clone()),
+                status: PairingStatus::Suggested,
+            });
+        }
+    }
+    Ok(suggestions)
+}
+
+/// Rewrite the confirmed pairings' remote keys to local keys in the database at
+/// `live_db`: the device row itself and every foreign key referring to it. A
+/// local row still present under a local key gives way to the pulled one. All
+/// pairings of a table are applied at once, so swapped or chained keys each
+/// move exactly once. Returns the number of values rewritten.
+pub fn translate_devices(live_db: &Path, map: &DeviceMap) -> Result<usize, SnapshotDbError> {
+    let mut live = Connection::open(live_db)?;
+    let tx = live.transaction()?;
+    // The device rows and their references change one column at a time
+    tx.execute_batch("PRAGMA defer_foreign_keys = ON")?;
+    let tables = table_names(&tx)?;
+    let mut rewritten = 0;
+
+    for (device_table, keys) in confirmed_keys(map) {
+        let pk = primary_key(&tx, device_table)?;
+        let [pk] = pk.as_slice() else {
+            continue;
+        };
+        let table = quote_ident(device_table);
+        let has_key = |key: &str| -> rusqlite::Result<bool> {
+            tx.query_row(
+                &format!(
+                    "SELECT EXISTS(SELECT 1 FROM {} WHERE CAST({} AS TEXT) = ?1)",
+                    table,
+                    quote_ident(pk)
+                ),
+                [key],
+                |r| r.get(0),
+            )
+        };
+        let mut present = Vec::new();
+        for &(remote, local) in &keys {
+            if has_key(remote)? {
+                present.push((remote, local));
+            }
+        }
+        for &(_, local) in &present {
+            if !present.iter().any(|&(remote, _)| remote == local) {
+                tx.execute(
+                    &format!(
+                        "DELETE FROM {} WHERE CAST({} AS TEXT) = ?1",
+                        table,
+                        quote_ident(pk)
+                    ),
+                    [local],
+                )?;
+            }
+        }
+        rewritten += rewrite_column(&tx, device_table, pk, &present)?;
+        for other in &tables {
+            for column in reference_columns(&tx, other, device_table, pk)? {
+                rewritten += rewrite_column(&tx, other, &column, &keys)?;
+            }
+        }
+    }
+    tx.commit()?;
+    Ok(rewritten)
+}
+
+/// Confirmed pairings as `(remote, local)` keys per device table. Pairings
+/// that would move a second device onto the same key, or move one device
+/// twice, are left out.
+fn confirmed_keys(map: &DeviceMap) -> BTreeMap<&str, Vec<(&str, &str)>> {
+    let mut tables: BTreeMap<&str, Vec<(&str, &str)>> = BTreeMap::new();
+    for pairing in &map.pairings {
+        if pairing.status != PairingStatus::Confirmed || pairing.remote_key == pairing.local_key {
+            continue;
+        }
+        let keys = tables.entry(pairing.table.as_str()).or_default();
+        if keys
+            .iter()
+            .any(|&(remote, local)| remote == pairing.remote_key || local == pairing.local_key)
+        {
+            log::warn!(
+                "Ignoring conflicting device pairing {} -> {}",
+                pairing.remote_key,
+                pairing.local_key
+            );
+            continue;
+        }
+        keys.push((&pairing.remote_key, &pairing.local_key));
+    }
+    tables
+}
+
+/// Replace remote keys with local keys in one column, keeping integer values
+/// integers. Keys first move to temporary values no row can hold yet, so a
+/// key that is both replaced and a replacement doesn't collide or move twice.
+fn rewrite_column(
+    conn: &Connection,
+    table: &str,
+    column: &str,
+    keys: &[(&str, &str)],
+) -> Result<usize, SnapshotDbError> {
+    if keys.is_empty() {
+        return Ok(0);
+    }
+    let table = quote_ident(table);
+    let column = quote_ident(column);
+    let first_temp: i64 = conn.query_row(
+        &format!(
+            "SELECT COALESCE(MAX(CAST({} AS INTEGER)), 0) + 1 FROM {}",
+            column, table
+        ),
+        [],
+        |r| r.get(0),
+    )?;
+    let tag = format!("device-map:{}:", Ulid::new());
+    let temps: Vec<(i64, String)> = (0..keys.len() as i64)
+        .map(|i| (first_temp + i, format!("{}{}", tag, i)))
+        .collect();
+
+    let mut to_temp = format!(
+        "UPDATE {} SET {} = CASE CAST({} AS TEXT)",
+        table, column, column
+    );
+    for (i, (number, text)) in temps.iter().enumerate() {
+        to_temp += &format!(
+            " WHEN ?{} THEN CASE WHEN typeof({}) = 'integer' THEN {} ELSE '{}' END",
+            i + 1,
+            column,
+            number,
+            text
+        );
+    }
+    to_temp += &format!(
+        " END WHERE CAST({} AS TEXT) IN ({})",
+        column,
+        placeholders(keys.len())
+    );
+    let rewritten = conn.execute(
+        &to_temp,
+        rusqlite::params_from_iter(keys.iter().map(|&(remote, _)| remote)),
+    )?;
+
+    let mut to_local = format!(
+        "UPDATE {} SET {} = CASE CAST({} AS TEXT)",
+        table, column, column
+    );
+    let mut temp_values = Vec::new();
+    for (i, (number, text)) in temps.iter().enumerate() {
+        to_local += &format!(
+            " WHEN '{}' THEN CAST(?{} AS INTEGER) WHEN '{}' THEN ?{}",
+            number,
+            i + 1,
+            text,
+            i + 1
+        );
+        temp_values.push(format!("'{}', '{}'", number, text));
+    }
+    to_local += &format!(
+        " END WHERE CAST({} AS TEXT) IN ({})",
+        column,
+        temp_values.join(", ")
+    );
+    conn.execute(
+        &to_local,
+        rusqlite::params_from_iter(keys.iter().map(|&(_, local)| local)),
+    )?;
+    Ok(rewritten)
+}
+
+fn placeholders(count: usize) -> String {
+    (1..=count)
+        .map(|i| format!("?{}", i))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Columns of `table` declared as foreign keys to the key `pk` of `device_table`.
+fn reference_columns(
+    conn: &Connection,
+    table: &str,
+    device_table: &str,
+    pk: &str,
+) -> Result<Vec<String>, SnapshotDbError> {
+    let mut stmt = conn.prepare(&format!("PRAGMA foreign_key_list({})", quote_ident(table)))?;
+    let foreign: Vec<(String, String, Option<String>)> = stmt
+        .query_map([], |r| Ok((r.get("table")?, r.get("from")?, r.get("to")?)))?
+        .collect::<Result<_, _>>()?;
+    let mut columns = Vec::new();
+    for (target, from, to) in foreign {
+        // Without a target column the foreign key refers to the primary key
+        if target.eq_ignore_ascii_case(device_table)
+            && to.is_none_or(|to| to.eq_ignore_ascii_case(pk))
+            && !columns.contains(&from)
+        {
+            columns.push(from);
+        }
+    }
+    Ok(columns)
+}
+
+/// Devices in tables addressed by a single-column key.
+fn devices(snapshot: &ConfigSnapshot) -> Result<Vec<Device>, SnapshotDbError> {
+    let db = SnapshotDb::open(snapshot)?;
+    let mut devices = Vec::new();
+    for table in table_names(db.conn())? {
+        if GgConcept::from_table(&table) != GgConcept::Device
+            || primary_key(db.conn(), &table)?.len() != 1
+        {
+            continue;
+        }
+        for (key, row) in read_table(db.conn(), &table)? {
+            let model = MODEL_COLUMNS
+                .iter()
+                .find_map(|c| match row.get(*c) {
+                    Some(Value::Null) | None => None,
+                    Some(value) => Some(key_part(value)),
+                })
+                .or_else(|| row_label(&row));
+            devices.push(Device {
+                table: table.clone(),
+                key,
+                model,
+            });
+        }
+    }
+    Ok(devices)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snapshot_db::tests::snapshot_from_sql;
+    use tempfile::TempDir;
+
+    const SCHEMA: &str = "
+        CREATE TABLE devices (serial TEXT PRIMARY KEY, model TEXT, name TEXT);
+        CREATE TABLE profiles (id INTEGER PRIMARY KEY, name TEXT,
+                               device_serial TEXT REFERENCES devices(serial));";
+
+    #[test]
+    fn test_suggests_unique_model_matches() {
+        let remote = snapshot_from_sql(&format!(
+            "{} INSERT INTO devices VALUES ('R-1', 'Rival 3', 'Mouse'), ('R-2', 'Apex Pro', 'Kbd'),
+                                           ('SHARED', 'Arctis 7', 'Headset');",
+            SCHEMA
+        ));
+        let local = snapshot_from_sql(&format!(
+            "{} INSERT INTO devices VALUES ('L-1', 'Rival 3', 'Mouse'), ('L-2', 'Apex Pro', 'A'),
+                                           ('L-3', 'Apex Pro', 'B'), ('SHARED', 'Arctis 7', 'H');",
+            SCHEMA
+        ));
+
+        let suggestions = suggest_pairings(&remote, &local).unwrap();
+
+        // Apex Pro is ambiguous and the headset already has the same key
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!(suggestions[0].remote_key, "R-1");
+        assert_eq!(suggestions[0].local_key, "L-1");
+
+        let mut map = DeviceMap::default();
+        assert_eq!(map.suggest(suggestions.clone()).len(), 1);
+        assert!(map.suggest(suggestions).is_empty());
+    }
+
+    #[test]
+    fn test_translates_confirmed_pairings() {
+        let tmp = TempDir::new().unwrap();
+        let pulled = snapshot_from_sql(&format!(
+            "{} INSERT INTO devices VALUES ('R-1', 'Rival 3', 'Mouse'), ('R-2', 'Apex Pro', 'Kbd');
+               INSERT INTO profiles VALUES (1, 'FPS', 'R-1'), (2, 'Typing', 'R-2');",
+            SCHEMA
+        ));
+        let live = tmp.path().join("database.db");
+        std::fs::write(&live, &pulled.db).unwrap();
+        let pairing = |remote: &str, local: &str, status| DevicePairing {
+            table: "devices".into(),
+            remote_key: remote.into(),
+            local_key: local.into(),
+            model: None,
+            status,
+        };
+        let map = DeviceMap {
+            pairings: vec![
+                pairing("R-1", "L-1", PairingStatus::Confirmed),
+                pairing("R-2", "L-2", PairingStatus::Suggested),
+            ],
+        };
+
+        assert_eq!(translate_devices(&live, &map).unwrap(), 2);
+
+        let conn = Connection::open(&live).unwrap();
+        let profiles: Vec<(String, String)> = conn
+            .prepare("SELECT p.device_serial, d.name FROM profiles p JOIN devices d ON d.serial = p.device_serial ORDER BY p.id")
+            .unwrap()
+            .query_map([], |r| Ok((r.get(0)?, r.get(1)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            profiles,
+            [
+                ("L-1".to_string(), "Mouse".to_string()),
+                ("R-2".to_string(), "Kbd".to_string()),
+            ]
+        );
+    }
+
+    fn confirmed(pairs: &[(&str, &str)]) -> DeviceMap {
+        DeviceMap {
+            pairings: pairs
+                .iter()
+                .map(|(remote, local)| DevicePairing {
+                    table: "devices".into(),
+                    remote_key: remote.to_string(),
+                    local_key: local.to_string(),
+                    model: None,
+                    status: PairingStatus::Confirmed,
+                })
+                .collect(),
+        }
+    }
+
+    fn profile_devices(conn: &Connection) -> Vec<(String, String)> {
+        conn.prepare(
+            "SELECT CAST(p.device AS TEXT), d.name FROM profiles p \
+             JOIN devices d ON d.id = p.device ORDER BY p.id",
+        )
+        .unwrap()
+        .query_map([], |r| Ok((r.get(0)?, r.get(1)?)))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap()
+    }
+
+    #[test]
+    fn test_swapped_pairings_trade_keys() {
+        let tmp = TempDir::new().unwrap();
+        let pulled = snapshot_from_sql(
+            "CREATE TABLE devices (id TEXT PRIMARY KEY, name TEXT);
+             CREATE TABLE profiles (id INTEGER PRIMARY KEY,
+                                    device TEXT REFERENCES devices(id));
+             INSERT INTO devices VALUES ('A', 'Mouse'), ('B', 'Kbd');
+             INSERT INTO profiles VALUES (1, 'A'), (2, 'B');",
+        );
+        let live = tmp.path().join("database.db");
+        std::fs::write(&live, &pulled.db).unwrap();
+
+        let map = confirmed(&[("A", "B"), ("B", "A")]);
+        assert_eq!(translate_devices(&live, &map).unwrap(), 4);
+
+        let conn = Connection::open(&live).unwrap();
+        assert_eq!(
+            profile_devices(&conn),
+            [
+                ("B".to_string(), "Mouse".to_string()),
+                ("A".to_string(), "Kbd".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_chained_pairings_move_once_and_skip_other_columns() {
+        let tmp = TempDir::new().unwrap();
+        let pulled = snapshot_from_sql(
+            "CREATE TABLE devices (id INTEGER PRIMARY KEY, name TEXT);
+             CREATE TABLE profiles (id INTEGER PRIMARY KEY,
+                                    device INTEGER REFERENCES devices(id),
+                                    device_count INTEGER);
+             INSERT INTO devices VALUES (1, 'Mouse'), (2, 'Kbd'), (3, 'Stale');
+             INSERT INTO profiles VALUES (1, 1, 1), (2, 2, 2);",
+        );
+        let live = tmp.path().join("database.db");
+        std::fs::write(&live, &pulled.db).unwrap();
+
+        let map = confirmed(&[("1", "2"), ("2", "3")]);
+        assert_eq!(translate_devices(&live, &map).unwrap(), 4);
+
+        let conn = Connection::open(&live).unwrap();
+        assert_eq!(
+            profile_devices(&conn),
+            [
+                ("2".to_string(), "Mouse".to_string()),
+                ("3".to_string(), "Kbd".to_string()),
+            ]
+        );
+        // The local device 3 gave way, and keys stay integers
+        let devices: Vec<(i64, String)> = conn
+            .prepare("SELECT id, name FROM devices ORDER BY id")
+            .unwrap()
+            .query_map([], |r| Ok((r.get(0)?, r.get(1)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(devices, [(2, "Mouse".to_string()), (3, "Kbd".to_string())]);
+        let counts: Vec<i64> = conn
+            .prepare("SELECT device_count FROM profiles ORDER BY id")
+            .unwrap()
+            .query_map([], |r| r.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(counts, [1, 2]);
+    }
+}
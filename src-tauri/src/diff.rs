@@ -0,0 +1,405 @@
+//! Row-level diff between two GG config snapshots.
+//!
+//! Both snapshots are opened as SQLite databases and compared table by table.
+//! Rows are matched by primary key (or rowid for tables without one), and each
+//! table is mapped to the GG concept it most likely holds, based on its name.
+
+use crate::providers::ConfigSnapshot;
+use crate::snapshot_db::{SnapshotDb, SnapshotDbError};
+use rusqlite::types::{Value, ValueRef};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Column names used as a human-readable label for a row, in order of preference.
+const LABEL_COLUMNS: &[&str] = &["name", "display_name", "title", "label"];
+
+/// Blobs up to this size are shown as hex; larger ones only by length.
+const MAX_HEX_BLOB: usize = 32;
+
+/// Where a snapshot to diff comes from.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum SnapshotSource {
+    /// The local GG config directory.
+    Live,
+    /// A backup, by name.
+    Backup { name: String },
+    /// The provider's current remote config.
+    Remote,
+}
+
+/// What a table holds, in GG terms.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum GgConcept {
+    Device,
+    /// Configs linked to a game or application.
+    App,
+    Profile,
+    Setting,
+    Other,
+}
+
+impl GgConcept {
+    /// Guess the concept from a table name.
+    pub(crate) fn from_table(table: &str) -> Self {
+        let name = table.to_lowercase();
+        // "app" as a whole word, so e.g. "mappings" doesn't count
+        let app_word = name
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .any(|w| matches!(w, "app" | "apps" | "application" | "applications"));
+        if name.contains("device") {
+            GgConcept::Device
+        } else if app_word {
+            GgConcept::App
+        } else if name.contains("setting") || name.contains("pref") || name.contains("option") {
+            // Before profiles, so per-profile tables like "profile_settings" count as settings
+            GgConcept::Setting
+        } else if name.contains("profile") || name.contains("config") {
+            GgConcept::Profile
+        } else {
+            GgConcept::Other
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SnapshotDiff {
+    /// Tables with at least one difference, by name.
+    pub tables: Vec<TableDiff>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TableDiff {
+    pub table: String,
+    pub concept: GgConcept,
+    pub added: Vec<RowSummary>,
+    pub removed: Vec<RowSummary>,
+    pub changed: Vec<RowChange>,
+}
+
+/// A row that exists on only one side.
+#[derive(Debug, Clone, Serialize)]
+pub struct RowSummary {
+    pub key: String,
+    pub label: Option<String>,
+    pub values: BTreeMap<String, serde_json::Value>,
+}
+
+/// A row present on both sides with different values.
+#[derive(Debug, Clone, Serialize)]
+pub struct RowChange {
+    pub key: String,
+    pub label: Option<String>,
+    pub fields: Vec<FieldChange>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldChange {
+    pub column: String,
+    pub before: serde_json::Value,
+    pub after: serde_json::Value,
+}
+
+pub(crate) type Row = BTreeMap<String, Value>;
+
+/// Compare two snapshots. `from` is the "before" side.
+pub fn diff_snapshots(
+    from: &ConfigSnapshot,
+    to: &ConfigSnapshot,
+) -> Result<SnapshotDiff, SnapshotDbError> {
+    let from = SnapshotDb::open(from)?;
+    let to = SnapshotDb::open(to)?;
+    let from_tables = table_names(from.conn())?;
+    let to_tables = table_names(to.conn())?;
+
+    let mut tables = Vec::new();
+    for table in from_tables.union(&to_tables) {
+        let before = if from_tables.contains(table) {
+            read_table(from.conn(), table)?
+        } else {
+            BTreeMap::new()
+        };
+        let after = if to_tables.contains(table) {
+            read_table(to.conn(), table)?
+        } else {
+            BTreeMap::new()
+        };
+        let diff = diff_table(table, &before, &after);
+        if !(diff.added.is_empty() && diff.removed.is_empty() && diff.changed.is_empty()) {
+            tables.push(diff);
+        }
+    }
+    Ok(SnapshotDiff { tables })
+}
+
+fn diff_table(
+    table: &str,
+    before: &BTreeMap<String, Row>,
+    after: &BTreeMap<String, Row>,
+) -> TableDiff {
+    let mut diff = TableDiff {
+        table: table.to_string(),
+        concept: GgConcept::from_table(table),
+        added: Vec::new(),
+        removed: Vec::new(),
+        changed: Vec::new(),
+    };
+    for (key, row) in after {
+        match before.get(key) {
+            None => diff.added.push(summarize(key, row)),
+            Some(old) if old != row => diff.changed.push(RowChange {
+                key: key.clone(),
+                label: row_label(row).or_else(|| row_label(old)),
+                fields: field_changes(old, row),
+            }),
+            Some(_) => {}
+        }
+    }
+    for (key, row) in before {
+        if !after.contains_key(key) {
+            diff.removed.push(summarize(key, row));
+        }
+    }
+    diff
+}
+
+fn field_changes(before: &Row, after: &Row) -> Vec<FieldChange> {
+    let columns: BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+    columns
+        .into_iter()
+        .filter_map(|column| {
+            let old = before.get(column).unwrap_or(&Value::Null);
+            let new = after.get(column).unwrap_or(&Value::Null);
+            (old != new).then(|| FieldChange {
+                column: column.clone(),
+                before: to_json(old),
+                after: to_json(new),
+            })
+        })
+        .collect()
+}
+
+fn summarize(key: &str, row: &Row) -> RowSummary {
+    RowSummary {
+        key: key.to_string(),
+        label: row_label(row),
+        values: row.iter().map(|(c, v)| (c.clone(), to_json(v))).collect(),
+    }
+}
+
+pub(crate) fn row_label(row: &Row) -> Option<String> {
+    LABEL_COLUMNS
+        .iter()
+        .find_map(|column| match row.get(*column) {
+            Some(Value::Text(text)) if !text.is_empty() => Some(text.clone()),
+            _ => None,
+        })
+}
+
+/// User tables in a snapshot (SQLite's internal tables are skipped).
+pub(crate) fn table_names(
+    conn: &rusqlite::Connection,
+) -> Result<BTreeSet<String>, SnapshotDbError> {
+    let mut stmt = conn.prepare(
+        "SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%'",
+    )?;
+    let names = stmt
+        .query_map([], |row| row.get(0))?
+        .collect::<Result<_, _>>()?;
+    Ok(names)
+}
+
+/// Primary key columns of a table, in key order. Empty if it has none.
+pub(crate) fn primary_key(
+    conn: &rusqlite::Connection,
+    table: &str,
+) -> Result<Vec<String>, SnapshotDbError> {
+    let mut pk_columns: Vec<(i64, String)> = Vec::new();
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", quote_ident(table)))?;
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        let pk: i64 = row.get("pk")?;
+        if pk > 0 {
+            pk_columns.push((pk, row.get("name")?));
+        }
+    }
+    pk_columns.sort();
+    Ok(pk_columns.into_iter().map(|(_, name)| name).collect())
+}
+
+/// Key identifying a row: its primary key values joined with `/`.
+fn row_key(row: &Row, pk_columns: &[String]) -> String {
+    pk_columns
+        .iter()
+        .map(|c| key_part(row.get(c).unwrap_or(&Value::Null)))
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// All rows of a table, keyed by primary key (or rowid if it has none).
+pub(crate) fn read_table(
+    conn: &rusqlite::Connection,
+    table: &str,
+) -> Result<BTreeMap<String, Row>, SnapshotDbError> {
+    let quoted = quote_ident(table);
+    let pk_columns = primary_key(conn, table)?;
+
+    let sql = if pk_columns.is_empty() {
+        format!("SELECT rowid AS \"__rowid\", * FROM {}", quoted)
+    } else {
+        format!("SELECT * FROM {}", quoted)
+    };
+    let mut stmt = conn.prepare(&sql)?;
+    let columns: Vec<String> = stmt
+        .column_names()
+        .into_iter()
+        .map(str::to_string)
+        .collect();
+    let mut rows = stmt.query([])?;
+    let mut result = BTreeMap::new();
+    while let Some(row) = rows.next()? {
+        let mut values = Row::new();
+        for (i, column) in columns.iter().enumerate() {
+            values.insert(column.clone(), row.get_ref(i)?.into());
+        }
+        let key = if pk_columns.is_empty() {
+            key_part(&values.remove("__rowid").unwrap_or(Value::Null))
+        } else {
+            row_key(&values, &pk_columns)
+        };
+        result.insert(key, values);
+    }
+    Ok(result)
+}
+
+pub(crate) fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+pub(crate) fn key_part(value: &Value) -> String {
+    match ValueRef::from(value) {
+        ValueRef::Null => "null".to_string(),
+        ValueRef::Integer(i) => i.to_string(),
+        ValueRef::Real(f) => f.to_string(),
+        ValueRef::Text(t) => String::from_utf8_lossy(t).to_string(),
+        ValueRef::Blob(b) => hex(b),
+    }
+}
+
+pub(crate) fn to_json(value: &Value) -> serde_json::Value {
+    match value {
+        Value::Null => serde_json::Value::Null,
+        Value::Integer(i) => (*i).into(),
+        Value::Real(f) => (*f).into(),
+        Value::Text(t) => t.clone().into(),
+        Value::Blob(b) if b.len() <= MAX_HEX_BLOB => format!("x'{}'", hex(b)).into(),
+        Value::Blob(b) => format!("<{} byte blob>", b.len()).into(),
+    }
+}
+
+pub(crate) fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Decode lowercase or uppercase hex; `None` on odd length or bad digits.
+pub(crate) fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snapshot_db::tests::snapshot_from_sql;
+
+    const SCHEMA: &str = "
+        CREATE TABLE devices (id INTEGER PRIMARY KEY, name TEXT, dpi INTEGER);
+        CREATE TABLE profile_settings (profile TEXT, key TEXT, value TEXT,
+                                       PRIMARY KEY (profile, key));
+        CREATE TABLE log (message TEXT);";
+
+    #[test]
+    fn test_diff_reports_added_removed_and_changed_rows() {
+        let before = snapshot_from_sql(&format!(
+            "{}
+             INSERT INTO devices VALUES (1, 'Rival 3', 800), (2, 'Apex Pro', NULL);
+             INSERT INTO profile_settings VALUES ('fps', 'brightness', '50');
+             INSERT INTO log VALUES ('a');",
+            SCHEMA
+        ));
+        let after = snapshot_from_sql(&format!(
+            "{}
+             INSERT INTO devices VALUES (1, 'Rival 3', 1600), (3, 'Arctis 7', NULL);
+             INSERT INTO profile_settings VALUES ('fps', 'brightness', '50');
+             INSERT INTO log VALUES ('a');",
+            SCHEMA
+        ));
+
+        let diff = diff_snapshots(&before, &after).unwrap();
+
+        assert_eq!(diff.tables.len(), 1);
+        let devices = &diff.tables[0];
+        assert_eq!(devices.concept, GgConcept::Device);
+        assert_eq!(devices.added[0].label.as_deref(), Some("Arctis 7"));
+        assert_eq!(devices.removed[0].key, "2");
+        let change = &devices.changed[0];
+        assert_eq!(change.label.as_deref(), Some("Rival 3"));
+        assert_eq!(change.fields.len(), 1);
+        assert_eq!(change.fields[0].column, "dpi");
+        assert_eq!(change.fields[0].before, 800);
+        assert_eq!(change.fields[0].after, 1600);
+    }
+
+    #[test]
+    fn test_diff_handles_composite_keys_and_missing_tables() {
+        let before = snapshot_from_sql(&format!(
+            "{} INSERT INTO profile_settings VALUES ('fps', 'brightness', '50');",
+            SCHEMA
+        ));
+        let after = snapshot_from_sql(
+            "CREATE TABLE profile_settings (profile TEXT, key TEXT, value TEXT,
+                                            PRIMARY KEY (profile, key));
+             INSERT INTO profile_settings VALUES ('fps', 'brightness', '80');
+             CREATE TABLE macros (id INTEGER PRIMARY KEY, body BLOB);
+             INSERT INTO macros VALUES (1, x'00ff');",
+        );
+
+        let diff = diff_snapshots(&before, &after).unwrap();
+
+        let tables: Vec<_> = diff.tables.iter().map(|t| t.table.as_str()).collect();
+        assert_eq!(tables, ["macros", "profile_settings"]);
+        assert_eq!(diff.tables[0].added[0].values["body"], "x'00ff'");
+        let settings = &diff.tables[1];
+        assert_eq!(settings.concept, GgConcept::Setting);
+        assert_eq!(settings.changed[0].key, "fps/brightness");
+    }
+
+    #[test]
+    fn test_concept_from_table() {
+        assert_eq!(GgConcept::from_table("Devices"), GgConcept::Device);
+        assert_eq!(GgConcept::from_table("app_configs"), GgConcept::App);
+        assert_eq!(GgConcept::from_table("key_mappings"), GgConcept::Other);
+        assert_eq!(GgConcept::from_table("profiles"), GgConcept::Profile);
+        assert_eq!(GgConcept::from_table("user_prefs"), GgConcept::Setting);
+        assert_eq!(
+            GgConcept::from_table("profile_settings"),
+            GgConcept::Setting
+        );
+    }
+
+    #[test]
+    fn test_identical_snapshots_have_empty_diff() {
+        let snapshot =
+            snapshot_from_sql(&format!("{} INSERT INTO log VALUES ('a'), ('b');", SCHEMA));
+        assert!(diff_snapshots(&snapshot, &snapshot)
+            .unwrap()
+            .tables
+            .is_empty());
+    }
+}
@@ -0,0 +1,152 @@
+//! Persistent log of completed syncs and what each one changed.
+
+use crate::archive::{read_archive, write_archive};
+use crate::providers::ConfigSnapshot;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+pub const HISTORY_FILE: &str = "sync_history.json";
+pub const LAST_SYNCED_FILE: &str = "last_synced.ggsync";
+
+/// Oldest entries are dropped beyond this count.
+const MAX_ENTRIES: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SyncDirection {
+    Push,
+    Pull,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncHistoryEntry {
+    pub at: DateTime<Utc>,
+    pub direction: SyncDirection,
+    /// Device the config came from.
+    pub device: String,
+    /// Human-readable change summary, one line per change.
+    pub changes: Vec<String>,
+}
+
+pub struct SyncHistory {
+    path: PathBuf,
+}
+
+impl SyncHistory {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// Recorded syncs, newest first. A missing or unreadable file is empty.
+    pub fn entries(&self) -> Vec<SyncHistoryEntry> {
+        fs::read_to_string(&self.path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn record(&self, entry: SyncHistoryEntry) -> std::io::Result<()> {
+        let mut entries = self.entries();
+        entries.insert(0, entry);
+        entries.truncate(MAX_ENTRIES);
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(&entries).map_err(std::io::Error::other)?;
+        let tmp = self.path.with_extension("json.tmp");
+        fs::write(&tmp, json)?;
+        fs::rename(tmp, &self.path)
+    }
+}
+
+/// The config as of the last push or pull, to summarize the next one against.
+/// Kept as an archive on disk so the first sync after a restart is summarized
+/// too.
+pub struct LastSynced {
+    path: PathBuf,
+    cached: Mutex<Option<ConfigSnapshot>>,
+}
+
+impl LastSynced {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            cached: Mutex::new(None),
+        }
+    }
+
+    /// The last synced config. A missing or unreadable file is `None`.
+    pub fn get(&self) -> Option<ConfigSnapshot> {
+        let mut cached = self.cached.lock().unwrap_or_else(|e| e.into_inner());
+        if cached.is_none() {
+            *cached = fs::read(&self.path)
+                .ok()
+                .and_then(|data| read_archive(&data, None).ok())
+                .map(|(snapshot, _)| snapshot);
+        }
+        cached.clone()
+    }
+
+    pub fn set(&self, snapshot: &ConfigSnapshot) -> std::io::Result<()> {
+        *self.cached.lock().unwrap_or_else(|e| e.into_inner()) = Some(snapshot.clone());
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let tmp = self.path.with_extension("ggsync.tmp");
+        fs::write(&tmp, write_archive(snapshot, None))?;
+        fs::rename(tmp, &self.path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_record_newest_first_and_capped() {
+        let tmp = TempDir::new().unwrap();
+        let history = SyncHistory::new(tmp.path().join(HISTORY_FILE));
+        assert!(history.entries().is_empty());
+
+        for i in 0..MAX_ENTRIES + 5 {
+            history
+                .record(SyncHistoryEntry {
+                    at: Utc::now(),
+                    direction: SyncDirection::Pull,
+                    device: format!("pc-{}", i),
+                    changes: vec![],
+                })
+                .unwrap();
+        }
+
+        let entries = history.entries();
+        assert_eq!(entries.len(), MAX_ENTRIES);
+        assert_eq!(entries[0].device, format!("pc-{}", MAX_ENTRIES + 4));
+    }
+
+    #[test]
+    fn test_last_synced_survives_restart() {
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join(LAST_SYNCED_FILE);
+        assert!(LastSynced::new(path.clone()).get().is_none());
+
+        let snapshot = ConfigSnapshot {
+            db: b"SQLite format 3\0db".to_vec(),
+            db_shm: None,
+            db_wal: Some(b"wal".to_vec()),
+            meta: crate::providers::SyncMeta {
+                last_modified: Utc::now(),
+                device_name: "this-pc".into(),
+            },
+        };
+        LastSynced::new(path.clone()).set(&snapshot).unwrap();
+
+        let loaded = LastSynced::new(path).get().unwrap();
+        assert_eq!(loaded.db, snapshot.db);
+        assert_eq!(loaded.db_wal, snapshot.db_wal);
+    }
+}
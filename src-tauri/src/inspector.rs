@@ -0,0 +1,326 @@
+//! Structured, read-only view of what a GG config snapshot contains.
+//!
+//! GG's schema is undocumented and changes between releases, so nothing here
+//! assumes fixed table names. Tables are classified with `GgConcept`, and rows
+//! are linked through columns whose names mention the other concept (e.g. a
+//! `profile_id` on a device or app row). Every table is listed with its row
+//! count even when it can't be interpreted, and tables that fail to read are
+//! reported as warnings rather than failing the whole inspection.
+
+use crate::diff::{
+    key_part, primary_key, read_table, row_label, table_names, to_json, GgConcept, Row,
+};
+use crate::providers::ConfigSnapshot;
+use crate::safety::sqlite_user_version;
+use crate::snapshot_db::{SnapshotDb, SnapshotDbError};
+use rusqlite::types::Value;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Column names holding a setting's name in key/value settings tables.
+const SETTING_KEY_COLUMNS: &[&str] = &["key", "name", "setting"];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Inspection {
+    /// SQLite `user_version` of the database, if set.
+    pub schema_version: Option<u32>,
+    pub devices: Vec<DeviceInfo>,
+    pub profiles: Vec<ProfileInfo>,
+    pub tables: Vec<TableInfo>,
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TableInfo {
+    pub name: String,
+    pub concept: GgConcept,
+    pub rows: usize,
+    /// Whether the table's rows are reflected in `devices` or `profiles`.
+    pub interpreted: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DeviceInfo {
+    pub table: String,
+    pub key: String,
+    pub name: Option<String>,
+    /// Key of the profile active on this device, if one could be linked.
+    pub active_profile: Option<String>,
+    /// Remaining columns of the device row.
+    pub details: BTreeMap<String, serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProfileInfo {
+    pub table: String,
+    pub key: String,
+    pub name: Option<String>,
+    /// Key of the device the profile belongs to, if one could be linked.
+    pub device: Option<String>,
+    /// Games/applications this profile is linked to.
+    pub apps: Vec<String>,
+    pub settings: BTreeMap<String, serde_json::Value>,
+}
+
+/// A readable table and its rows.
+struct Table {
+    name: String,
+    concept: GgConcept,
+    pk: Vec<String>,
+    rows: BTreeMap<String, Row>,
+}
+
+impl Table {
+    fn is_entity(&self) -> bool {
+        self.pk.len() == 1
+    }
+}
+
+/// Inspect a snapshot. Fails only if it can't be opened as a database.
+pub fn inspect_snapshot(snapshot: &ConfigSnapshot) -> Result<Inspection, SnapshotDbError> {
+    let db = SnapshotDb::open(snapshot)?;
+    let mut inspection = Inspection {
+        schema_version: sqlite_user_version(&snapshot.db),
+        devices: Vec::new(),
+        profiles: Vec::new(),
+        tables: Vec::new(),
+        warnings: Vec::new(),
+    };
+
+    let mut tables = Vec::new();
+    for name in table_names(db.conn())? {
+        let read =
+            primary_key(db.conn(), &name).and_then(|pk| Ok((pk, read_table(db.conn(), &name)?)));
+        match read {
+            Ok((pk, rows)) => tables.push(Table {
+                concept: GgConcept::from_table(&name),
+                name,
+                pk,
+                rows,
+            }),
+            Err(e) => inspection
+                .warnings
+                .push(format!("Could not read {}: {}", name, e)),
+        }
+    }
+    // Entities are addressed by a single-column key, which is what links refer to
+    let entities = |concept| {
+        tables
+            .iter()
+            .filter(move |t| t.concept == concept && t.is_entity())
+    };
+    let profile_keys: BTreeSet<&String> = entities(GgConcept::Profile)
+        .flat_map(|t| t.rows.keys())
+        .collect();
+    let device_keys: BTreeSet<&String> = entities(GgConcept::Device)
+        .flat_map(|t| t.rows.keys())
+        .collect();
+
+    for table in entities(GgConcept::Profile) {
+        for (key, row) in &table.rows {
+            inspection.profiles.push(ProfileInfo {
+                table: table.name.clone(),
+                key: key.clone(),
+                name: row_label(row),
+                device: linked_key(row, "device", &device_keys),
+                apps: Vec::new(),
+                settings: plain_columns(row, &table.pk),
+            });
+        }
+    }
+
+    for table in entities(GgConcept::Device) {
+        for (key, row) in &table.rows {
+            inspection.devices.push(DeviceInfo {
+                table: table.name.clone(),
+                key: key.clone(),
+                name: row_label(row),
+                active_profile: linked_key(row, "profile", &profile_keys),
+                details: plain_columns(row, &table.pk),
+            });
+        }
+    }
+
+    for table in &tables {
+        let interpreted = if table.concept == GgConcept::App {
+            let mut linked = false;
+            for (key, row) in &table.rows {
+                if let Some(profile) = linked_key(row, "profile", &profile_keys) {
+                    let app = row_label(row).unwrap_or_else(|| key.clone());
+                    profiles_with_key(&mut inspection.profiles, &profile)
+                        .for_each(|p| p.apps.push(app.clone()));
+                    linked = true;
+                }
+            }
+            linked
+        } else if table.is_entity() {
+            matches!(table.concept, GgConcept::Device | GgConcept::Profile)
+        } else {
+            link_rows(table, &mut inspection, &profile_keys, &device_keys)
+        };
+        inspection.tables.push(TableInfo {
+            name: table.name.clone(),
+            concept: table.concept,
+            rows: table.rows.len(),
+            interpreted,
+        });
+    }
+    Ok(inspection)
+}
+
+/// Pick up rows of a table that aren't entities themselves: per-profile
+/// key/value settings, and device/profile link tables with an "active" flag.
+/// Returns whether any row was linked.
+fn link_rows(
+    table: &Table,
+    inspection: &mut Inspection,
+    profile_keys: &BTreeSet<&String>,
+    device_keys: &BTreeSet<&String>,
+) -> bool {
+    let mut linked = false;
+    for row in table.rows.values() {
+        let Some(profile) = linked_key(row, "profile", profile_keys) else {
+            continue;
+        };
+        let setting_name = SETTING_KEY_COLUMNS.iter().find_map(|c| match row.get(*c) {
+            Some(Value::Text(name)) => Some(name.clone()),
+            _ => None,
+        });
+        if let (Some(name), Some(value)) = (setting_name, row.get("value")) {
+            for p in profiles_with_key(&mut inspection.profiles, &profile) {
+                p.settings.insert(name.clone(), to_json(value));
+            }
+            linked = true;
+            continue;
+        }
+
+        let active = row
+            .iter()
+            .any(|(c, v)| c.to_lowercase().contains("active") && is_truthy(v));
+        if let (true, Some(device)) = (active, linked_key(row, "device", device_keys)) {
+            for d in inspection.devices.iter_mut().filter(|d| d.key == device) {
+                d.active_profile.get_or_insert_with(|| profile.clone());
+            }
+            linked = true;
+        }
+    }
+    linked
+}
+
+fn profiles_with_key<'a>(
+    profiles: &'a mut [ProfileInfo],
+    key: &'a str,
+) -> impl Iterator<Item = &'a mut ProfileInfo> {
+    profiles.iter_mut().filter(move |p| p.key == key)
+}
+
+/// The first column whose name mentions `concept` and whose value is one of
+/// `keys`. Columns mentioning "active" are preferred (e.g. `active_profile_id`
+/// over `default_profile_id`).
+fn linked_key(row: &Row, concept: &str, keys: &BTreeSet<&String>) -> Option<String> {
+    let mut candidates: Vec<(&String, &Value)> = row
+        .iter()
+        .filter(|(c, v)| c.to_lowercase().contains(concept) && !matches!(v, Value::Null))
+        .collect();
+    candidates.sort_by_key(|(c, _)| !c.to_lowercase().contains("active"));
+    candidates
+        .into_iter()
+        .map(|(_, v)| key_part(v))
+        .find(|k| keys.contains(k))
+}
+
+/// Row columns other than its key, label and links to devices or profiles.
+fn plain_columns(row: &Row, pk: &[String]) -> BTreeMap<String, serde_json::Value> {
+    let label = row_label(row);
+    row.iter()
+        .filter(|(c, v)| {
+            let lower = c.to_lowercase();
+            !lower.contains("device")
+                && !lower.contains("profile")
+                && !pk.contains(c)
+                && !matches!((v, &label), (Value::Text(t), Some(l)) if t == l)
+        })
+        .map(|(c, v)| (c.clone(), to_json(v)))
+        .collect()
+}
+
+fn is_truthy(value: &Value) -> bool {
+    match value {
+        Value::Integer(i) => *i != 0,
+        Value::Text(t) => matches!(t.to_lowercase().as_str(), "1" | "true" | "yes"),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snapshot_db::tests::snapshot_from_sql;
+
+    #[test]
+    fn test_inspect_devices_profiles_and_apps() {
+        let snapshot = snapshot_from_sql(
+            "PRAGMA user_version = 12;
+             CREATE TABLE devices (id INTEGER PRIMARY KEY, name TEXT, active_profile_id INTEGER,
+                                   firmware TEXT);
+             CREATE TABLE profiles (id INTEGER PRIMARY KEY, name TEXT, device_id INTEGER,
+                                    dpi INTEGER);
+             CREATE TABLE profile_settings (profile_id INTEGER, key TEXT, value TEXT,
+                                            PRIMARY KEY (profile_id, key));
+             CREATE TABLE apps (id INTEGER PRIMARY KEY, name TEXT, profile_id INTEGER);
+             INSERT INTO devices VALUES (1, 'Rival 3', 11, '1.2.0'), (2, 'Apex Pro', NULL, '3.0');
+             INSERT INTO profiles VALUES (10, 'Default', 2, 400), (11, 'FPS', 1, 800);
+             INSERT INTO profile_settings VALUES (11, 'rgb', 'red');
+             INSERT INTO apps VALUES (1, 'Valorant', 11), (2, 'Notepad', NULL);",
+        );
+
+        let inspection = inspect_snapshot(&snapshot).unwrap();
+
+        assert_eq!(inspection.schema_version, Some(12));
+        assert_eq!(inspection.devices.len(), 2);
+        let rival = &inspection.devices[0];
+        assert_eq!(rival.name.as_deref(), Some("Rival 3"));
+        assert_eq!(rival.active_profile.as_deref(), Some("11"));
+        assert_eq!(rival.details["firmware"], "1.2.0");
+        assert!(inspection.devices[1].active_profile.is_none());
+
+        let fps = inspection.profiles.iter().find(|p| p.key == "11").unwrap();
+        assert_eq!(fps.device.as_deref(), Some("1"));
+        assert_eq!(fps.apps, ["Valorant"]);
+        assert_eq!(fps.settings["dpi"], 800);
+        assert_eq!(fps.settings["rgb"], "red");
+        assert_eq!(inspection.tables.len(), 4);
+        assert!(inspection.tables.iter().all(|t| t.interpreted));
+    }
+
+    #[test]
+    fn test_active_profile_from_link_table() {
+        let snapshot = snapshot_from_sql(
+            "CREATE TABLE device (serial TEXT PRIMARY KEY, name TEXT);
+             CREATE TABLE profile (id INTEGER PRIMARY KEY, name TEXT);
+             CREATE TABLE device_profile (device_serial TEXT, profile_id INTEGER, is_active INTEGER);
+             INSERT INTO device VALUES ('ABC', 'Arctis');
+             INSERT INTO profile VALUES (1, 'Music'), (2, 'Games');
+             INSERT INTO device_profile VALUES ('ABC', 1, 0), ('ABC', 2, 1);",
+        );
+
+        let inspection = inspect_snapshot(&snapshot).unwrap();
+
+        assert_eq!(inspection.devices[0].active_profile.as_deref(), Some("2"));
+    }
+
+    #[test]
+    fn test_unknown_schema_is_listed_not_rejected() {
+        let snapshot =
+            snapshot_from_sql("CREATE TABLE blobs (data BLOB); INSERT INTO blobs VALUES (x'00');");
+
+        let inspection = inspect_snapshot(&snapshot).unwrap();
+
+        assert!(inspection.devices.is_empty());
+        assert!(inspection.profiles.is_empty());
+        assert_eq!(inspection.tables.len(), 1);
+        assert_eq!(inspection.tables[0].concept, GgConcept::Other);
+        assert_eq!(inspection.tables[0].rows, 1);
+        assert!(!inspection.tables[0].interpreted);
+    }
+}
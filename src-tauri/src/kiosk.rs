@@ -0,0 +1,125 @@
+//! Kiosk mode — every session on a shared machine starts from a golden config.
+//!
+//! The golden backup is restored when the app starts and whenever GG exits, so
+//! whatever the previous player changed is gone by the time the next one
+//! launches GG. Pushes are skipped so a player's changes never reach other
+//! machines. Staff can lift both for the running session with the override
+//! password, whose salted PBKDF2 hash is all that is stored in the config.
+
+use crate::diff::hex;
+use crate::sync_engine::{SyncEngine, SyncError, SyncResult};
+use sha2::Sha256;
+use std::sync::Arc;
+use subtle::ConstantTimeEq;
+use ulid::Ulid;
+
+/// Prefix identifying the hash scheme, so the parameters can change later.
+const HASH_SCHEME: &str = "pbkdf2-sha256";
+
+/// PBKDF2 rounds for new hashes. Slow enough to make guessing a leaked hash
+/// expensive, while unlocking still takes well under a second.
+const HASH_ITERATIONS: u32 = 210_000;
+
+/// Highest round count accepted from a stored hash, so a hand-edited config
+/// can't make unlocking hang.
+const MAX_HASH_ITERATIONS: u32 = 2_000_000;
+
+/// Hash an override password for `KioskConfig::override_password_hash`,
+/// as `pbkdf2-sha256$<iterations>$<salt>$<hex key>`.
+pub fn hash_password(password: &str) -> String {
+    let salt = Ulid::new().to_string();
+    let key = derive_key(password, &salt, HASH_ITERATIONS);
+    format!("{}${}${}${}", HASH_SCHEME, HASH_ITERATIONS, salt, hex(&key))
+}
+
+/// Whether `password` matches a hash made by `hash_password`. The derived
+/// key is compared in constant time.
+pub fn verify_password(hash: &str, password: &str) -> bool {
+    let mut parts = hash.split('$');
+    let (Some(HASH_SCHEME), Some(iterations), Some(salt), Some(expected), None) = (
+        parts.next(),
+        parts.next(),
+        parts.next(),
+        parts.next(),
+        parts.next(),
+    ) else {
+        return false;
+    };
+    let Ok(iterations) = iterations.parse::<u32>() else {
+        return false;
+    };
+    if iterations == 0 || iterations > MAX_HASH_ITERATIONS {
+        return false;
+    }
+    let key = hex(&derive_key(password, salt, iterations));
+    key.as_bytes()
+        .ct_eq(expected.to_ascii_lowercase().as_bytes())
+        .into()
+}
+
+fn derive_key(password: &str, salt: &str, iterations: u32) -> [u8; 32] {
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<Sha256>(password.as_bytes(), salt.as_bytes(), iterations, &mut key);
+    key
+}
+
+/// Restores the golden backup each time GG exits.
+pub struct KioskWatcher {
+    engine: Arc<SyncEngine>,
+    gg_was_running: bool,
+}
+
+impl KioskWatcher {
+    pub fn new(engine: Arc<SyncEngine>) -> Self {
+        Self {
+            engine,
+            gg_was_running: false,
+        }
+    }
+
+    /// Check whether GG exited since the last tick and restore the golden
+    /// backup if it did. Returns the restore result, if one was attempted.
+    pub async fn tick(&mut self) -> Result<Option<SyncResult>, SyncError> {
+        let gg_running = self.engine.is_gg_running().await;
+        let gg_exited = self.gg_was_running && !gg_running;
+        self.gg_was_running = gg_running;
+        if !gg_exited {
+            return Ok(None);
+        }
+        self.engine.restore_golden().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_password_hash() {
+        let hash = hash_password("letmein");
+        assert!(verify_password(&hash, "letmein"));
+        assert!(!verify_password(&hash, "LetMeIn"));
+        assert!(!verify_password("not-a-hash", "letmein"));
+        assert!(hash.starts_with("pbkdf2-sha256$210000$"));
+        // The old single-round format is not accepted
+        let salt = hash.split('$').nth(2).unwrap();
+        let sha = crate::backup::sha256_hex(format!("{}letmein", salt).as_bytes());
+        assert!(!verify_password(&format!("{}${}", salt, sha), "letmein"));
+        // Salted: the same password hashes differently each time
+        assert_ne!(hash, hash_password("letmein"));
+    }
+
+    #[test]
+    fn test_password_hash_uses_stored_iterations() {
+        let key = derive_key("letmein", "salt", 1000);
+        let hash = format!("pbkdf2-sha256$1000$salt${}", hex(&key));
+        assert!(verify_password(&hash, "letmein"));
+        assert!(!verify_password("pbkdf2-sha256$0$salt$00", "letmein"));
+        assert!(!verify_password(
+            "pbkdf2-sha256$4294967295$salt$00",
+            "letmein"
+        ));
+        let wrong_rounds = format!("pbkdf2-sha256$1001$salt${}", hex(&key));
+        assert!(!verify_password(&wrong_rounds, "letmein"));
+    }
+}
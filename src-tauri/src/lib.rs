@@ -1,12 +1,26 @@
+mod archive;
 mod backup;
+mod changes;
 mod config;
+mod device_map;
+mod diff;
+mod history;
+mod inspector;
+mod kiosk;
+mod partial_restore;
+mod path_mapping;
+mod profile_export;
 mod providers;
 mod safety;
+mod scheduler;
+mod setting_history;
+mod snapshot_db;
 mod sync_engine;
+mod transform;
 pub mod tray;
 mod watcher;
 
-use config::{AppConfig, ProviderConfig, load_config, save_config_to_disk};
+use config::{load_config, save_config_to_disk, AppConfig, ProviderConfig};
 use providers::folder::FolderProvider;
 use providers::hosted::HostedProvider;
 use providers::SyncProvider;
@@ -23,16 +37,15 @@ struct AppState {
 
 fn build_provider(config: &AppConfig) -> Arc<dyn SyncProvider> {
     match &config.provider {
-        ProviderConfig::Folder { sync_dir } => {
-            Arc::new(FolderProvider::new(sync_dir.clone(), config.device_name.clone()))
-        }
-        ProviderConfig::Hosted { api_url, api_key } => {
-            Arc::new(HostedProvider::new(
-                api_url.clone(),
-                api_key.clone(),
-                config.device_name.clone(),
-            ))
-        }
+        ProviderConfig::Folder { sync_dir } => Arc::new(FolderProvider::new(
+            sync_dir.clone(),
+            config.device_name.clone(),
+        )),
+        ProviderConfig::Hosted { api_url, api_key } => Arc::new(HostedProvider::new(
+            api_url.clone(),
+            api_key.clone(),
+            config.device_name.clone(),
+        )),
     }
 }
 
@@ -54,6 +67,7 @@ async fn push_now(state: State<'_, AppState>) -> Result<String, String> {
 
 #[tauri::command]
 async fn pull_now(state: State<'_, AppState>) -> Result<String, String> {
+    ensure_unlocked(&state.engine)?;
     match state.engine.pull_from_remote().await {
         Ok(result) => Ok(format_sync_result(&result)),
         Err(e) => Err(e.to_string()),
@@ -62,36 +76,318 @@ async fn pull_now(state: State<'_, AppState>) -> Result<String, String> {
 
 fn format_sync_result(result: &sync_engine::SyncResult) -> String {
     match result {
-        sync_engine::SyncResult::Pushed => "Pushed".to_string(),
-        sync_engine::SyncResult::Pulled { from_device, gg_was_running } => {
-            if *gg_was_running {
-                format!("Pulled from {}. Restart SteelSeries GG to apply changes.", from_device)
+        sync_engine::SyncResult::Pushed { changes } => with_changes("Pushed".to_string(), changes),
+        sync_engine::SyncResult::Pulled {
+            from_device,
+            gg_was_running,
+            gg_restarted,
+            changes,
+        } => {
+            let summary = if *gg_restarted {
+                format!("Pulled from {}. SteelSeries GG was restarted.", from_device)
+            } else if *gg_was_running {
+                format!(
+                    "Pulled from {}. Restart SteelSeries GG to apply changes.",
+                    from_device
+                )
             } else {
                 format!("Pulled from {}", from_device)
+            };
+            with_changes(summary, changes)
+        }
+        sync_engine::SyncResult::Restored {
+            backup_id,
+            gg_restarted,
+        } => {
+            if *gg_restarted {
+                format!(
+                    "Restored backup '{}'. SteelSeries GG was restarted.",
+                    backup_id
+                )
+            } else {
+                format!("Restored backup '{}'", backup_id)
             }
         }
+        sync_engine::SyncResult::ProfilesImported {
+            file,
+            profiles,
+            gg_restarted,
+        } => {
+            if *gg_restarted {
+                format!(
+                    "Imported {} profile(s) from '{}'. SteelSeries GG was restarted.",
+                    profiles, file
+                )
+            } else {
+                format!("Imported {} profile(s) from '{}'", profiles, file)
+            }
+        }
+        sync_engine::SyncResult::PartiallyRestored {
+            backup_id,
+            rows,
+            gg_restarted,
+        } => {
+            if *gg_restarted {
+                format!(
+                    "Restored {} row(s) from backup '{}'. SteelSeries GG was restarted.",
+                    rows, backup_id
+                )
+            } else {
+                format!("Restored {} row(s) from backup '{}'", rows, backup_id)
+            }
+        }
+        sync_engine::SyncResult::Imported { backup_id } => {
+            format!("Imported as backup '{}'", backup_id)
+        }
         sync_engine::SyncResult::Skipped(reason) => format!("Skipped({:?})", reason),
     }
 }
 
+/// Most change lines shown in a status message; the full list is in the history.
+const MAX_STATUS_CHANGES: usize = 5;
+
+fn with_changes(summary: String, changes: &[String]) -> String {
+    if changes.is_empty() {
+        return summary;
+    }
+    let mut message = summary;
+    for change in changes.iter().take(MAX_STATUS_CHANGES) {
+        message.push_str(&format!("\n• {}", change));
+    }
+    if changes.len() > MAX_STATUS_CHANGES {
+        message.push_str(&format!(
+            "\n… and {} more",
+            changes.len() - MAX_STATUS_CHANGES
+        ));
+    }
+    message
+}
+
+#[tauri::command]
+fn sync_history(state: State<'_, AppState>) -> Vec<history::SyncHistoryEntry> {
+    state.engine.sync_history()
+}
+
+#[tauri::command]
+fn get_device_map(state: State<'_, AppState>) -> device_map::DeviceMap {
+    state.engine.device_map()
+}
+
+#[tauri::command]
+fn save_device_map(state: State<'_, AppState>, map: device_map::DeviceMap) -> Result<(), String> {
+    ensure_unlocked(&state.engine)?;
+    state
+        .engine
+        .save_device_map(&map)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn refresh_device_map(state: State<'_, AppState>) -> Result<device_map::DeviceMap, String> {
+    state
+        .engine
+        .refresh_device_map()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn export_profiles(
+    state: State<'_, AppState>,
+    source: diff::SnapshotSource,
+    units: Vec<partial_restore::UnitRef>,
+    path: String,
+) -> Result<usize, String> {
+    state
+        .engine
+        .export_profiles(&source, units, std::path::Path::new(&path))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn import_profiles(state: State<'_, AppState>, path: String) -> Result<String, String> {
+    ensure_unlocked(&state.engine)?;
+    state
+        .engine
+        .import_profiles(std::path::Path::new(&path))
+        .await
+        .map(|result| format_sync_result(&result))
+        .map_err(|e| e.to_string())
+}
+
 #[tauri::command]
-async fn list_backups(state: State<'_, AppState>) -> Result<Vec<String>, String> {
+async fn setting_history(
+    state: State<'_, AppState>,
+    query: setting_history::SettingQuery,
+    include_remote: bool,
+) -> Result<Vec<setting_history::SettingChange>, String> {
+    state
+        .engine
+        .setting_history(query, include_remote)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn list_backups(state: State<'_, AppState>) -> Result<backup::BackupList, String> {
     state
         .engine
         .backups()
-        .list_backups()
-        .map(|entries| entries.iter().map(|e| e.name.clone()).collect())
+        .list_with_usage()
         .map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+async fn pin_backup(
+    state: State<'_, AppState>,
+    backup_name: String,
+    pinned: bool,
+) -> Result<(), String> {
+    ensure_unlocked(&state.engine)?;
+    state
+        .engine
+        .backups()
+        .set_pinned(&backup_name, pinned)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn annotate_backup(
+    state: State<'_, AppState>,
+    backup_name: String,
+    note: Option<String>,
+) -> Result<(), String> {
+    ensure_unlocked(&state.engine)?;
+    state
+        .engine
+        .backups()
+        .set_note(&backup_name, note)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn verify_backups(state: State<'_, AppState>) -> Result<backup::VerifySummary, String> {
+    let engine = state.engine.clone();
+    tokio::task::spawn_blocking(move || engine.backups().verify_backups())
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn diff_snapshots(
+    state: State<'_, AppState>,
+    from: diff::SnapshotSource,
+    to: diff::SnapshotSource,
+) -> Result<diff::SnapshotDiff, String> {
+    state
+        .engine
+        .diff(&from, &to)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn inspect_snapshot(
+    state: State<'_, AppState>,
+    source: diff::SnapshotSource,
+) -> Result<inspector::Inspection, String> {
+    state
+        .engine
+        .inspect(&source)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn export_snapshot(
+    state: State<'_, AppState>,
+    source: diff::SnapshotSource,
+    path: String,
+) -> Result<(), String> {
+    state
+        .engine
+        .export_snapshot(&source, std::path::Path::new(&path))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn import_snapshot(
+    state: State<'_, AppState>,
+    path: String,
+    apply: bool,
+) -> Result<String, String> {
+    if apply {
+        ensure_unlocked(&state.engine)?;
+    }
+    match state
+        .engine
+        .import_snapshot(std::path::Path::new(&path), apply)
+        .await
+    {
+        Ok(sync_engine::SyncResult::Skipped(sync_engine::SkipReason::GGRunning)) => Ok(
+            "Imported. SteelSeries GG is running; the config will be applied once it exits."
+                .to_string(),
+        ),
+        Ok(result) => Ok(format_sync_result(&result)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+#[tauri::command]
+async fn list_remote_backups(
+    state: State<'_, AppState>,
+) -> Result<Vec<providers::RemoteBackup>, String> {
+    state
+        .engine
+        .list_remote_backups()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn restore_remote_backup(
+    state: State<'_, AppState>,
+    backup_id: String,
+) -> Result<String, String> {
+    ensure_unlocked(&state.engine)?;
+    match state.engine.restore_remote_backup(&backup_id).await {
+        Ok(sync_engine::SyncResult::Skipped(sync_engine::SkipReason::GGRunning)) => Ok(
+            "Downloaded. SteelSeries GG is running; the backup will be restored once it exits."
+                .to_string(),
+        ),
+        Ok(result) => Ok(format_sync_result(&result)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
 #[tauri::command]
 async fn get_config(state: State<'_, AppState>) -> Result<String, String> {
     let config = state.config.lock().await;
     serde_json::to_string(&*config).map_err(|e| e.to_string())
 }
 
+/// Refuse changes to the live config, backups, config sets and device pairings
+/// while kiosk mode is locked.
+fn ensure_unlocked(engine: &SyncEngine) -> Result<(), String> {
+    if engine.kiosk_locked() {
+        return Err("Kiosk mode is on. Unlock it with the override password first.".to_string());
+    }
+    Ok(())
+}
+
 #[tauri::command]
 async fn save_config(state: State<'_, AppState>, config: AppConfig) -> Result<(), String> {
+    ensure_unlocked(&state.engine)?;
+    if let Some(golden) = &config.kiosk.golden_backup {
+        state
+            .engine
+            .backups()
+            .set_pinned(golden, true)
+            .map_err(|e| e.to_string())?;
+    }
     save_config_to_disk(&config).map_err(|e| e.to_string())?;
     let mut current = state.config.lock().await;
     *current = config;
@@ -99,21 +395,181 @@ async fn save_config(state: State<'_, AppState>, config: AppConfig) -> Result<()
 }
 
 #[tauri::command]
-async fn restore_backup(
+fn list_backup_units(
+    state: State<'_, AppState>,
+    backup_name: String,
+) -> Result<Vec<partial_restore::RestoreUnit>, String> {
+    state
+        .engine
+        .backup_units(&backup_name)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn restore_backup_units(
     state: State<'_, AppState>,
     backup_name: String,
+    units: Vec<partial_restore::UnitRef>,
 ) -> Result<String, String> {
-    let config = state.config.lock().await;
-    let backup_dir = config.backup_dir.join(&backup_name);
-    if !backup_dir.exists() {
-        return Err(format!("Backup '{}' not found", backup_name));
+    ensure_unlocked(&state.engine)?;
+    state
+        .engine
+        .restore_units(&backup_name, &units)
+        .await
+        .map(|result| format_sync_result(&result))
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn restore_backup(state: State<'_, AppState>, backup_name: String) -> Result<String, String> {
+    ensure_unlocked(&state.engine)?;
+    match state.engine.restore_backup(&backup_name).await {
+        Ok(sync_engine::SyncResult::Skipped(sync_engine::SkipReason::GGRunning)) => {
+            Ok("SteelSeries GG is running. The restore will be applied once it exits.".to_string())
+        }
+        Ok(result) => Ok(format_sync_result(&result)),
+        Err(e) => Err(e.to_string()),
     }
+}
+
+/// Names of the saved config sets, for the tray menu.
+fn config_set_names(engine: &SyncEngine) -> Vec<String> {
+    engine
+        .backups()
+        .config_sets()
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|b| b.config_set().map(str::to_string))
+        .collect()
+}
+
+#[tauri::command]
+fn list_config_sets(state: State<'_, AppState>) -> Result<Vec<backup::BackupEntry>, String> {
     state
         .engine
         .backups()
-        .restore_backup(&backup_dir, &config.steelseries_db_path)
+        .config_sets()
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn save_config_set(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+    name: String,
+) -> Result<String, String> {
+    ensure_unlocked(&state.engine)?;
+    state
+        .engine
+        .save_config_set(&name)
         .map_err(|e| e.to_string())?;
-    Ok(format!("Restored backup '{}'", backup_name))
+    tray::refresh_config_sets(&app, &config_set_names(&state.engine));
+    Ok(format!("Saved config set '{}'", name.trim()))
+}
+
+#[tauri::command]
+fn delete_config_set(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+    name: String,
+) -> Result<(), String> {
+    ensure_unlocked(&state.engine)?;
+    state
+        .engine
+        .backups()
+        .delete_config_set(&name)
+        .map_err(|e| e.to_string())?;
+    tray::refresh_config_sets(&app, &config_set_names(&state.engine));
+    Ok(())
+}
+
+#[derive(serde::Serialize)]
+struct KioskStatus {
+    enabled: bool,
+    locked: bool,
+    golden_backup: Option<String>,
+}
+
+/// Kiosk mode as the running engine applies it; config changes take effect on restart.
+#[tauri::command]
+async fn kiosk_status(state: State<'_, AppState>) -> Result<KioskStatus, String> {
+    let kiosk = state.engine.kiosk();
+    Ok(KioskStatus {
+        enabled: kiosk.enabled,
+        locked: state.engine.kiosk_locked(),
+        golden_backup: kiosk.golden_backup.clone(),
+    })
+}
+
+#[tauri::command]
+async fn unlock_kiosk(state: State<'_, AppState>, password: String) -> Result<(), String> {
+    if state.engine.unlock_kiosk(&password).await {
+        Ok(())
+    } else {
+        Err("Wrong override password".to_string())
+    }
+}
+
+#[tauri::command]
+fn lock_kiosk(state: State<'_, AppState>) {
+    state.engine.lock_kiosk();
+}
+
+#[tauri::command]
+async fn hash_kiosk_password(password: String) -> Result<String, String> {
+    tokio::task::spawn_blocking(move || kiosk::hash_password(&password))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Download a replicated backup to use as the golden backup; returns its local name.
+#[tauri::command]
+async fn import_golden_backup(state: State<'_, AppState>, id: String) -> Result<String, String> {
+    state
+        .engine
+        .import_golden(&id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn switch_config_set(state: State<'_, AppState>, name: String) -> Result<String, String> {
+    switch_config_set_message(&state.engine, &name).await
+}
+
+/// Switch config sets and describe the outcome; shared by the command and the tray.
+async fn switch_config_set_message(engine: &SyncEngine, name: &str) -> Result<String, String> {
+    ensure_unlocked(engine)?;
+    match engine.switch_config_set(name).await {
+        Ok(sync_engine::SyncResult::Skipped(sync_engine::SkipReason::GGRunning)) => Ok(format!(
+            "SteelSeries GG is running. Config set '{}' will be applied once it exits.",
+            name
+        )),
+        Ok(sync_engine::SyncResult::Restored { gg_restarted, .. }) => {
+            if gg_restarted {
+                Ok(format!(
+                    "Switched to config set '{}'. SteelSeries GG was restarted.",
+                    name
+                ))
+            } else {
+                Ok(format!("Switched to config set '{}'", name))
+            }
+        }
+        Ok(result) => Ok(format_sync_result(&result)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+#[tauri::command]
+async fn undo_last_restore(state: State<'_, AppState>) -> Result<String, String> {
+    ensure_unlocked(&state.engine)?;
+    match state.engine.undo_last_restore().await {
+        Ok(sync_engine::SyncResult::Skipped(sync_engine::SkipReason::GGRunning)) => {
+            Ok("SteelSeries GG is running. The undo will be applied once it exits.".to_string())
+        }
+        Ok(result) => Ok(format_sync_result(&result)),
+        Err(e) => Err(e.to_string()),
+    }
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -124,6 +580,9 @@ pub fn run() {
 
     let watcher_config_dir = config.steelseries_db_path.clone();
     let watcher_debounce = config.debounce_secs;
+    let verify_interval_hours = config.verify_interval_hours;
+    let backup_schedule = config.backup_schedule.clone();
+    let kiosk_enabled = config.kiosk.enabled;
 
     let app_state = AppState {
         engine,
@@ -138,13 +597,42 @@ pub fn run() {
             push_now,
             pull_now,
             list_backups,
+            pin_backup,
+            annotate_backup,
+            verify_backups,
+            diff_snapshots,
+            sync_history,
+            setting_history,
+            get_device_map,
+            save_device_map,
+            refresh_device_map,
+            inspect_snapshot,
+            export_snapshot,
+            import_snapshot,
+            list_remote_backups,
+            restore_remote_backup,
             get_config,
             save_config,
             restore_backup,
+            list_backup_units,
+            restore_backup_units,
+            export_profiles,
+            import_profiles,
+            undo_last_restore,
+            list_config_sets,
+            save_config_set,
+            delete_config_set,
+            switch_config_set,
+            kiosk_status,
+            unlock_kiosk,
+            lock_kiosk,
+            hash_kiosk_password,
+            import_golden_backup,
         ])
         .setup(move |app| {
             // Set up system tray
-            let _ = tray::setup_tray(app.handle());
+            let config_sets = config_set_names(&app.state::<AppState>().engine);
+            let _ = tray::setup_tray(app.handle(), &config_sets);
 
             // Spawn file watcher thread (outbound: local changes -> push)
             let watcher_handle = app.handle().clone();
@@ -172,7 +660,7 @@ pub fn run() {
                         match result {
                             Ok(r) => {
                                 log::info!("Auto-push result: {:?}", r);
-                                let _ = handle.emit("sync-status", format!("{:?}", r));
+                                let _ = handle.emit("sync-status", format_sync_result(&r));
                             }
                             Err(e) => {
                                 log::error!("Auto-push error: {}", e);
@@ -192,6 +680,24 @@ pub fn run() {
                 let mut interval = tokio::time::interval(std::time::Duration::from_secs(30));
                 loop {
                     interval.tick().await;
+
+                    // Apply a restore that was deferred while GG was running
+                    match poll_engine.apply_pending_restore().await {
+                        Some(Ok(r)) => {
+                            log::info!("Deferred restore result: {:?}", r);
+                            let _ = poll_handle.emit("sync-status", format_sync_result(&r));
+                        }
+                        Some(Err(e)) => log::error!("Deferred restore error: {}", e),
+                        None => {}
+                    }
+
+                    // Copy selected backups off-site
+                    match poll_engine.replicate_backups().await {
+                        Ok(0) => {}
+                        Ok(n) => log::info!("Replicated {} backup(s) to the provider", n),
+                        Err(e) => log::warn!("Backup replication error: {}", e),
+                    }
+
                     log::info!("Polling remote for inbound changes...");
 
                     // Check if remote has new data we haven't seen
@@ -207,7 +713,11 @@ pub fn run() {
 
                     // Remote has newer data — pull it
                     match poll_engine.pull_from_remote().await {
-                        Ok(ref r @ sync_engine::SyncResult::Pulled { ref from_device, .. }) => {
+                        Ok(
+                            ref r @ sync_engine::SyncResult::Pulled {
+                                ref from_device, ..
+                            },
+                        ) => {
                             log::info!("Inbound sync: pulled from {}", from_device);
                             last_seen = meta.last_modified;
                             let _ = poll_handle.emit("sync-status", format_sync_result(r));
@@ -216,7 +726,9 @@ pub fn run() {
                             log::debug!("Inbound poll skipped: {:?}", reason);
                             last_seen = meta.last_modified;
                         }
-                        Ok(_) => { last_seen = meta.last_modified; }
+                        Ok(_) => {
+                            last_seen = meta.last_modified;
+                        }
                         Err(e) => {
                             log::error!("Inbound poll error: {}", e);
                         }
@@ -224,6 +736,81 @@ pub fn run() {
                 }
             });
 
+            // Scheduled backups (timer and/or GG exit), independent of sync
+            let mut scheduler = scheduler::BackupScheduler::new(
+                app.state::<AppState>().engine.clone(),
+                backup_schedule,
+            );
+            if scheduler.is_enabled() {
+                tauri::async_runtime::spawn(async move {
+                    let mut interval = tokio::time::interval(std::time::Duration::from_secs(60));
+                    loop {
+                        interval.tick().await;
+                        if let Err(e) = scheduler.tick().await {
+                            log::error!("Scheduled backup error: {}", e);
+                        }
+                    }
+                });
+            }
+
+            // Kiosk mode: start from the golden backup, and return to it whenever GG exits
+            if kiosk_enabled {
+                let kiosk_handle = app.handle().clone();
+                let kiosk_engine = app.state::<AppState>().engine.clone();
+                tauri::async_runtime::spawn(async move {
+                    let report = |result: Result<
+                        Option<sync_engine::SyncResult>,
+                        sync_engine::SyncError,
+                    >| match result {
+                        Ok(Some(r)) => {
+                            log::info!("Golden restore result: {:?}", r);
+                            let _ = kiosk_handle.emit("sync-status", format_sync_result(&r));
+                        }
+                        Ok(None) => {}
+                        Err(e) => log::error!("Golden restore error: {}", e),
+                    };
+                    report(kiosk_engine.restore_golden().await);
+
+                    let mut watcher = kiosk::KioskWatcher::new(kiosk_engine);
+                    let mut interval = tokio::time::interval(std::time::Duration::from_secs(5));
+                    loop {
+                        interval.tick().await;
+                        report(watcher.tick().await);
+                    }
+                });
+            }
+
+            // Periodically verify backup integrity, if enabled
+            if verify_interval_hours > 0 {
+                let verify_handle = app.handle().clone();
+                let verify_engine = app.state::<AppState>().engine.clone();
+                tauri::async_runtime::spawn(async move {
+                    let period = std::time::Duration::from_secs(verify_interval_hours * 3600);
+                    let mut interval = tokio::time::interval(period);
+                    interval.tick().await;
+                    loop {
+                        interval.tick().await;
+                        let engine = verify_engine.clone();
+                        let result =
+                            tokio::task::spawn_blocking(move || engine.backups().verify_backups())
+                                .await;
+                        match result {
+                            Ok(Ok(summary)) => {
+                                log::info!(
+                                    "Verified {} backups: {} corrupt, {} incomplete",
+                                    summary.checked,
+                                    summary.corrupt.len(),
+                                    summary.incomplete.len()
+                                );
+                                let _ = verify_handle.emit("backup-verify", summary);
+                            }
+                            Ok(Err(e)) => log::error!("Backup verification error: {}", e),
+                            Err(e) => log::error!("Backup verification task failed: {}", e),
+                        }
+                    }
+                });
+            }
+
             Ok(())
         })
         .run(tauri::generate_context!())
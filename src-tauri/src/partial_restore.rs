@@ -0,0 +1,504 @@
+//! Selective restore of individual devices, profiles or app configs.
+//!
+//! A backup's logical units are the rows of its device, profile and app tables.
+//! Restoring a unit copies that row into the live database, and replaces the
+//! rows of other tables that reference it through a single-column foreign key
+//! (e.g. a profile's settings) with the backup's. All changes for one restore
+//! happen in a single transaction.
+
+use crate::diff::{primary_key, quote_ident, read_table, row_label, table_names, GgConcept, Row};
+use crate::providers::ConfigSnapshot;
+use crate::snapshot_db::{SnapshotDb, SnapshotDbError};
+use rusqlite::types::Value;
+use rusqlite::{Connection, Transaction};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+
+/// A restorable unit in a backup.
+#[derive(Debug, Clone, Serialize)]
+pub struct RestoreUnit {
+    pub table: String,
+    pub key: String,
+    pub label: Option<String>,
+    pub concept: GgConcept,
+    /// Rows in other tables that belong to this unit and are restored with it.
+    pub child_rows: usize,
+}
+
+/// Selects a unit to restore, as listed by `list_units`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnitRef {
+    pub table: String,
+    pub key: String,
+}
+
+/// A foreign key from `child_table.from_column` to a parent column.
+struct ChildLink {
+    child_table: String,
+    from_column: String,
+    parent_column: String,
+}
+
+/// Devices, profiles and app configs in a snapshot. Only tables with a primary
+/// key are considered, so every unit can be addressed reliably.
+pub fn list_units(snapshot: &ConfigSnapshot) -> Result<Vec<RestoreUnit>, SnapshotDbError> {
+    let db = SnapshotDb::open(snapshot)?;
+    let mut units = Vec::new();
+    for table in table_names(db.conn())? {
+        let concept = GgConcept::from_table(&table);
+        if !matches!(
+            concept,
+            GgConcept::Device | GgConcept::Profile | GgConcept::App
+        ) {
+            continue;
+        }
+        let pk = primary_key(db.conn(), &table)?;
+        if pk.is_empty() || is_dependent(&db, &table, &pk)? {
+            continue;
+        }
+        let links = child_links(db.conn(), &table)?;
+        for (key, row) in read_table(db.conn(), &table)? {
+            let mut child_rows = 0;
+            for link in &links {
+                child_rows += select_children(db.conn(), link, &row)?.len();
+            }
+            units.push(RestoreUnit {
+                table: table.clone(),
+                key,
+                label: row_label(&row),
+                concept,
+                child_rows,
+            });
+        }
+    }
+    Ok(units)
+}
+
+/// Copy the selected units from `backup` into the database at `live_db`.
+/// GG must not be running. Returns the number of rows written.
+pub fn restore_units(
+    live_db: &Path,
+    backup: &ConfigSnapshot,
+    units: &[UnitRef],
+) -> Result<usize, SnapshotDbError> {
+    let source = SnapshotDb::open(backup)?;
+    let mut live = Connection::open(live_db)?;
+    let tx = live.transaction()?;
+    let mut written = 0;
+    for unit in units {
+        let rows = read_table(source.conn(), &unit.table)?;
+        let row = rows.get(&unit.key).ok_or_else(|| {
+            SnapshotDbError::NotFound(format!("{} '{}' in the backup", unit.table, unit.key))
+        })?;
+        upsert(&tx, &unit.table, row)?;
+        written += 1;
+
+        for link in child_links(source.conn(), &unit.table)? {
+            let parent_value = row.get(&link.parent_column).unwrap_or(&Value::Null);
+            tx.execute(
+                &format!(
+                    "DELETE FROM {} WHERE {} = ?1",
+                    quote_ident(&link.child_table),
+                    quote_ident(&link.from_column)
+                ),
+                [parent_value],
+            )?;
+            for child in select_children(source.conn(), &link, row)? {
+                upsert(&tx, &link.child_table, &child)?;
+                written += 1;
+            }
+        }
+    }
+    tx.commit()?;
+    Ok(written)
+}
+
+/// A unit's row and the rows of other tables that belong to it, by table.
+pub(crate) fn unit_rows(
+    db: &SnapshotDb,
+    unit: &UnitRef,
+) -> Result<(Row, BTreeMap<String, Vec<Row>>), SnapshotDbError> {
+    let mut rows = read_table(db.conn(), &unit.table)?;
+    let row = rows
+        .remove(&unit.key)
+        .ok_or_else(|| SnapshotDbError::NotFound(format!("{} '{}'", unit.table, unit.key)))?;
+    let mut children: BTreeMap<String, Vec<Row>> = BTreeMap::new();
+    for link in child_links(db.conn(), &unit.table)? {
+        let rows = select_children(db.conn(), &link, &row)?;
+        if !rows.is_empty() {
+            children.entry(link.child_table).or_default().extend(rows);
+        }
+    }
+    Ok((row, children))
+}
+
+/// Insert or replace a unit's row in `db` and replace the rows belonging to it
+/// with `children` (by table).
+pub(crate) fn replace_unit(
+    db: &SnapshotDb,
+    table: &str,
+    row: &Row,
+    children: &BTreeMap<String, Vec<Row>>,
+) -> Result<(), SnapshotDbError> {
+    let links = child_links(db.conn(), table)?;
+    let tx = db.conn().unchecked_transaction()?;
+    upsert(&tx, table, row)?;
+    for link in &links {
+        let parent_value = row.get(&link.parent_column).unwrap_or(&Value::Null);
+        tx.execute(
+            &format!(
+                "DELETE FROM {} WHERE {} = ?1",
+                quote_ident(&link.child_table),
+                quote_ident(&link.from_column)
+            ),
+            [parent_value],
+        )?;
+    }
+    for (child_table, rows) in children {
+        for child in rows {
+            upsert(&tx, child_table, child)?;
+        }
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+/// A copy of `snapshot` without the units `keep` rejects. Rows in other tables
+/// that belong to a dropped unit are dropped with it; everything else is kept.
+pub fn filter_units(
+    snapshot: &ConfigSnapshot,
+    keep: impl Fn(&RestoreUnit) -> bool,
+) -> Result<ConfigSnapshot, SnapshotDbError> {
+    let mut dropped: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for unit in list_units(snapshot)?.into_iter().filter(|u| !keep(u)) {
+        dropped.entry(unit.table).or_default().push(unit.key);
+    }
+
+    let db = SnapshotDb::open(snapshot)?;
+    let tx = db.conn().unchecked_transaction()?;
+    remove_units(&tx, &dropped)?;
+    tx.commit()?;
+    db.into_snapshot(snapshot.meta.clone())
+}
+
+/// Delete the given units, and the rows of other tables that belong to them,
+/// from the database at `live_db`. GG must not be running. Returns the number
+/// of units deleted; units that don't exist are ignored.
+pub fn delete_units(live_db: &Path, units: &[UnitRef]) -> Result<usize, SnapshotDbError> {
+    let mut by_table: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for unit in units {
+        by_table
+            .entry(unit.table.clone())
+            .or_default()
+            .push(unit.key.clone());
+    }
+    let mut live = Connection::open(live_db)?;
+    let tx = live.transaction()?;
+    let deleted = remove_units(&tx, &by_table)?;
+    tx.commit()?;
+    Ok(deleted)
+}
+
+/// Delete units (keys by table) and the rows belonging to them. Returns the
+/// number of units deleted.
+fn remove_units(
+    conn: &Connection,
+    units: &BTreeMap<String, Vec<String>>,
+) -> Result<usize, SnapshotDbError> {
+    let mut deleted = 0;
+    for (table, keys) in units {
+        let pk = primary_key(conn, table)?;
+        let links = child_links(conn, table)?;
+        let rows = read_table(conn, table)?;
+        for row in keys.iter().filter_map(|key| rows.get(key)) {
+            for link in &links {
+                let parent_value = row.get(&link.parent_column).unwrap_or(&Value::Null);
+                conn.execute(
+                    &format!(
+                        "DELETE FROM {} WHERE {} = ?1",
+                        quote_ident(&link.child_table),
+                        quote_ident(&link.from_column)
+                    ),
+                    [parent_value],
+                )?;
+            }
+            let condition = pk
+                .iter()
+                .enumerate()
+                .map(|(i, column)| format!("{} = ?{}", quote_ident(column), i + 1))
+                .collect::<Vec<_>>()
+                .join(" AND ");
+            conn.execute(
+                &format!("DELETE FROM {} WHERE {}", quote_ident(table), condition),
+                rusqlite::params_from_iter(pk.iter().map(|c| row.get(c).unwrap_or(&Value::Null))),
+            )?;
+            deleted += 1;
+        }
+    }
+    Ok(deleted)
+}
+
+/// Whether a table's primary key includes a foreign key (e.g. per-profile
+/// settings keyed by profile). Its rows are restored with their parent instead.
+fn is_dependent(db: &SnapshotDb, table: &str, pk: &[String]) -> Result<bool, SnapshotDbError> {
+    let mut stmt = db
+        .conn()
+        .prepare(&format!("PRAGMA foreign_key_list({})", quote_ident(table)))?;
+    let from: Vec<String> = stmt
+        .query_map([], |r| r.get("from"))?
+        .collect::<Result<_, _>>()?;
+    Ok(from.iter().any(|column| pk.contains(column)))
+}
+
+/// Single-column foreign keys in any table that reference `parent`.
+fn child_links(conn: &Connection, parent: &str) -> Result<Vec<ChildLink>, SnapshotDbError> {
+    let parent_pk = primary_key(conn, parent)?;
+    let mut links = Vec::new();
+    for table in table_names(conn)? {
+        let mut stmt =
+            conn.prepare(&format!("PRAGMA foreign_key_list({})", quote_ident(&table)))?;
+        let keys: Vec<(i64, String, String, Option<String>)> = stmt
+            .query_map([], |r| {
+                Ok((r.get("id")?, r.get("table")?, r.get("from")?, r.get("to")?))
+            })?
+            .collect::<Result<_, _>>()?;
+        let multi_column: HashSet<i64> = {
+            let mut seen = HashSet::new();
+            keys.iter()
+                .filter(|(id, ..)| !seen.insert(*id))
+                .map(|(id, ..)| *id)
+                .collect()
+        };
+        for (id, target, from, to) in keys {
+            if multi_column.contains(&id) || !target.eq_ignore_ascii_case(parent) {
+                continue;
+            }
+            // A missing target column means the parent's (single-column) primary key
+            let parent_column = match to {
+                Some(column) => column,
+                None if parent_pk.len() == 1 => parent_pk[0].clone(),
+                None => continue,
+            };
+            links.push(ChildLink {
+                child_table: table.clone(),
+                from_column: from,
+                parent_column,
+            });
+        }
+    }
+    Ok(links)
+}
+
+fn select_children(
+    conn: &Connection,
+    link: &ChildLink,
+    parent: &Row,
+) -> Result<Vec<Row>, SnapshotDbError> {
+    let parent_value = parent.get(&link.parent_column).unwrap_or(&Value::Null);
+    let mut stmt = conn.prepare(&format!(
+        "SELECT * FROM {} WHERE {} = ?1",
+        quote_ident(&link.child_table),
+        quote_ident(&link.from_column)
+    ))?;
+    let columns: Vec<String> = stmt
+        .column_names()
+        .into_iter()
+        .map(str::to_string)
+        .collect();
+    let mut rows = stmt.query([parent_value])?;
+    let mut children = Vec::new();
+    while let Some(row) = rows.next()? {
+        let mut values = Row::new();
+        for (i, column) in columns.iter().enumerate() {
+            values.insert(column.clone(), row.get_ref(i)?.into());
+        }
+        children.push(values);
+    }
+    Ok(children)
+}
+
+/// Insert or replace a row, writing only columns the live table has.
+fn upsert(tx: &Transaction, table: &str, row: &Row) -> Result<(), SnapshotDbError> {
+    let live_columns = table_columns(tx, table)?;
+    if live_columns.is_empty() {
+        return Err(SnapshotDbError::NotFound(format!(
+            "table {} in the live database",
+            table
+        )));
+    }
+    let (columns, values): (Vec<&String>, Vec<&Value>) = row
+        .iter()
+        .filter(|(c, _)| live_columns.contains(*c))
+        .unzip();
+    let sql = format!(
+        "INSERT OR REPLACE INTO {} ({}) VALUES ({})",
+        quote_ident(table),
+        columns
+            .iter()
+            .map(|c| quote_ident(c))
+            .collect::<Vec<_>>()
+            .join(", "),
+        (1..=values.len())
+            .map(|i| format!("?{}", i))
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    tx.execute(&sql, rusqlite::params_from_iter(values))?;
+    Ok(())
+}
+
+fn table_columns(conn: &Connection, table: &str) -> Result<HashSet<String>, SnapshotDbError> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", quote_ident(table)))?;
+    let columns = stmt
+        .query_map([], |r| r.get("name"))?
+        .collect::<Result<_, _>>()?;
+    Ok(columns)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snapshot_db::tests::snapshot_from_sql;
+    use tempfile::TempDir;
+
+    const SCHEMA: &str = "
+        CREATE TABLE profiles (id INTEGER PRIMARY KEY, name TEXT);
+        CREATE TABLE profile_settings (
+            profile_id INTEGER REFERENCES profiles(id), key TEXT, value TEXT,
+            PRIMARY KEY (profile_id, key));
+        CREATE TABLE log (message TEXT);";
+
+    fn live_db(tmp: &TempDir, sql: &str) -> std::path::PathBuf {
+        let path = tmp.path().join("database.db");
+        std::fs::write(&path, snapshot_from_sql(sql).db).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_list_units_counts_children() {
+        let backup = snapshot_from_sql(&format!(
+            "{}
+             INSERT INTO profiles VALUES (1, 'FPS'), (2, 'MOBA');
+             INSERT INTO profile_settings VALUES (1, 'dpi', '800'), (1, 'rgb', 'red');",
+            SCHEMA
+        ));
+
+        let units = list_units(&backup).unwrap();
+
+        assert_eq!(units.len(), 2);
+        assert_eq!(units[0].label.as_deref(), Some("FPS"));
+        assert_eq!(units[0].child_rows, 2);
+        assert_eq!(units[1].child_rows, 0);
+    }
+
+    #[test]
+    fn test_restore_unit_replaces_only_its_rows() {
+        let tmp = TempDir::new().unwrap();
+        let backup = snapshot_from_sql(&format!(
+            "{}
+             INSERT INTO profiles VALUES (1, 'FPS'), (2, 'MOBA');
+             INSERT INTO profile_settings VALUES (1, 'dpi', '800'), (2, 'dpi', '400');",
+            SCHEMA
+        ));
+        let live = live_db(
+            &tmp,
+            &format!(
+                "{}
+                 INSERT INTO profiles VALUES (1, 'FPS edited'), (2, 'MOBA edited');
+                 INSERT INTO profile_settings VALUES
+                     (1, 'dpi', '1600'), (1, 'extra', 'x'), (2, 'dpi', '3200');",
+                SCHEMA
+            ),
+        );
+
+        let unit = UnitRef {
+            table: "profiles".into(),
+            key: "1".into(),
+        };
+        let written = restore_units(&live, &backup, &[unit]).unwrap();
+
+        assert_eq!(written, 2);
+        let conn = Connection::open(&live).unwrap();
+        let names: Vec<String> = conn
+            .prepare("SELECT name FROM profiles ORDER BY id")
+            .unwrap()
+            .query_map([], |r| r.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(names, ["FPS", "MOBA edited"]);
+        let settings: Vec<(i64, String, String)> = conn
+            .prepare("SELECT * FROM profile_settings ORDER BY profile_id, key")
+            .unwrap()
+            .query_map([], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            settings,
+            [
+                (1, "dpi".into(), "800".into()),
+                (2, "dpi".into(), "3200".into())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_filter_units_drops_rejected_units_and_their_rows() {
+        let snapshot = snapshot_from_sql(&format!(
+            "{}
+             INSERT INTO profiles VALUES (1, 'FPS'), (2, 'Streaming');
+             INSERT INTO profile_settings VALUES (1, 'dpi', '800'), (2, 'dpi', '400');
+             INSERT INTO log VALUES ('kept');",
+            SCHEMA
+        ));
+
+        let filtered =
+            filter_units(&snapshot, |u| u.label.as_deref() != Some("Streaming")).unwrap();
+
+        let db = SnapshotDb::open(&filtered).unwrap();
+        let count = |sql: &str| -> i64 { db.conn().query_row(sql, [], |r| r.get(0)).unwrap() };
+        assert_eq!(count("SELECT COUNT(*) FROM profiles"), 1);
+        assert_eq!(
+            count("SELECT COUNT(*) FROM profile_settings WHERE profile_id = 2"),
+            0
+        );
+        assert_eq!(
+            count("SELECT COUNT(*) FROM profile_settings WHERE profile_id = 1"),
+            1
+        );
+        assert_eq!(count("SELECT COUNT(*) FROM log"), 1);
+    }
+
+    #[test]
+    fn test_unknown_unit_rolls_back() {
+        let tmp = TempDir::new().unwrap();
+        let backup = snapshot_from_sql(&format!(
+            "{} INSERT INTO profiles VALUES (1, 'FPS');",
+            SCHEMA
+        ));
+        let live = live_db(
+            &tmp,
+            &format!("{} INSERT INTO profiles VALUES (1, 'Mine');", SCHEMA),
+        );
+
+        let units = [
+            UnitRef {
+                table: "profiles".into(),
+                key: "1".into(),
+            },
+            UnitRef {
+                table: "profiles".into(),
+                key: "99".into(),
+            },
+        ];
+        assert!(restore_units(&live, &backup, &units).is_err());
+
+        let conn = Connection::open(&live).unwrap();
+        let name: String = conn
+            .query_row("SELECT name FROM profiles", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(name, "Mine");
+    }
+}
@@ -0,0 +1,329 @@
+//! Translation of game executable paths between operating systems.
+//!
+//! App-linked profiles store the executable GG watches for. A path written on
+//! Windows means nothing on macOS and vice versa, so when a pull is applied,
+//! paths that belong to another OS are rewritten using, in order:
+//! 1. the configured per-game mappings, by exact path;
+//! 2. the configured mappings, by executable name;
+//! 3. paths this machine had before the pull, by executable name.
+//!
+//! Paths none of these can translate are left as they are and reported.
+
+use crate::config::{FieldRef, GameMapping, PathMappingConfig};
+use crate::diff::{read_table, table_names, GgConcept, Row};
+use crate::providers::ConfigSnapshot;
+use crate::snapshot_db::{SnapshotDb, SnapshotDbError};
+use crate::transform::update_row;
+use rusqlite::types::Value;
+use rusqlite::Connection;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Column name fragments that mark a column as holding executable paths.
+const PATH_COLUMN_HINTS: &[&str] = &["path", "exe", "executable", "process", "program"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OsFamily {
+    Windows,
+    MacOs,
+    Linux,
+}
+
+impl OsFamily {
+    pub fn current() -> Self {
+        if cfg!(target_os = "windows") {
+            OsFamily::Windows
+        } else if cfg!(target_os = "macos") {
+            OsFamily::MacOs
+        } else {
+            OsFamily::Linux
+        }
+    }
+
+    /// The OS a path was written on, if its shape gives it away.
+    fn of_path(path: &str) -> Option<Self> {
+        let bytes = path.as_bytes();
+        let drive = bytes.len() >= 3
+            && bytes[0].is_ascii_alphabetic()
+            && bytes[1] == b':'
+            && matches!(bytes[2], b'\\' | b'/');
+        if drive || path.starts_with("\\\\") {
+            Some(OsFamily::Windows)
+        } else if ["/Applications/", "/Users/", "/System/"]
+            .iter()
+            .any(|p| path.starts_with(p))
+            || (path.starts_with('/') && (path.contains(".app/") || path.ends_with(".app")))
+        {
+            Some(OsFamily::MacOs)
+        } else if ["/home/", "/usr/", "/opt/"]
+            .iter()
+            .any(|p| path.starts_with(p))
+        {
+            Some(OsFamily::Linux)
+        } else {
+            None
+        }
+    }
+}
+
+impl GameMapping {
+    fn path_for(&self, os: OsFamily) -> Option<&String> {
+        match os {
+            OsFamily::Windows => self.windows.as_ref(),
+            OsFamily::MacOs => self.macos.as_ref(),
+            OsFamily::Linux => self.linux.as_ref(),
+        }
+    }
+
+    fn paths(&self) -> impl Iterator<Item = &String> {
+        [&self.windows, &self.macos, &self.linux]
+            .into_iter()
+            .flatten()
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PathMappingReport {
+    pub rewritten: Vec<PathRewrite>,
+    pub unmapped: Vec<UnmappedPath>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PathRewrite {
+    pub table: String,
+    pub key: String,
+    pub column: String,
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UnmappedPath {
+    pub table: String,
+    pub key: String,
+    pub column: String,
+    pub path: String,
+}
+
+/// Rewrite paths from other operating systems in the database at `live_db` to
+/// their equivalents on `os`. `previous` is this machine's config before the
+/// pull, used to find local paths by executable name.
+pub fn map_paths(
+    live_db: &Path,
+    previous: Option<&ConfigSnapshot>,
+    config: &PathMappingConfig,
+    os: OsFamily,
+) -> Result<PathMappingReport, SnapshotDbError> {
+    let local_paths = match previous {
+        Some(previous) => {
+            let db = SnapshotDb::open(previous)?;
+            let mut index = BTreeMap::new();
+            for PathCell { path, .. } in path_cells(db.conn())? {
+                if OsFamily::of_path(&path) == Some(os) {
+                    index.entry(executable_name(&path)).or_insert(path);
+                }
+            }
+            index
+        }
+        None => BTreeMap::new(),
+    };
+
+    let mut live = Connection::open(live_db)?;
+    let tx = live.transaction()?;
+    let mut report = PathMappingReport::default();
+    for PathCell {
+        field,
+        key,
+        row,
+        path,
+    } in path_cells(&tx)?
+    {
+        if !matches!(OsFamily::of_path(&path), Some(from) if from != os) {
+            continue;
+        }
+        match translate(&path, os, &config.games, &local_paths) {
+            Some(to) => {
+                update_row(&tx, &field, &key, &row, &Value::Text(to.clone()))?;
+                report.rewritten.push(PathRewrite {
+                    table: field.table,
+                    key,
+                    column: field.column,
+                    from: path,
+                    to,
+                });
+            }
+            None => report.unmapped.push(UnmappedPath {
+                table: field.table,
+                key,
+                column: field.column,
+                path,
+            }),
+        }
+    }
+    tx.commit()?;
+    Ok(report)
+}
+
+fn translate(
+    path: &str,
+    os: OsFamily,
+    games: &[GameMapping],
+    local_paths: &BTreeMap<String, String>,
+) -> Option<String> {
+    let exact = games
+        .iter()
+        .find(|game| game.paths().any(|p| p.eq_ignore_ascii_case(path)))
+        .and_then(|game| game.path_for(os));
+    if let Some(target) = exact {
+        return Some(target.clone());
+    }
+    let name = executable_name(path);
+    let by_name = games
+        .iter()
+        .filter(|game| {
+            game.paths().any(|p| executable_name(p) == name)
+                || game.name.replace(' ', "").eq_ignore_ascii_case(&name)
+        })
+        .find_map(|game| game.path_for(os));
+    by_name.or_else(|| local_paths.get(&name)).cloned()
+}
+
+/// Lowercased executable name without `.exe`/`.app`, e.g. "valorant" for both
+/// `C:\Riot Games\VALORANT\VALORANT.exe` and `/Applications/Valorant.app`.
+fn executable_name(path: &str) -> String {
+    let file = path
+        .rsplit(['/', '\\'])
+        .find(|part| !part.is_empty())
+        .unwrap_or(path)
+        .to_lowercase();
+    match file.rsplit_once('.') {
+        Some((stem, "exe" | "app")) => stem.to_string(),
+        _ => file,
+    }
+}
+
+/// A text cell that may hold an executable path, with the row it belongs to.
+struct PathCell {
+    field: FieldRef,
+    key: String,
+    row: Row,
+    path: String,
+}
+
+/// Text cells that may hold executable paths: every text column of app tables,
+/// and columns named like paths in other tables.
+fn path_cells(conn: &Connection) -> Result<Vec<PathCell>, SnapshotDbError> {
+    let mut cells = Vec::new();
+    for table in table_names(conn)? {
+        let app_table = GgConcept::from_table(&table) == GgConcept::App;
+        for (key, row) in read_table(conn, &table)? {
+            for (column, value) in &row {
+                let Value::Text(text) = value else {
+                    continue;
+                };
+                let lower = column.to_lowercase();
+                if app_table || PATH_COLUMN_HINTS.iter().any(|hint| lower.contains(hint)) {
+                    let field = FieldRef {
+                        table: table.clone(),
+                        column: column.clone(),
+                    };
+                    cells.push(PathCell {
+                        field,
+                        key: key.clone(),
+                        row: row.clone(),
+                        path: text.clone(),
+                    });
+                }
+            }
+        }
+    }
+    Ok(cells)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snapshot_db::tests::snapshot_from_sql;
+    use tempfile::TempDir;
+
+    const SCHEMA: &str = "CREATE TABLE apps (id INTEGER PRIMARY KEY, name TEXT, exe_path TEXT);";
+
+    #[test]
+    fn test_detects_path_os() {
+        assert_eq!(
+            OsFamily::of_path(r"C:\Games\cs2.exe"),
+            Some(OsFamily::Windows)
+        );
+        assert_eq!(
+            OsFamily::of_path(r"\\nas\games\cs2.exe"),
+            Some(OsFamily::Windows)
+        );
+        assert_eq!(
+            OsFamily::of_path("/Applications/Discord.app"),
+            Some(OsFamily::MacOs)
+        );
+        assert_eq!(
+            OsFamily::of_path("/home/me/.steam/cs2"),
+            Some(OsFamily::Linux)
+        );
+        assert_eq!(OsFamily::of_path("Valorant"), None);
+        assert_eq!(
+            executable_name(r"C:\Riot Games\VALORANT\VALORANT.exe"),
+            "valorant"
+        );
+        assert_eq!(executable_name("/Applications/Valorant.app/"), "valorant");
+    }
+
+    #[test]
+    fn test_maps_windows_paths_on_mac() {
+        let tmp = TempDir::new().unwrap();
+        let pulled = snapshot_from_sql(&format!(
+            r"{} INSERT INTO apps VALUES
+                (1, 'Valorant', 'C:\Riot Games\VALORANT\VALORANT.exe'),
+                (2, 'Discord', 'C:\Users\me\AppData\Local\Discord\Discord.exe'),
+                (3, 'Notepad', 'C:\Windows\notepad.exe'),
+                (4, 'Safari', '/Applications/Safari.app');",
+            SCHEMA
+        ));
+        let previous = snapshot_from_sql(&format!(
+            "{} INSERT INTO apps VALUES (2, 'Discord', '/Applications/Discord.app');",
+            SCHEMA
+        ));
+        let live = tmp.path().join("database.db");
+        std::fs::write(&live, &pulled.db).unwrap();
+        let config = PathMappingConfig {
+            enabled: true,
+            games: vec![GameMapping {
+                name: "Valorant".into(),
+                windows: Some(r"D:\Other\VALORANT.exe".into()),
+                macos: Some("/Applications/Valorant.app".into()),
+                linux: None,
+            }],
+        };
+
+        let report = map_paths(&live, Some(&previous), &config, OsFamily::MacOs).unwrap();
+
+        let conn = Connection::open(&live).unwrap();
+        let paths: Vec<String> = conn
+            .prepare("SELECT exe_path FROM apps ORDER BY id")
+            .unwrap()
+            .query_map([], |r| r.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            paths,
+            [
+                "/Applications/Valorant.app",
+                "/Applications/Discord.app",
+                r"C:\Windows\notepad.exe",
+                "/Applications/Safari.app",
+            ]
+        );
+        assert_eq!(report.rewritten.len(), 2);
+        assert_eq!(report.unmapped.len(), 1);
+        assert_eq!(report.unmapped[0].path, r"C:\Windows\notepad.exe");
+    }
+}
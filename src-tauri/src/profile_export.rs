@@ -0,0 +1,268 @@
+//! Text export of GG profiles, for keeping them under review.
+//!
+//! A profile is exported with its own row and the rows that belong to it in
+//! other tables (settings, bindings, ...). Tables, columns and rows come out in
+//! a stable order, so exporting an unchanged profile twice gives identical
+//! files. Values keep their SQLite type (text stays text even when it looks
+//! like a number, NULLs are listed explicitly), so importing an export writes
+//! back exactly what was exported.
+
+use crate::diff::{decode_hex, hex, row_label, Row};
+use crate::partial_restore::{replace_unit, unit_rows, UnitRef};
+use crate::providers::ConfigSnapshot;
+use crate::snapshot_db::{SnapshotDb, SnapshotDbError};
+use rusqlite::types::Value;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+pub const EXPORT_FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Toml,
+    Json,
+}
+
+impl ExportFormat {
+    /// JSON for `.json` files, TOML otherwise.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("json") => ExportFormat::Json,
+            _ => ExportFormat::Toml,
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ExportError {
+    #[error("Profile export format version {0} is not supported")]
+    UnsupportedVersion(u32),
+    #[error("Invalid profile export: {0}")]
+    Invalid(String),
+    #[error(transparent)]
+    Database(#[from] SnapshotDbError),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProfileExport {
+    pub format_version: u32,
+    /// Device name of the machine the profiles were exported from.
+    pub exported_from: String,
+    pub profiles: Vec<ExportedProfile>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportedProfile {
+    pub table: String,
+    pub key: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub row: ExportedRow,
+    /// Rows of other tables that belong to the profile, by table.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub children: BTreeMap<String, Vec<ExportedRow>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportedRow {
+    /// Columns that are NULL, which neither TOML nor `values` can express.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub nulls: Vec<String>,
+    pub values: BTreeMap<String, Cell>,
+}
+
+/// A non-NULL SQLite value.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Cell {
+    Integer(i64),
+    Real(f64),
+    Text(String),
+    /// Hex-encoded.
+    Blob {
+        blob: String,
+    },
+}
+
+impl ProfileExport {
+    pub fn to_text(&self, format: ExportFormat) -> Result<String, ExportError> {
+        match format {
+            ExportFormat::Toml => {
+                toml::to_string_pretty(self).map_err(|e| ExportError::Invalid(e.to_string()))
+            }
+            ExportFormat::Json => serde_json::to_string_pretty(self)
+                .map(|json| json + "\n")
+                .map_err(|e| ExportError::Invalid(e.to_string())),
+        }
+    }
+
+    pub fn parse(text: &str, format: ExportFormat) -> Result<Self, ExportError> {
+        let export: ProfileExport = match format {
+            ExportFormat::Toml => {
+                toml::from_str(text).map_err(|e| ExportError::Invalid(e.to_string()))?
+            }
+            ExportFormat::Json => {
+                serde_json::from_str(text).map_err(|e| ExportError::Invalid(e.to_string()))?
+            }
+        };
+        if export.format_version != EXPORT_FORMAT_VERSION {
+            return Err(ExportError::UnsupportedVersion(export.format_version));
+        }
+        Ok(export)
+    }
+}
+
+/// Export the given profiles of `snapshot` with the rows that belong to them.
+pub fn export_profiles(
+    snapshot: &ConfigSnapshot,
+    units: &[UnitRef],
+) -> Result<ProfileExport, SnapshotDbError> {
+    let db = SnapshotDb::open(snapshot)?;
+    let mut profiles = Vec::new();
+    for unit in units {
+        let (row, children) = unit_rows(&db, unit)?;
+        profiles.push(ExportedProfile {
+            table: unit.table.clone(),
+            key: unit.key.clone(),
+            name: row_label(&row),
+            row: export_row(&row),
+            children: children
+                .iter()
+                .map(|(table, rows)| (table.clone(), rows.iter().map(export_row).collect()))
+                .collect(),
+        });
+    }
+    Ok(ProfileExport {
+        format_version: EXPORT_FORMAT_VERSION,
+        exported_from: snapshot.meta.device_name.clone(),
+        profiles,
+    })
+}
+
+/// A copy of `base` with the exported profiles written in, and the units to
+/// copy from it into the live database.
+pub fn stage_import(
+    base: &ConfigSnapshot,
+    export: &ProfileExport,
+) -> Result<(ConfigSnapshot, Vec<UnitRef>), ExportError> {
+    let db = SnapshotDb::open(base)?;
+    let mut units = Vec::new();
+    for profile in &export.profiles {
+        let children = profile
+            .children
+            .iter()
+            .map(|(table, rows)| {
+                Ok((
+                    table.clone(),
+                    rows.iter().map(import_row).collect::<Result<_, _>>()?,
+                ))
+            })
+            .collect::<Result<BTreeMap<String, Vec<Row>>, ExportError>>()?;
+        replace_unit(&db, &profile.table, &import_row(&profile.row)?, &children)?;
+        units.push(UnitRef {
+            table: profile.table.clone(),
+            key: profile.key.clone(),
+        });
+    }
+    Ok((db.into_snapshot(base.meta.clone())?, units))
+}
+
+fn export_row(row: &Row) -> ExportedRow {
+    let mut exported = ExportedRow {
+        nulls: Vec::new(),
+        values: BTreeMap::new(),
+    };
+    for (column, value) in row {
+        let cell = match value {
+            Value::Null => {
+                exported.nulls.push(column.clone());
+                continue;
+            }
+            Value::Integer(i) => Cell::Integer(*i),
+            Value::Real(f) => Cell::Real(*f),
+            Value::Text(t) => Cell::Text(t.clone()),
+            Value::Blob(b) => Cell::Blob { blob: hex(b) },
+        };
+        exported.values.insert(column.clone(), cell);
+    }
+    exported
+}
+
+fn import_row(row: &ExportedRow) -> Result<Row, ExportError> {
+    let mut imported: Row = row.nulls.iter().map(|c| (c.clone(), Value::Null)).collect();
+    for (column, cell) in &row.values {
+        let value = match cell {
+            Cell::Integer(i) => Value::Integer(*i),
+            Cell::Real(f) => Value::Real(*f),
+            Cell::Text(t) => Value::Text(t.clone()),
+            Cell::Blob { blob } => Value::Blob(decode_hex(blob).ok_or_else(|| {
+                ExportError::Invalid(format!("column {} is not valid hex", column))
+            })?),
+        };
+        imported.insert(column.clone(), value);
+    }
+    Ok(imported)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snapshot_db::tests::snapshot_from_sql;
+
+    const SCHEMA: &str = "
+        CREATE TABLE profiles (id INTEGER PRIMARY KEY, name TEXT, color BLOB, gamma REAL,
+                               note TEXT);
+        CREATE TABLE profile_settings (
+            profile_id INTEGER REFERENCES profiles(id), key TEXT, value TEXT,
+            PRIMARY KEY (profile_id, key));";
+
+    fn fps() -> Vec<UnitRef> {
+        vec![UnitRef {
+            table: "profiles".into(),
+            key: "1".into(),
+        }]
+    }
+
+    #[test]
+    fn test_round_trip_is_lossless() {
+        let source = snapshot_from_sql(&format!(
+            "{} INSERT INTO profiles VALUES (1, 'FPS', x'ff0080', 2.0, NULL), (2, 'MOBA', NULL, 1.5, '');
+               INSERT INTO profile_settings VALUES (1, 'dpi', '800'), (1, 'rgb', 'red'), (2, 'dpi', '400');",
+            SCHEMA
+        ));
+        let export = export_profiles(&source, &fps()).unwrap();
+
+        for format in [ExportFormat::Toml, ExportFormat::Json] {
+            let text = export.to_text(format).unwrap();
+            let parsed = ProfileExport::parse(&text, format).unwrap();
+            assert_eq!(parsed, export);
+            // Re-exporting gives the same file
+            assert_eq!(parsed.to_text(format).unwrap(), text);
+        }
+        let toml = export.to_text(ExportFormat::Toml).unwrap();
+        assert!(toml.contains("value = \"800\""));
+
+        // Importing into a database where the profile was edited restores it exactly
+        let edited = snapshot_from_sql(&format!(
+            "{} INSERT INTO profiles VALUES (1, 'FPS edited', NULL, 1.0, 'x');
+               INSERT INTO profile_settings VALUES (1, 'dpi', '1600'), (1, 'extra', '1');",
+            SCHEMA
+        ));
+        let (staged, units) = stage_import(&edited, &export).unwrap();
+        assert_eq!(units.len(), 1);
+        assert_eq!(
+            export_profiles(&staged, &fps()).unwrap().profiles,
+            export.profiles
+        );
+    }
+
+    #[test]
+    fn test_rejects_unknown_version() {
+        let text = "format_version = 99\nexported_from = \"pc\"\nprofiles = []\n";
+        assert!(matches!(
+            ProfileExport::parse(text, ExportFormat::Toml),
+            Err(ExportError::UnsupportedVersion(99))
+        ));
+    }
+}
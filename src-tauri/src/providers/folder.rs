@@ -1,12 +1,15 @@
 //! Cloud folder sync provider — copies config to/from a shared folder
 //! (Dropbox, OneDrive, Google Drive, iCloud, or any synced directory).
 
-use super::{ConfigSnapshot, ProviderError, ProviderResult, SyncMeta, SyncProvider};
+use super::{ConfigSnapshot, ProviderError, ProviderResult, RemoteBackup, SyncMeta, SyncProvider};
 use chrono::Utc;
 use std::fs;
 use std::path::PathBuf;
 
 const META_FILE: &str = "sync_meta.json";
+/// Replicated backups live in `backups/<device>/<name>.ggsync`.
+const BACKUP_DIR: &str = "backups";
+const BACKUP_EXTENSION: &str = "ggsync";
 
 pub struct FolderProvider {
     sync_dir: PathBuf,
@@ -23,6 +26,49 @@ impl FolderProvider {
     pub fn new(sync_dir: PathBuf, device_name: String) -> Self {
         Self { sync_dir, device_name }
     }
+
+    /// Path of a stored backup. `None` if either component could escape the
+    /// backup directory.
+    fn backup_path(&self, device: &str, name: &str) -> Option<PathBuf> {
+        if !is_safe_component(device) || !is_safe_component(name) {
+            return None;
+        }
+        Some(
+            self.sync_dir
+                .join(BACKUP_DIR)
+                .join(device)
+                .join(format!("{}.{}", name, BACKUP_EXTENSION)),
+        )
+    }
+}
+
+/// A single path component with no separators or parent references.
+fn is_safe_component(name: &str) -> bool {
+    !name.is_empty()
+        && name != "."
+        && name != ".."
+        && !name
+            .chars()
+            .any(|c| matches!(c, '/' | '\\' | ':') || c.is_control())
+}
+
+/// Make a device name usable as a directory name.
+fn device_dir_name(device: &str) -> String {
+    let name: String = device
+        .chars()
+        .map(|c| {
+            if matches!(c, '/' | '\\' | ':') || c.is_control() {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect();
+    if is_safe_component(&name) {
+        name
+    } else {
+        "unknown".to_string()
+    }
 }
 
 #[async_trait::async_trait]
@@ -66,6 +112,65 @@ impl SyncProvider for FolderProvider {
             device_name: stored.device_name,
         })
     }
+
+    async fn upload_backup(&self, name: &str, archive: &[u8]) -> ProviderResult<()> {
+        let path = self
+            .backup_path(&device_dir_name(&self.device_name), name)
+            .ok_or_else(|| ProviderError::Other(format!("Invalid backup name '{}'", name)))?;
+        let dir = path.parent().expect("backup path has a parent");
+        fs::create_dir_all(dir)?;
+        // Stage and rename so other devices never see a partial archive
+        let tmp = dir.join(format!("{}.tmp", name));
+        fs::write(&tmp, archive)?;
+        fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+
+    async fn list_backups(&self) -> ProviderResult<Vec<RemoteBackup>> {
+        let root = self.sync_dir.join(BACKUP_DIR);
+        let mut backups = Vec::new();
+        if !root.exists() {
+            return Ok(backups);
+        }
+        for device in fs::read_dir(&root)? {
+            let device = device?;
+            if !device.file_type()?.is_dir() {
+                continue;
+            }
+            let device_name = device.file_name().to_string_lossy().to_string();
+            for file in fs::read_dir(device.path())? {
+                let file = file?;
+                let path = file.path();
+                if path.extension().and_then(|e| e.to_str()) != Some(BACKUP_EXTENSION) {
+                    continue;
+                }
+                let Some(name) = path.file_stem().map(|s| s.to_string_lossy().to_string()) else {
+                    continue;
+                };
+                let meta = file.metadata()?;
+                backups.push(RemoteBackup {
+                    id: format!("{}/{}", device_name, name),
+                    name,
+                    device_name: device_name.clone(),
+                    size: meta.len(),
+                    uploaded: chrono::DateTime::<Utc>::from(meta.modified()?),
+                });
+            }
+        }
+        backups.sort_by_key(|b| std::cmp::Reverse(b.uploaded));
+        Ok(backups)
+    }
+
+    async fn fetch_backup(&self, id: &str) -> ProviderResult<Vec<u8>> {
+        let path = id
+            .split_once('/')
+            .and_then(|(device, name)| self.backup_path(device, name))
+            .ok_or(ProviderError::NotFound)?;
+        fs::read(path).map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => ProviderError::NotFound,
+            _ => ProviderError::Io(e),
+        })
+    }
 }
 
 #[cfg(test)]
@@ -144,4 +249,39 @@ mod tests {
         let meta = provider.remote_meta().await.unwrap();
         assert_eq!(meta.device_name, "gaming-rig");
     }
+
+    #[tokio::test]
+    async fn test_backup_upload_list_fetch() {
+        let tmp = TempDir::new().unwrap();
+        let desk = FolderProvider::new(tmp.path().to_path_buf(), "desk/pc".into());
+        let laptop = FolderProvider::new(tmp.path().to_path_buf(), "laptop".into());
+
+        desk.upload_backup("manual-01ABC", b"archive-bytes")
+            .await
+            .unwrap();
+
+        let listed = laptop.list_backups().await.unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].device_name, "desk_pc");
+        assert_eq!(listed[0].name, "manual-01ABC");
+        assert_eq!(
+            laptop.fetch_backup(&listed[0].id).await.unwrap(),
+            b"archive-bytes"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fetch_backup_rejects_traversal() {
+        let tmp = TempDir::new().unwrap();
+        let provider = FolderProvider::new(tmp.path().join("sync"), "pc".into());
+        fs::write(tmp.path().join("secret.ggsync"), b"x").unwrap();
+
+        for id in ["../secret", "pc/../../secret", "..", "pc/"] {
+            assert!(matches!(
+                provider.fetch_backup(id).await,
+                Err(ProviderError::NotFound)
+            ));
+        }
+        assert!(provider.upload_backup("../escape", b"x").await.is_err());
+    }
 }
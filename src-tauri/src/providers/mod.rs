@@ -19,6 +19,30 @@ pub struct ConfigSnapshot {
     pub meta: SyncMeta,
 }
 
+impl ConfigSnapshot {
+    /// Total size in bytes of the database and its sidecars.
+    pub fn total_size(&self) -> u64 {
+        let sidecars = [&self.db_shm, &self.db_wal]
+            .iter()
+            .filter_map(|f| f.as_ref())
+            .map(|f| f.len() as u64)
+            .sum::<u64>();
+        self.db.len() as u64 + sidecars
+    }
+}
+
+/// A backup archive stored with a provider.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RemoteBackup {
+    /// Opaque identifier passed to `fetch_backup`.
+    pub id: String,
+    /// Name of the backup on the device that uploaded it.
+    pub name: String,
+    pub device_name: String,
+    pub size: u64,
+    pub uploaded: chrono::DateTime<chrono::Utc>,
+}
+
 /// The result type for provider operations.
 pub type ProviderResult<T> = Result<T, ProviderError>;
 
@@ -30,6 +54,8 @@ pub enum ProviderError {
     Network(#[from] reqwest::Error),
     #[error("No remote config found")]
     NotFound,
+    #[error("Not supported by this provider")]
+    Unsupported,
     #[error("Provider error: {0}")]
     Other(String),
 }
@@ -45,4 +71,20 @@ pub trait SyncProvider: Send + Sync {
 
     /// Get metadata about the remote config without downloading files.
     async fn remote_meta(&self) -> ProviderResult<SyncMeta>;
+
+    /// Store a backup archive (see `archive`) under `name`, namespaced by device.
+    /// Backup storage is optional; the default reports `Unsupported`.
+    async fn upload_backup(&self, _name: &str, _archive: &[u8]) -> ProviderResult<()> {
+        Err(ProviderError::Unsupported)
+    }
+
+    /// List backup archives uploaded by any device, newest first.
+    async fn list_backups(&self) -> ProviderResult<Vec<RemoteBackup>> {
+        Err(ProviderError::Unsupported)
+    }
+
+    /// Download a backup archive by `RemoteBackup::id`.
+    async fn fetch_backup(&self, _id: &str) -> ProviderResult<Vec<u8>> {
+        Err(ProviderError::Unsupported)
+    }
 }
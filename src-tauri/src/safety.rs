@@ -1,7 +1,11 @@
 //! Safety guard — checks GG process state and file locks before sync operations.
 
-use std::path::Path;
-use sysinfo::System;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use sysinfo::{Pid, System};
+
+/// Default headroom required on top of the bytes about to be written.
+pub const DEFAULT_SPACE_MARGIN: u64 = 64 * 1024 * 1024;
 
 const GG_PROCESS_NAMES: &[&str] = &[
     "SteelSeriesGG",
@@ -25,11 +29,72 @@ impl SafetyGuard {
 
     /// Check if SteelSeries GG is currently running.
     pub fn is_gg_running(&mut self) -> bool {
+        !self.gg_pids().is_empty()
+    }
+
+    /// PIDs of all running GG processes.
+    fn gg_pids(&mut self) -> Vec<Pid> {
         self.system.refresh_processes(sysinfo::ProcessesToUpdate::All, true);
-        self.system.processes().values().any(|p| {
-            let name = p.name().to_string_lossy();
-            GG_PROCESS_NAMES.iter().any(|gg| name.contains(gg))
-        })
+        self.system
+            .processes()
+            .iter()
+            .filter(|(_, p)| {
+                let name = p.name().to_string_lossy();
+                GG_PROCESS_NAMES.iter().any(|gg| name.contains(gg))
+            })
+            .map(|(pid, _)| *pid)
+            .collect()
+    }
+
+    /// Ask every running GG process to exit gracefully.
+    /// Returns the number of processes that accepted the request.
+    pub fn request_gg_exit(&mut self) -> usize {
+        self.gg_pids()
+            .into_iter()
+            .filter(|pid| self.request_exit(*pid))
+            .count()
+    }
+
+    #[cfg(target_os = "windows")]
+    fn request_exit(&self, pid: Pid) -> bool {
+        // taskkill without /F posts WM_CLOSE, letting GG flush its database.
+        Command::new("taskkill")
+            .args(["/PID", &pid.to_string()])
+            .status()
+            .map(|s| s.success())
+            .unwrap_or(false)
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    fn request_exit(&self, pid: Pid) -> bool {
+        self.system
+            .process(pid)
+            .and_then(|p| p.kill_with(sysinfo::Signal::Term))
+            .unwrap_or(false)
+    }
+
+    /// Forcefully terminate GG. Only call this when the user has consented.
+    pub fn force_kill_gg(&mut self) -> usize {
+        self.gg_pids()
+            .into_iter()
+            .filter(|pid| self.system.process(*pid).map(|p| p.kill()).unwrap_or(false))
+            .count()
+    }
+
+    /// Launch GG from the given executable (or .app bundle on macOS).
+    pub fn launch_gg(executable: &Path) -> std::io::Result<()> {
+        if !executable.exists() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("GG executable not found: {}", executable.display()),
+            ));
+        }
+        if cfg!(target_os = "macos") {
+            Command::new("open").arg("-a").arg(executable).spawn()?;
+        } else {
+            Command::new(executable).spawn()?;
+        }
+        Ok(())
     }
 
     /// Check if a file can be read (not locked by another process).
@@ -66,12 +131,82 @@ impl SafetyGuard {
     }
 }
 
+/// Control over the GG processes, so the stop/relaunch sequence around a pull
+/// can run against something other than the real process list.
+pub trait GgProcesses: Send {
+    fn is_gg_running(&mut self) -> bool;
+    fn request_gg_exit(&mut self) -> usize;
+    fn force_kill_gg(&mut self) -> usize;
+    fn launch_gg(&mut self, executable: &Path) -> std::io::Result<()>;
+}
+
+impl GgProcesses for SafetyGuard {
+    fn is_gg_running(&mut self) -> bool {
+        SafetyGuard::is_gg_running(self)
+    }
+
+    fn request_gg_exit(&mut self) -> usize {
+        SafetyGuard::request_gg_exit(self)
+    }
+
+    fn force_kill_gg(&mut self) -> usize {
+        SafetyGuard::force_kill_gg(self)
+    }
+
+    fn launch_gg(&mut self, executable: &Path) -> std::io::Result<()> {
+        SafetyGuard::launch_gg(executable)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum SafetyCheck {
     Safe,
     GGRunning,
     FileLocked,
     NoConfig,
+    /// Not enough free space on the target volume (bytes, margin included).
+    InsufficientSpace {
+        required: u64,
+        available: u64,
+    },
+}
+
+/// Free bytes on the volume holding `path`, or `None` if it can't be determined.
+pub fn available_space(path: &Path) -> Option<u64> {
+    let target = existing_ancestor(path)?.canonicalize().ok()?;
+    let disks = sysinfo::Disks::new_with_refreshed_list();
+    disks
+        .list()
+        .iter()
+        .filter(|d| target.starts_with(d.mount_point()))
+        .max_by_key(|d| d.mount_point().as_os_str().len())
+        .map(|d| d.available_space())
+}
+
+/// The path itself or its nearest parent that exists (targets may not be created yet).
+fn existing_ancestor(path: &Path) -> Option<PathBuf> {
+    path.ancestors().find(|p| p.exists()).map(Path::to_path_buf)
+}
+
+/// Check that `required` bytes plus `margin` fit on the volume holding `target_dir`.
+/// Unknown free space is treated as safe so unusual mounts never block a sync.
+pub fn check_disk_space(target_dir: &Path, required: u64, margin: u64) -> SafetyCheck {
+    match available_space(target_dir) {
+        Some(available) => space_check(available, required, margin),
+        None => SafetyCheck::Safe,
+    }
+}
+
+fn space_check(available: u64, required: u64, margin: u64) -> SafetyCheck {
+    let required = required.saturating_add(margin);
+    if available >= required {
+        SafetyCheck::Safe
+    } else {
+        SafetyCheck::InsufficientSpace {
+            required,
+            available,
+        }
+    }
 }
 
 /// Validate that a file looks like a valid SQLite database.
@@ -81,6 +216,16 @@ pub fn validate_sqlite_header(data: &[u8]) -> bool {
     data.len() > SQLITE_MAGIC.len() && data[..SQLITE_MAGIC.len()] == *SQLITE_MAGIC
 }
 
+/// Read the SQLite `user_version` header field, which apps use as a schema version.
+/// Returns `None` for non-SQLite data or an unset (zero) version.
+pub fn sqlite_user_version(data: &[u8]) -> Option<u32> {
+    if !validate_sqlite_header(data) || data.len() < 64 {
+        return None;
+    }
+    let version = u32::from_be_bytes([data[60], data[61], data[62], data[63]]);
+    (version != 0).then_some(version)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -100,6 +245,16 @@ mod tests {
         assert!(!validate_sqlite_header(b"SQLite format 3")); // missing null
     }
 
+    #[test]
+    fn test_sqlite_user_version() {
+        let mut data = b"SQLite format 3\0".to_vec();
+        data.resize(100, 0);
+        assert_eq!(sqlite_user_version(&data), None);
+        data[60..64].copy_from_slice(&7u32.to_be_bytes());
+        assert_eq!(sqlite_user_version(&data), Some(7));
+        assert_eq!(sqlite_user_version(b"not a database"), None);
+    }
+
     #[test]
     fn test_safety_check_no_config() {
         let tmp = TempDir::new().unwrap();
@@ -127,4 +282,29 @@ mod tests {
     fn test_cannot_read_missing_file() {
         assert!(!SafetyGuard::can_read_file(Path::new("/nonexistent/file.db")));
     }
+
+    #[test]
+    fn test_space_check_includes_margin() {
+        assert_eq!(space_check(1000, 500, 400), SafetyCheck::Safe);
+        assert_eq!(
+            space_check(1000, 700, 400),
+            SafetyCheck::InsufficientSpace {
+                required: 1100,
+                available: 1000
+            }
+        );
+    }
+
+    #[test]
+    fn test_check_disk_space_on_missing_subdir() {
+        let tmp = TempDir::new().unwrap();
+        let target = tmp.path().join("not").join("created");
+        assert_eq!(check_disk_space(&target, 0, 0), SafetyCheck::Safe);
+    }
+
+    #[test]
+    fn test_launch_gg_missing_executable() {
+        let err = SafetyGuard::launch_gg(Path::new("/nonexistent/SteelSeriesGG")).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+    }
 }
@@ -0,0 +1,93 @@
+//! Backup scheduler — periodic and on-GG-exit snapshots, independent of sync.
+
+use crate::config::BackupScheduleConfig;
+use crate::sync_engine::SyncEngine;
+use chrono::{DateTime, Utc};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+pub struct BackupScheduler {
+    engine: Arc<SyncEngine>,
+    settings: BackupScheduleConfig,
+    /// Last time the schedule fired, whether or not a backup was written.
+    last_run: Option<DateTime<Utc>>,
+    gg_was_running: bool,
+}
+
+impl BackupScheduler {
+    pub fn new(engine: Arc<SyncEngine>, settings: BackupScheduleConfig) -> Self {
+        Self {
+            engine,
+            settings,
+            last_run: None,
+            gg_was_running: false,
+        }
+    }
+
+    /// Whether any trigger is enabled.
+    pub fn is_enabled(&self) -> bool {
+        self.settings.interval.duration().is_some() || self.settings.on_gg_exit
+    }
+
+    /// Check the triggers and take a backup if one fired.
+    /// Returns the new backup's path, if one was written.
+    pub async fn tick(&mut self) -> std::io::Result<Option<PathBuf>> {
+        let gg_running = self.engine.is_gg_running().await;
+        let gg_exited = self.gg_was_running && !gg_running;
+        self.gg_was_running = gg_running;
+
+        let now = Utc::now();
+        let last = self.last_run.or_else(|| self.last_scheduled_backup());
+        let timer_due = is_due(self.settings.interval.duration(), last, now);
+        if !(timer_due || (self.settings.on_gg_exit && gg_exited)) {
+            return Ok(None);
+        }
+
+        self.last_run = Some(now);
+        let backup = self.engine.scheduled_backup()?;
+        match &backup {
+            Some(path) => log::info!("Scheduled backup written to {:?}", path),
+            None => log::debug!("Scheduled backup skipped: config unchanged"),
+        }
+        Ok(backup)
+    }
+
+    /// Creation time of the newest scheduled backup on disk.
+    fn last_scheduled_backup(&self) -> Option<DateTime<Utc>> {
+        let backups = self.engine.backups().list_backups().ok()?;
+        backups
+            .iter()
+            .find(|b| b.is_scheduled())
+            .and_then(|b| b.manifest.as_ref())
+            .map(|m| m.created)
+    }
+}
+
+/// Whether an interval schedule is due, given when it last ran.
+fn is_due(
+    interval: Option<chrono::Duration>,
+    last: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+) -> bool {
+    match (interval, last) {
+        (None, _) => false,
+        (Some(_), None) => true,
+        (Some(interval), Some(last)) => now - last >= interval,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn test_is_due() {
+        let now = Utc::now();
+        let hourly = Some(Duration::hours(1));
+        assert!(!is_due(None, None, now));
+        assert!(is_due(hourly, None, now));
+        assert!(!is_due(hourly, Some(now - Duration::minutes(59)), now));
+        assert!(is_due(hourly, Some(now - Duration::minutes(60)), now));
+    }
+}
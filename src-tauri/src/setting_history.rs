@@ -0,0 +1,198 @@
+//! Timeline of a single setting's value across stored configs.
+//!
+//! Each stored config (local backups, replicated backups, the live config) is
+//! inspected, the setting looked up by device, profile and setting name, and
+//! consecutive samples compared. Only changes are kept, each attributed to the
+//! first config it was seen in, which answers "when did this change, and on
+//! which machine?" to the resolution of the backups taken.
+
+use crate::inspector::{inspect_snapshot, DeviceInfo};
+use crate::providers::ConfigSnapshot;
+use crate::snapshot_db::SnapshotDbError;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Which setting to follow. Names match case-insensitively; keys exactly.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SettingQuery {
+    /// Device name or key. Required for device settings, optional for profiles.
+    #[serde(default)]
+    pub device: Option<String>,
+    /// Profile name or key. Without one, the setting is a device setting.
+    #[serde(default)]
+    pub profile: Option<String>,
+    pub setting: String,
+}
+
+/// Where a sampled config came from.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SampleSource {
+    pub at: DateTime<Utc>,
+    /// Machine whose config it was.
+    pub device: String,
+    /// Backup it was read from; `None` for the live config.
+    pub backup_id: Option<String>,
+    /// Whether it was a backup replicated to the sync provider.
+    pub remote: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SettingChange {
+    #[serde(flatten)]
+    pub source: SampleSource,
+    /// `None` when the setting (or its profile) no longer exists.
+    pub value: Option<serde_json::Value>,
+    pub previous: Option<serde_json::Value>,
+}
+
+/// The setting's value in `snapshot`, if it has the device/profile and setting.
+pub fn find_setting(
+    snapshot: &ConfigSnapshot,
+    query: &SettingQuery,
+) -> Result<Option<serde_json::Value>, SnapshotDbError> {
+    let inspection = inspect_snapshot(snapshot)?;
+    let devices: Vec<&DeviceInfo> = inspection
+        .devices
+        .iter()
+        .filter(|d| {
+            query
+                .device
+                .as_deref()
+                .is_none_or(|q| matches(q, d.name.as_deref(), &d.key))
+        })
+        .collect();
+
+    let value = match &query.profile {
+        Some(profile) => inspection
+            .profiles
+            .iter()
+            .filter(|p| matches(profile, p.name.as_deref(), &p.key))
+            .filter(|p| {
+                query.device.is_none()
+                    || p.device
+                        .as_ref()
+                        .is_some_and(|key| devices.iter().any(|d| &d.key == key))
+            })
+            .find_map(|p| lookup(&p.settings, &query.setting)),
+        None => devices
+            .iter()
+            .find_map(|d| lookup(&d.details, &query.setting)),
+    };
+    Ok(value)
+}
+
+/// Add a sample to a chronological timeline, keeping it only if the value
+/// differs from the last one recorded. A setting that doesn't exist yet in the
+/// first samples isn't a change.
+pub fn record_sample(
+    timeline: &mut Vec<SettingChange>,
+    source: SampleSource,
+    value: Option<serde_json::Value>,
+) {
+    let previous = match timeline.last() {
+        Some(last) if last.value == value => return,
+        Some(last) => last.value.clone(),
+        None if value.is_none() => return,
+        None => None,
+    };
+    timeline.push(SettingChange {
+        source,
+        value,
+        previous,
+    });
+}
+
+fn matches(query: &str, name: Option<&str>, key: &str) -> bool {
+    key == query || name.is_some_and(|n| n.eq_ignore_ascii_case(query))
+}
+
+fn lookup(
+    values: &BTreeMap<String, serde_json::Value>,
+    setting: &str,
+) -> Option<serde_json::Value> {
+    values
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(setting))
+        .map(|(_, value)| value.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snapshot_db::tests::snapshot_from_sql;
+
+    fn config(dpi: Option<&str>) -> ConfigSnapshot {
+        let setting = dpi
+            .map(|v| {
+                format!(
+                    "INSERT INTO profile_settings VALUES (10, 'Sensitivity', '{}');",
+                    v
+                )
+            })
+            .unwrap_or_default();
+        snapshot_from_sql(&format!(
+            "CREATE TABLE devices (id INTEGER PRIMARY KEY, name TEXT, firmware TEXT);
+             CREATE TABLE profiles (id INTEGER PRIMARY KEY, name TEXT, device_id INTEGER);
+             CREATE TABLE profile_settings (profile_id INTEGER, key TEXT, value TEXT,
+                                            PRIMARY KEY (profile_id, key));
+             INSERT INTO devices VALUES (1, 'Rival 3', '1.0');
+             INSERT INTO profiles VALUES (10, 'FPS', 1);
+             {}",
+            setting
+        ))
+    }
+
+    fn source(hour: u32, device: &str) -> SampleSource {
+        SampleSource {
+            at: format!("2026-01-01T{:02}:00:00Z", hour).parse().unwrap(),
+            device: device.into(),
+            backup_id: Some(format!("backup-{}", hour)),
+            remote: false,
+        }
+    }
+
+    #[test]
+    fn test_timeline_keeps_only_changes() {
+        let query = SettingQuery {
+            device: Some("rival 3".into()),
+            profile: Some("FPS".into()),
+            setting: "sensitivity".into(),
+        };
+        let samples = [
+            (source(1, "desk"), config(None)),
+            (source(2, "desk"), config(Some("800"))),
+            (source(3, "laptop"), config(Some("800"))),
+            (source(4, "laptop"), config(Some("1600"))),
+        ];
+
+        let mut timeline = Vec::new();
+        for (source, snapshot) in samples {
+            record_sample(
+                &mut timeline,
+                source,
+                find_setting(&snapshot, &query).unwrap(),
+            );
+        }
+
+        assert_eq!(timeline.len(), 2);
+        assert_eq!(timeline[0].value, Some("800".into()));
+        assert_eq!(timeline[0].source.device, "desk");
+        assert_eq!(timeline[1].previous, Some("800".into()));
+        assert_eq!(timeline[1].value, Some("1600".into()));
+        assert_eq!(timeline[1].source.backup_id.as_deref(), Some("backup-4"));
+    }
+
+    #[test]
+    fn test_device_setting_lookup() {
+        let query = SettingQuery {
+            device: Some("1".into()),
+            profile: None,
+            setting: "Firmware".into(),
+        };
+        assert_eq!(
+            find_setting(&config(None), &query).unwrap(),
+            Some("1.0".into())
+        );
+    }
+}
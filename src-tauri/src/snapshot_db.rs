@@ -0,0 +1,138 @@
+//! SQLite access to GG config snapshots.
+//!
+//! A snapshot is a set of in-memory files (`database.db` plus optional WAL/SHM).
+//! To query it, the files are written to a private temporary directory and opened
+//! there, so the live database, backups and remote copies are never touched.
+
+use crate::providers::{ConfigSnapshot, SyncMeta};
+use crate::safety::validate_sqlite_header;
+use rusqlite::Connection;
+use std::fs;
+use tempfile::TempDir;
+
+pub struct SnapshotDb {
+    conn: Connection,
+    /// Keeps the materialized files alive for as long as the connection.
+    dir: TempDir,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SnapshotDbError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("SQLite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+    #[error("Not found: {0}")]
+    NotFound(String),
+}
+
+impl SnapshotDb {
+    /// Materialize a snapshot into a temporary directory and open it.
+    pub fn open(snapshot: &ConfigSnapshot) -> Result<Self, SnapshotDbError> {
+        let dir = tempfile::tempdir()?;
+        fs::write(dir.path().join("database.db"), &snapshot.db)?;
+        if let Some(shm) = &snapshot.db_shm {
+            fs::write(dir.path().join("database.db-shm"), shm)?;
+        }
+        if let Some(wal) = &snapshot.db_wal {
+            fs::write(dir.path().join("database.db-wal"), wal)?;
+        }
+        let conn = Connection::open(dir.path().join("database.db"))?;
+        Ok(Self { conn, dir })
+    }
+
+    pub fn conn(&self) -> &Connection {
+        &self.conn
+    }
+
+    /// Read the database back, including any changes made through `conn`.
+    /// Closing the connection checkpoints the WAL, so the result is a single file.
+    pub fn into_snapshot(self, meta: SyncMeta) -> Result<ConfigSnapshot, SnapshotDbError> {
+        let Self { conn, dir } = self;
+        conn.close().map_err(|(_, e)| e)?;
+        Ok(ConfigSnapshot {
+            db: fs::read(dir.path().join("database.db"))?,
+            db_shm: None,
+            db_wal: None,
+            meta,
+        })
+    }
+
+    /// Run `PRAGMA integrity_check`. Returns the reported problems (empty if ok).
+    pub fn integrity_check(&self) -> Result<Vec<String>, SnapshotDbError> {
+        let mut stmt = self.conn.prepare("PRAGMA integrity_check")?;
+        let rows = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows.into_iter().filter(|r| r != "ok").collect())
+    }
+}
+
+/// Check that a snapshot holds an intact SQLite database.
+/// Returns a description of the problem if it doesn't.
+pub fn check_integrity(snapshot: &ConfigSnapshot) -> Result<(), String> {
+    if !validate_sqlite_header(&snapshot.db) {
+        return Err("database.db is not a SQLite database".to_string());
+    }
+    let problems = SnapshotDb::open(snapshot)
+        .and_then(|db| db.integrity_check())
+        .map_err(|e| e.to_string())?;
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(problems.join("; "))
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Build a snapshot from SQL statements, for tests across modules.
+    pub(crate) fn snapshot_from_sql(sql: &str) -> ConfigSnapshot {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("database.db");
+        let conn = Connection::open(&path).unwrap();
+        conn.execute_batch(sql).unwrap();
+        drop(conn);
+        ConfigSnapshot {
+            db: fs::read(&path).unwrap(),
+            db_shm: None,
+            db_wal: None,
+            meta: SyncMeta {
+                last_modified: chrono::Utc::now(),
+                device_name: "test-pc".into(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_open_and_query_snapshot() {
+        let snapshot = snapshot_from_sql(
+            "CREATE TABLE devices (id INTEGER PRIMARY KEY, name TEXT);
+             INSERT INTO devices (name) VALUES ('Rival 3');",
+        );
+        let db = SnapshotDb::open(&snapshot).unwrap();
+        let name: String = db
+            .conn()
+            .query_row("SELECT name FROM devices", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(name, "Rival 3");
+        assert!(db.integrity_check().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_check_integrity() {
+        let mut snapshot = snapshot_from_sql("CREATE TABLE t (x); INSERT INTO t VALUES (1);");
+        assert!(check_integrity(&snapshot).is_ok());
+
+        snapshot.db = b"not a database at all".to_vec();
+        assert!(check_integrity(&snapshot).is_err());
+
+        // Valid header, truncated body
+        let mut truncated = snapshot_from_sql("CREATE TABLE t (x);").db;
+        truncated.truncate(200);
+        snapshot.db = truncated;
+        assert!(check_integrity(&snapshot).is_err());
+    }
+}
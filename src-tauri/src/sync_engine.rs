@@ -1,14 +1,47 @@
 //! Core sync orchestration — coordinates watcher, safety, backup, and provider.
 
-use crate::backup::BackupManager;
-use crate::config::AppConfig;
-use crate::providers::{ConfigSnapshot, ProviderError, SyncMeta, SyncProvider};
-use crate::safety::{validate_sqlite_header, SafetyCheck, SafetyGuard};
+use crate::archive::{read_archive, write_archive, ArchiveError};
+use crate::backup::{BackupManager, BackupReason};
+use crate::changes::summarize_changes;
+use crate::config::{AppConfig, GgRestartConfig, KioskConfig};
+use crate::device_map::{
+    suggest_pairings, translate_devices, DeviceMap, DeviceMapStore, DEVICE_MAP_FILE,
+};
+use crate::diff::{diff_snapshots, table_names, SnapshotDiff, SnapshotSource};
+use crate::history::{
+    LastSynced, SyncDirection, SyncHistory, SyncHistoryEntry, HISTORY_FILE, LAST_SYNCED_FILE,
+};
+use crate::inspector::{inspect_snapshot, Inspection};
+use crate::kiosk::verify_password;
+use crate::partial_restore::{
+    delete_units, filter_units, list_units, restore_units, RestoreUnit, UnitRef,
+};
+use crate::path_mapping::{map_paths, OsFamily};
+use crate::profile_export::{
+    export_profiles, stage_import, ExportError, ExportFormat, ProfileExport,
+};
+use crate::providers::{ConfigSnapshot, ProviderError, RemoteBackup, SyncMeta, SyncProvider};
+use crate::safety::{
+    check_disk_space, validate_sqlite_header, GgProcesses, SafetyCheck, SafetyGuard,
+};
+use crate::setting_history::{
+    find_setting, record_sample, SampleSource, SettingChange, SettingQuery,
+};
+use crate::snapshot_db::{check_integrity, SnapshotDb, SnapshotDbError};
+use crate::transform::{restore_machine_fields, strip_machine_fields};
 use chrono::Utc;
+use std::collections::HashSet;
 use std::fs;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::Mutex;
 
+/// How often to re-check the process list while waiting for GG to exit.
+const GG_EXIT_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Longest wait after a wrong kiosk override password.
+const MAX_UNLOCK_BACKOFF: Duration = Duration::from_secs(30);
+
 pub struct SyncEngine {
     config: AppConfig,
     provider: Arc<dyn SyncProvider>,
@@ -16,12 +49,51 @@ pub struct SyncEngine {
     safety: Mutex<SafetyGuard>,
     /// Suppresses the next watcher-triggered push after a pull (prevents feedback loop).
     pull_in_progress: std::sync::atomic::AtomicBool,
+    /// Restore deferred until GG exits.
+    pending_restore: std::sync::Mutex<Option<String>>,
+    history: SyncHistory,
+    device_map: DeviceMapStore,
+    /// The config as of the last push or pull, to summarize the next one against.
+    last_synced: LastSynced,
+    /// Kiosk mode lifted with the override password for this session.
+    kiosk_unlocked: std::sync::atomic::AtomicBool,
+    /// Wrong override passwords in a row; held across an attempt so attempts
+    /// run one at a time.
+    unlock_failures: Mutex<u32>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum SyncResult {
-    Pushed,
-    Pulled { from_device: String, gg_was_running: bool },
+    /// `changes` summarizes what the push changed remotely.
+    Pushed {
+        changes: Vec<String>,
+    },
+    Pulled {
+        from_device: String,
+        gg_was_running: bool,
+        gg_restarted: bool,
+        changes: Vec<String>,
+    },
+    Restored {
+        backup_id: String,
+        gg_restarted: bool,
+    },
+    /// Selected devices/profiles were copied from a backup.
+    PartiallyRestored {
+        backup_id: String,
+        rows: usize,
+        gg_restarted: bool,
+    },
+    /// Profiles from a text export were written into the live config.
+    ProfilesImported {
+        file: String,
+        profiles: usize,
+        gg_restarted: bool,
+    },
+    /// An archive was stored as a backup without being applied.
+    Imported {
+        backup_id: String,
+    },
     Skipped(SkipReason),
 }
 
@@ -33,20 +105,50 @@ pub enum SkipReason {
     NoRemoteConfig,
     AlreadyInSync,
     InvalidRemoteFile,
+    /// GG did not exit within the configured timeout; nothing was written.
+    GGStopTimeout,
+    /// A backup failed its integrity check and was not restored.
+    CorruptBackup,
+    /// Nothing to undo.
+    NoRestoreToUndo,
+    /// Kiosk mode is on; pushes, pulls and restores are disabled until it is
+    /// unlocked.
+    KioskMode,
+    /// Not enough free space for the write (bytes, margin included).
+    InsufficientDiskSpace {
+        required: u64,
+        available: u64,
+    },
 }
 
 impl SyncEngine {
     pub fn new(config: AppConfig, provider: Arc<dyn SyncProvider>) -> Self {
-        let backup_manager = BackupManager::new(
-            config.backup_dir.clone(),
-            config.max_backups,
-        );
+        let backup_manager = BackupManager::new(config.backup_dir.clone(), config.max_backups)
+            .with_retention(config.retention.clone())
+            .with_scheduled_retention(
+                config.backup_schedule.max_backups,
+                config.backup_schedule.retention.clone(),
+            )
+            .with_budget(
+                Some(config.backup_budget_mb.saturating_mul(1024 * 1024)).filter(|&b| b > 0),
+            )
+            .with_space_margin(config.disk_space_margin_mb.saturating_mul(1024 * 1024))
+            .with_device_name(config.device_name.clone());
+        let history = SyncHistory::new(config.backup_dir.join(HISTORY_FILE));
+        let device_map = DeviceMapStore::new(config.backup_dir.join(DEVICE_MAP_FILE));
+        let last_synced = LastSynced::new(config.backup_dir.join(LAST_SYNCED_FILE));
         Self {
             config,
             provider,
             backup_manager,
             safety: Mutex::new(SafetyGuard::new()),
             pull_in_progress: std::sync::atomic::AtomicBool::new(false),
+            pending_restore: std::sync::Mutex::new(None),
+            history,
+            device_map,
+            last_synced,
+            kiosk_unlocked: std::sync::atomic::AtomicBool::new(false),
+            unlock_failures: Mutex::new(0),
         }
     }
 
@@ -68,37 +170,300 @@ impl SyncEngine {
     }
 
     /// Write a snapshot to the local config directory.
+    /// Every file is staged next to its target and renamed into place; sidecars
+    /// missing from the snapshot are removed so a stale WAL is never replayed.
     fn write_local_config(&self, snapshot: &ConfigSnapshot) -> std::io::Result<()> {
         let dir = &self.config.steelseries_db_path;
         fs::create_dir_all(dir)?;
-        fs::write(dir.join("database.db"), &snapshot.db)?;
-        if let Some(shm) = &snapshot.db_shm {
-            fs::write(dir.join("database.db-shm"), shm)?;
+        let files: [(&str, Option<&Vec<u8>>); 3] = [
+            ("database.db", Some(&snapshot.db)),
+            ("database.db-shm", snapshot.db_shm.as_ref()),
+            ("database.db-wal", snapshot.db_wal.as_ref()),
+        ];
+        for (name, data) in &files {
+            if let Some(data) = data {
+                fs::write(dir.join(format!("{}.sync-tmp", name)), data)?;
+            }
         }
-        if let Some(wal) = &snapshot.db_wal {
-            fs::write(dir.join("database.db-wal"), wal)?;
+        for (name, data) in &files {
+            let target = dir.join(name);
+            if data.is_some() {
+                fs::rename(dir.join(format!("{}.sync-tmp", name)), &target)?;
+            } else if target.exists() {
+                fs::remove_file(&target)?;
+            }
         }
         Ok(())
     }
 
+    /// Ask GG to exit before a write; see `stop_gg`.
+    async fn stop_gg(&self) -> Result<(), SkipReason> {
+        stop_gg(&self.safety, &self.config.gg_restart, GG_EXIT_POLL_INTERVAL).await
+    }
+
+    /// Relaunch GG after a controlled stop. Returns whether the launch succeeded.
+    async fn relaunch_gg(&self) -> bool {
+        relaunch_gg(&self.safety, &self.config.gg_restart.executable_path).await
+    }
+
     /// Push local config to the remote provider.
     pub async fn push_to_remote(&self) -> Result<SyncResult, SyncError> {
+        if self.kiosk_locked() {
+            return Ok(SyncResult::Skipped(SkipReason::KioskMode));
+        }
         let mut safety = self.safety.lock().await;
         match safety.is_safe_to_read(&self.config.steelseries_db_path) {
             SafetyCheck::Safe => {}
             SafetyCheck::NoConfig => return Ok(SyncResult::Skipped(SkipReason::NoLocalConfig)),
             SafetyCheck::FileLocked => return Ok(SyncResult::Skipped(SkipReason::FileLocked)),
             SafetyCheck::GGRunning => {} // safe to read while GG runs
+            SafetyCheck::InsufficientSpace { .. } => {} // not checked for reads
         }
         drop(safety);
 
-        let snapshot = self.read_local_config()?;
+        let mut snapshot = self.read_local_config()?;
+        if self.config.selective_sync.is_active() || self.config.field_transforms.is_active() {
+            snapshot = self.prepare_for_push(snapshot).await?;
+        }
+        // Only the very first push from this machine has nothing to compare against
+        let previous = self.last_synced.get();
         self.provider.push(&snapshot).await?;
-        Ok(SyncResult::Pushed)
+        let changes = self
+            .record_sync(SyncDirection::Push, previous, snapshot)
+            .await;
+        Ok(SyncResult::Pushed { changes })
+    }
+
+    /// Drop the profiles and devices excluded from selective sync and strip
+    /// machine-specific fields.
+    async fn prepare_for_push(
+        &self,
+        snapshot: ConfigSnapshot,
+    ) -> Result<ConfigSnapshot, SyncError> {
+        let rules = self.config.selective_sync.clone();
+        let transforms = self.config.field_transforms.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut snapshot = snapshot;
+            if rules.is_active() {
+                snapshot = filter_units(&snapshot, |unit| {
+                    rules.selects(unit.label.as_deref(), &unit.key)
+                })?;
+            }
+            if transforms.is_active() {
+                snapshot = strip_machine_fields(&snapshot, &transforms)?;
+            }
+            Ok::<_, SnapshotDbError>(snapshot)
+        })
+        .await
+        .map_err(|e| SyncError::Io(std::io::Error::other(e)))?
+        .map_err(SyncError::from)
+    }
+
+    /// Write a pulled config locally, then attach paired devices, re-apply this
+    /// machine's fields and translate paths from other operating systems. With
+    /// selective sync, only the selected items are merged into an existing local
+    /// config; otherwise the remote config replaces it. Returns the resulting
+    /// local config and notes on paths that couldn't be translated.
+    fn apply_pull(
+        &self,
+        remote: ConfigSnapshot,
+        previous: Option<&ConfigSnapshot>,
+    ) -> Result<(ConfigSnapshot, Vec<String>), SyncError> {
+        let merge = self.config.selective_sync.is_active() && previous.is_some();
+        if merge {
+            self.merge_selected(&remote)?;
+        } else {
+            self.apply_snapshot(
+                &remote,
+                BackupReason::PrePull,
+                Some(&remote.meta.device_name),
+            )?;
+        }
+        let db = self.config.steelseries_db_path.join("database.db");
+        let mut notes = self.pair_devices(&remote, previous);
+        let device_map = self.device_map.load();
+        if device_map.has_confirmed() {
+            translate_devices(&db, &device_map)?;
+        }
+        let transforms = &self.config.field_transforms;
+        if transforms.is_active() {
+            restore_machine_fields(&db, previous, transforms)?;
+        }
+        let mapping = &self.config.path_mapping;
+        if mapping.enabled {
+            let report = map_paths(&db, previous, mapping, OsFamily::current())?;
+            if !report.rewritten.is_empty() {
+                log::info!("Translated {} path(s) for this OS", report.rewritten.len());
+            }
+            notes.extend(
+                report.unmapped.iter().map(|entry| {
+                    format!("{}: no path for this OS for {}", entry.table, entry.path)
+                }),
+            );
+        } else if !merge && !transforms.is_active() && !device_map.has_confirmed() {
+            return Ok((remote, notes));
+        }
+        let mut local = self.read_local_config()?;
+        local.meta = remote.meta;
+        Ok((local, notes))
+    }
+
+    /// Record pairing suggestions for devices in `remote` this machine doesn't
+    /// know, and describe them. Failures are logged; pairing is best-effort.
+    fn pair_devices(&self, remote: &ConfigSnapshot, local: Option<&ConfigSnapshot>) -> Vec<String> {
+        let Some(local) = local else {
+            return Vec::new();
+        };
+        let mut map = self.device_map.load();
+        let added = match suggest_pairings(remote, local) {
+            Ok(suggestions) => map.suggest(suggestions),
+            Err(e) => {
+                log::warn!("Could not match devices: {}", e);
+                return Vec::new();
+            }
+        };
+        if added.is_empty() {
+            return Vec::new();
+        }
+        if let Err(e) = self.device_map.save(&map) {
+            log::warn!("Could not save device map: {}", e);
+        }
+        added
+            .iter()
+            .map(|p| {
+                format!(
+                    "{} ({}) may be this machine's {}; confirm the pairing in Settings",
+                    p.model.as_deref().unwrap_or("Device"),
+                    p.remote_key,
+                    p.local_key
+                )
+            })
+            .collect()
+    }
+
+    /// Merge only the profiles and devices selected for sync into the local
+    /// database, after backing it up.
+    fn merge_selected(&self, remote: &ConfigSnapshot) -> Result<(), SyncError> {
+        let rules = &self.config.selective_sync;
+        let remote_units = list_units(remote)?;
+        let units: Vec<UnitRef> = remote_units
+            .iter()
+            .filter(|unit| rules.selects(unit.label.as_deref(), &unit.key))
+            .map(|unit| UnitRef {
+                table: unit.table.clone(),
+                key: unit.key.clone(),
+            })
+            .collect();
+
+        // Selected units deleted on the remote are deleted here too. Only
+        // tables the remote has count, so an older GG schema deletes nothing.
+        let remote_tables = table_names(SnapshotDb::open(remote)?.conn())?;
+        let remote_keys: HashSet<(&str, &str)> = remote_units
+            .iter()
+            .map(|unit| (unit.table.as_str(), unit.key.as_str()))
+            .collect();
+        let deleted: Vec<UnitRef> = list_units(&self.read_local_config()?)?
+            .into_iter()
+            .filter(|unit| rules.selects(unit.label.as_deref(), &unit.key))
+            .filter(|unit| remote_tables.contains(&unit.table))
+            .filter(|unit| !remote_keys.contains(&(unit.table.as_str(), unit.key.as_str())))
+            .map(|unit| UnitRef {
+                table: unit.table,
+                key: unit.key,
+            })
+            .collect();
+
+        let dir = &self.config.steelseries_db_path;
+        self.backup_manager.create_backup(
+            dir,
+            BackupReason::PrePull,
+            Some(&remote.meta.device_name),
+        )?;
+        self.pull_in_progress
+            .store(true, std::sync::atomic::Ordering::SeqCst);
+        restore_units(&dir.join("database.db"), remote, &units)?;
+        if !deleted.is_empty() {
+            delete_units(&dir.join("database.db"), &deleted)?;
+        }
+        Ok(())
+    }
+
+    /// Summarize a completed sync against the previous config and add it to the
+    /// history. Failures are logged; they never fail the sync itself.
+    async fn record_sync(
+        &self,
+        direction: SyncDirection,
+        previous: Option<ConfigSnapshot>,
+        current: ConfigSnapshot,
+    ) -> Vec<String> {
+        let device = current.meta.device_name.clone();
+        if let Err(e) = self.last_synced.set(&current) {
+            log::warn!("Could not save the synced config: {}", e);
+        }
+        let changes = match previous {
+            Some(previous) => {
+                let summary =
+                    tokio::task::spawn_blocking(move || summarize_changes(&previous, &current))
+                        .await;
+                match summary {
+                    Ok(Ok(changes)) => changes,
+                    Ok(Err(e)) => {
+                        log::warn!("Could not summarize sync changes: {}", e);
+                        Vec::new()
+                    }
+                    Err(e) => {
+                        log::warn!("Change summary task failed: {}", e);
+                        Vec::new()
+                    }
+                }
+            }
+            None => Vec::new(),
+        };
+        let entry = SyncHistoryEntry {
+            at: Utc::now(),
+            direction,
+            device,
+            changes: changes.clone(),
+        };
+        if let Err(e) = self.history.record(entry) {
+            log::warn!("Could not write sync history: {}", e);
+        }
+        changes
+    }
+
+    /// Recorded syncs, newest first.
+    pub fn sync_history(&self) -> Vec<SyncHistoryEntry> {
+        self.history.entries()
+    }
+
+    pub fn device_map(&self) -> DeviceMap {
+        self.device_map.load()
+    }
+
+    pub fn save_device_map(&self, map: &DeviceMap) -> std::io::Result<()> {
+        self.device_map.save(map)
+    }
+
+    /// Match devices in the remote config against local ones and record new
+    /// pairing suggestions, without applying anything.
+    pub async fn refresh_device_map(&self) -> Result<DeviceMap, SyncError> {
+        let remote = self.provider.pull().await?;
+        let local = self.read_local_config()?;
+        let suggestions = tokio::task::spawn_blocking(move || suggest_pairings(&remote, &local))
+            .await
+            .map_err(|e| SyncError::Io(std::io::Error::other(e)))??;
+        let mut map = self.device_map.load();
+        if !map.suggest(suggestions).is_empty() {
+            self.device_map.save(&map)?;
+        }
+        Ok(map)
     }
 
     /// Pull remote config and overwrite local (with backup).
     pub async fn pull_from_remote(&self) -> Result<SyncResult, SyncError> {
+        if self.kiosk_locked() {
+            return Ok(SyncResult::Skipped(SkipReason::KioskMode));
+        }
         let mut safety = self.safety.lock().await;
         let gg_was_running = safety.is_gg_running();
         match safety.is_safe_to_read(&self.config.steelseries_db_path) {
@@ -106,6 +471,7 @@ impl SyncEngine {
             SafetyCheck::FileLocked => return Ok(SyncResult::Skipped(SkipReason::FileLocked)),
             SafetyCheck::NoConfig => {} // OK to write even if no existing config
             SafetyCheck::GGRunning => {} // unreachable from is_safe_to_read
+            SafetyCheck::InsufficientSpace { .. } => {} // unreachable from is_safe_to_read
         }
         drop(safety);
 
@@ -122,21 +488,399 @@ impl SyncEngine {
             return Ok(SyncResult::Skipped(SkipReason::InvalidRemoteFile));
         }
 
-        // Backup current local before overwriting
-        if self.config.steelseries_db_path.join("database.db").exists() {
-            self.backup_manager
-                .create_backup(&self.config.steelseries_db_path, "pre-pull")?;
+        // Make sure both the pre-pull backup and the staged files fit
+        if let Some(reason) = self.apply_space_preflight(&remote)? {
+            return Ok(SyncResult::Skipped(reason));
         }
 
-        // Suppress watcher auto-push for this write (prevents feedback loop)
-        self.pull_in_progress.store(true, std::sync::atomic::Ordering::SeqCst);
-        self.write_local_config(&remote)?;
+        let previous = self.read_local_config().ok();
+
+        // Optionally stop GG so the pull applies cleanly
+        let gg_stopped = gg_was_running && self.config.gg_restart.enabled;
+        if gg_stopped {
+            if let Err(reason) = self.stop_gg().await {
+                return Ok(SyncResult::Skipped(reason));
+            }
+        }
+
+        let applied = self.apply_pull(remote, previous.as_ref());
+        let gg_restarted = gg_stopped && self.relaunch_gg().await;
+        let (applied, notes) = applied?;
+
+        let from_device = applied.meta.device_name.clone();
+        let mut changes = self
+            .record_sync(SyncDirection::Pull, previous, applied)
+            .await;
+        changes.extend(notes);
         Ok(SyncResult::Pulled {
-            from_device: remote.meta.device_name,
+            from_device,
             gg_was_running,
+            gg_restarted,
+            changes,
         })
     }
 
+    /// Disk-space preflight before applying a snapshot: the backup volume must fit
+    /// a copy of the local config, and the config volume must fit the staged files.
+    fn apply_space_preflight(
+        &self,
+        incoming: &ConfigSnapshot,
+    ) -> std::io::Result<Option<SkipReason>> {
+        let dir = &self.config.steelseries_db_path;
+        if dir.join("database.db").exists() {
+            if let Some(reason) = space_skip_reason(self.backup_manager.preflight(dir)?) {
+                return Ok(Some(reason));
+            }
+        }
+        let margin = self.config.disk_space_margin_mb.saturating_mul(1024 * 1024);
+        Ok(space_skip_reason(check_disk_space(
+            dir,
+            incoming.total_size(),
+            margin,
+        )))
+    }
+
+    /// Back up the current local config, then write the snapshot.
+    /// Returns the name of the backup taken, if there was a local config to back up.
+    fn apply_snapshot(
+        &self,
+        snapshot: &ConfigSnapshot,
+        reason: BackupReason,
+        remote_device: Option<&str>,
+    ) -> std::io::Result<Option<String>> {
+        // Backup current local before overwriting
+        let dir = &self.config.steelseries_db_path;
+        let backup = if dir.join("database.db").exists() {
+            let path = self
+                .backup_manager
+                .create_backup(dir, reason, remote_device)?;
+            path.file_name().map(|n| n.to_string_lossy().to_string())
+        } else {
+            None
+        };
+
+        // Suppress watcher auto-push for this write (prevents feedback loop)
+        self.pull_in_progress
+            .store(true, std::sync::atomic::Ordering::SeqCst);
+        self.write_local_config(snapshot)?;
+        Ok(backup)
+    }
+
+    /// Restore a backup through the guarded apply path: the backup is verified,
+    /// the current config is snapshotted first, and files are swapped in atomically.
+    /// If GG is running and may not be restarted, the restore is deferred until
+    /// it exits (see `apply_pending_restore`). Skipped while kiosk mode is locked.
+    pub async fn restore_backup(&self, backup_id: &str) -> Result<SyncResult, SyncError> {
+        if self.kiosk_locked() {
+            return Ok(SyncResult::Skipped(SkipReason::KioskMode));
+        }
+        self.apply_restore(backup_id).await
+    }
+
+    /// `restore_backup` without the kiosk check, for the golden backup.
+    async fn apply_restore(&self, backup_id: &str) -> Result<SyncResult, SyncError> {
+        let entry = self.backup_manager.find_backup(backup_id)?;
+        let snapshot = match self.backup_manager.load_snapshot(&entry) {
+            Ok(s) => match check_integrity(&s) {
+                Ok(()) => s,
+                Err(problem) => {
+                    log::error!("Refusing to restore {}: {}", entry.name, problem);
+                    return Ok(SyncResult::Skipped(SkipReason::CorruptBackup));
+                }
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::InvalidData => {
+                log::error!("Refusing to restore: {}", e);
+                return Ok(SyncResult::Skipped(SkipReason::CorruptBackup));
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        let gg_running = self.safety.lock().await.is_gg_running();
+        let gg_stopped = gg_running && self.config.gg_restart.enabled;
+        if gg_running && !gg_stopped {
+            log::info!("GG is running; deferring restore of {}", entry.name);
+            *self.pending_restore.lock().unwrap() = Some(entry.name);
+            return Ok(SyncResult::Skipped(SkipReason::GGRunning));
+        }
+
+        if let Some(reason) = self.apply_space_preflight(&snapshot)? {
+            return Ok(SyncResult::Skipped(reason));
+        }
+        if gg_stopped {
+            if let Err(reason) = self.stop_gg().await {
+                return Ok(SyncResult::Skipped(reason));
+            }
+        }
+
+        let applied = self.apply_snapshot(&snapshot, BackupReason::PreRestore, None);
+        let gg_restarted = gg_stopped && self.relaunch_gg().await;
+        let undo_backup = applied?;
+        self.backup_manager
+            .record_restore(&entry.name, undo_backup.as_deref())?;
+
+        Ok(SyncResult::Restored {
+            backup_id: entry.name,
+            gg_restarted,
+        })
+    }
+
+    /// Undo the last restore by restoring the snapshot taken just before it.
+    /// The undo is itself recorded, so undoing twice re-applies the restore.
+    pub async fn undo_last_restore(&self) -> Result<SyncResult, SyncError> {
+        match self
+            .backup_manager
+            .last_restore()
+            .and_then(|r| r.undo_backup)
+        {
+            Some(undo_backup) => self.restore_backup(&undo_backup).await,
+            None => Ok(SyncResult::Skipped(SkipReason::NoRestoreToUndo)),
+        }
+    }
+
+    /// Devices, profiles and app configs stored in a backup.
+    pub fn backup_units(&self, backup_id: &str) -> Result<Vec<RestoreUnit>, SyncError> {
+        let entry = self.backup_manager.find_backup(backup_id)?;
+        let snapshot = self.backup_manager.load_snapshot(&entry)?;
+        Ok(list_units(&snapshot)?)
+    }
+
+    /// Save the current local config as the named config set.
+    pub fn save_config_set(&self, name: &str) -> std::io::Result<String> {
+        let path = self
+            .backup_manager
+            .save_config_set(&self.config.steelseries_db_path, name)?;
+        Ok(path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string())
+    }
+
+    /// Switch the local config to a saved config set through the regular
+    /// restore path: GG is restarted if allowed, otherwise the switch waits
+    /// until GG exits. The previous config can be brought back with undo.
+    pub async fn switch_config_set(&self, name: &str) -> Result<SyncResult, SyncError> {
+        let entry = self.backup_manager.find_config_set(name)?;
+        self.restore_backup(&entry.name).await
+    }
+
+    pub fn kiosk(&self) -> &KioskConfig {
+        &self.config.kiosk
+    }
+
+    /// Whether kiosk mode is on and hasn't been unlocked for this session.
+    pub fn kiosk_locked(&self) -> bool {
+        self.config.kiosk.enabled
+            && !self
+                .kiosk_unlocked
+                .load(std::sync::atomic::Ordering::SeqCst)
+    }
+
+    /// Lift kiosk mode for this session if `password` matches the override
+    /// password. Returns whether it did. Each wrong password in a row doubles
+    /// the wait before the next attempt is answered, up to `MAX_UNLOCK_BACKOFF`.
+    pub async fn unlock_kiosk(&self, password: &str) -> bool {
+        let mut failures = self.unlock_failures.lock().await;
+        let hash = self.config.kiosk.override_password_hash.clone();
+        let password = password.to_string();
+        let unlocked = tokio::task::spawn_blocking(move || {
+            hash.is_some_and(|hash| verify_password(&hash, &password))
+        })
+        .await
+        .unwrap_or(false);
+        if unlocked {
+            *failures = 0;
+            self.kiosk_unlocked
+                .store(true, std::sync::atomic::Ordering::SeqCst);
+        } else {
+            *failures += 1;
+            tokio::time::sleep(unlock_backoff(*failures)).await;
+        }
+        unlocked
+    }
+
+    pub fn lock_kiosk(&self) {
+        self.kiosk_unlocked
+            .store(false, std::sync::atomic::Ordering::SeqCst);
+    }
+
+    /// Restore the kiosk golden backup through the regular restore path.
+    /// Returns `None` when kiosk mode is off, unlocked or has no golden backup,
+    /// and skips the restore when the live config already matches it.
+    pub async fn restore_golden(&self) -> Result<Option<SyncResult>, SyncError> {
+        let golden = match &self.config.kiosk.golden_backup {
+            Some(golden) if self.kiosk_locked() => golden,
+            _ => return Ok(None),
+        };
+        let entry = self.backup_manager.find_backup(golden)?;
+        let snapshot = self.backup_manager.load_snapshot(&entry)?;
+        if let Ok(live) = self.read_local_config() {
+            if live.db == snapshot.db
+                && live.db_wal == snapshot.db_wal
+                && live.db_shm == snapshot.db_shm
+            {
+                return Ok(Some(SyncResult::Skipped(SkipReason::AlreadyInSync)));
+            }
+        }
+        log::info!("Kiosk mode: restoring golden backup {}", entry.name);
+        self.apply_restore(&entry.name).await.map(Some)
+    }
+
+    /// Whether `name` is the configured kiosk golden backup.
+    fn is_golden(&self, name: &str) -> bool {
+        self.config
+            .kiosk
+            .golden_backup
+            .as_deref()
+            .and_then(|golden| self.backup_manager.find_backup(golden).ok())
+            .is_some_and(|entry| entry.name == name)
+    }
+
+    /// Restore only the selected units of a backup into the live database.
+    /// Unlike a full restore this is never deferred: if GG is running and may
+    /// not be restarted, the restore is skipped.
+    pub async fn restore_units(
+        &self,
+        backup_id: &str,
+        units: &[UnitRef],
+    ) -> Result<SyncResult, SyncError> {
+        let entry = self.backup_manager.find_backup(backup_id)?;
+        let snapshot = self.backup_manager.load_snapshot(&entry)?;
+        if let Err(problem) = check_integrity(&snapshot) {
+            log::error!("Refusing to restore from {}: {}", entry.name, problem);
+            return Ok(SyncResult::Skipped(SkipReason::CorruptBackup));
+        }
+        let (rows, gg_restarted) = match self.apply_units(&entry.name, &snapshot, units).await? {
+            Ok(applied) => applied,
+            Err(reason) => return Ok(SyncResult::Skipped(reason)),
+        };
+        Ok(SyncResult::PartiallyRestored {
+            backup_id: entry.name,
+            rows,
+            gg_restarted,
+        })
+    }
+
+    /// Copy units from `snapshot` into the live config as an undoable restore
+    /// named `restored`, stopping and relaunching GG as configured. Returns the
+    /// rows written and whether GG was restarted, or why nothing was applied.
+    async fn apply_units(
+        &self,
+        restored: &str,
+        snapshot: &ConfigSnapshot,
+        units: &[UnitRef],
+    ) -> Result<Result<(usize, bool), SkipReason>, SyncError> {
+        if self.kiosk_locked() {
+            return Ok(Err(SkipReason::KioskMode));
+        }
+        let dir = &self.config.steelseries_db_path;
+        if !dir.join("database.db").exists() {
+            return Ok(Err(SkipReason::NoLocalConfig));
+        }
+
+        let gg_running = self.safety.lock().await.is_gg_running();
+        let gg_stopped = gg_running && self.config.gg_restart.enabled;
+        if gg_running && !gg_stopped {
+            return Ok(Err(SkipReason::GGRunning));
+        }
+        if let Some(reason) = space_skip_reason(self.backup_manager.preflight(dir)?) {
+            return Ok(Err(reason));
+        }
+        if gg_stopped {
+            if let Err(reason) = self.stop_gg().await {
+                return Ok(Err(reason));
+            }
+        }
+
+        let written = self.write_units(snapshot, units);
+        let gg_restarted = gg_stopped && self.relaunch_gg().await;
+        let (undo_backup, rows) = written?;
+        self.backup_manager
+            .record_restore(restored, undo_backup.as_deref())?;
+        Ok(Ok((rows, gg_restarted)))
+    }
+
+    /// Write the given profiles of `source` to `path` as TOML, or as JSON for a
+    /// `.json` path. Returns the number of profiles exported.
+    pub async fn export_profiles(
+        &self,
+        source: &SnapshotSource,
+        units: Vec<UnitRef>,
+        path: &std::path::Path,
+    ) -> Result<usize, SyncError> {
+        let snapshot = self.load_source(source).await?;
+        let export = tokio::task::spawn_blocking(move || export_profiles(&snapshot, &units))
+            .await
+            .map_err(|e| SyncError::Io(std::io::Error::other(e)))??;
+        fs::write(path, export.to_text(ExportFormat::from_path(path))?)?;
+        Ok(export.profiles.len())
+    }
+
+    /// Import a profile export into the live config through the same guarded
+    /// path as a partial restore, so it can be undone.
+    pub async fn import_profiles(&self, path: &std::path::Path) -> Result<SyncResult, SyncError> {
+        let text = fs::read_to_string(path)?;
+        let export = ProfileExport::parse(&text, ExportFormat::from_path(path))?;
+        let local = match self.read_local_config() {
+            Ok(local) => local,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(SyncResult::Skipped(SkipReason::NoLocalConfig))
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let profiles = export.profiles.len();
+        let (staged, units) = tokio::task::spawn_blocking(move || stage_import(&local, &export))
+            .await
+            .map_err(|e| SyncError::Io(std::io::Error::other(e)))??;
+
+        let file = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        match self.apply_units(&file, &staged, &units).await? {
+            Ok((_, gg_restarted)) => Ok(SyncResult::ProfilesImported {
+                file,
+                profiles,
+                gg_restarted,
+            }),
+            Err(reason) => Ok(SyncResult::Skipped(reason)),
+        }
+    }
+
+    /// Back up the live config, then copy the units in. Returns the backup name
+    /// and the number of rows written.
+    fn write_units(
+        &self,
+        snapshot: &ConfigSnapshot,
+        units: &[UnitRef],
+    ) -> Result<(Option<String>, usize), SyncError> {
+        let dir = &self.config.steelseries_db_path;
+        let backup = self
+            .backup_manager
+            .create_backup(dir, BackupReason::PreRestore, None)?;
+        let undo_backup = backup.file_name().map(|n| n.to_string_lossy().to_string());
+
+        self.pull_in_progress
+            .store(true, std::sync::atomic::Ordering::SeqCst);
+        let rows = restore_units(&dir.join("database.db"), snapshot, units)?;
+        Ok((undo_backup, rows))
+    }
+
+    /// Apply a restore deferred because GG was running, once GG has exited.
+    /// Returns `None` if nothing is pending or GG is still running. While kiosk
+    /// mode is locked only a deferred golden restore is applied.
+    pub async fn apply_pending_restore(&self) -> Option<Result<SyncResult, SyncError>> {
+        let pending = self.pending_restore.lock().unwrap().clone()?;
+        if self.safety.lock().await.is_gg_running() {
+            return None;
+        }
+        self.pending_restore.lock().unwrap().take();
+        if self.kiosk_locked() && self.is_golden(&pending) {
+            return Some(self.apply_restore(&pending).await);
+        }
+        Some(self.restore_backup(&pending).await)
+    }
+
     /// Full sync: compare timestamps, push or pull as needed.
     pub async fn sync(&self) -> Result<SyncResult, SyncError> {
         let local_exists = self.config.steelseries_db_path.join("database.db").exists();
@@ -150,16 +894,27 @@ impl SyncEngine {
         match (local_exists, remote_meta) {
             // Both exist: compare timestamps (last-write-wins)
             (true, Some(remote)) => {
-                let local_modified = fs::metadata(
-                    self.config.steelseries_db_path.join("database.db"),
-                )?
-                .modified()?;
+                let local_modified =
+                    fs::metadata(self.config.steelseries_db_path.join("database.db"))?
+                        .modified()?;
                 let local_ts = chrono::DateTime::<Utc>::from(local_modified);
 
                 if local_ts > remote.last_modified {
                     // Local is newer -- push
-                    self.backup_manager
-                        .create_backup(&self.config.steelseries_db_path, "pre-push")?;
+                    if self.kiosk_locked() {
+                        return Ok(SyncResult::Skipped(SkipReason::KioskMode));
+                    }
+                    let check = self
+                        .backup_manager
+                        .preflight(&self.config.steelseries_db_path)?;
+                    if let Some(reason) = space_skip_reason(check) {
+                        return Ok(SyncResult::Skipped(reason));
+                    }
+                    self.backup_manager.create_backup(
+                        &self.config.steelseries_db_path,
+                        BackupReason::PrePush,
+                        None,
+                    )?;
                     self.push_to_remote().await
                 } else if remote.last_modified > local_ts {
                     // Remote is newer -- pull
@@ -177,6 +932,272 @@ impl SyncEngine {
         }
     }
 
+    /// Load the snapshot a diff source refers to.
+    pub async fn load_source(&self, source: &SnapshotSource) -> Result<ConfigSnapshot, SyncError> {
+        match source {
+            SnapshotSource::Live => Ok(self.read_local_config()?),
+            SnapshotSource::Backup { name } => {
+                let entry = self.backup_manager.find_backup(name)?;
+                Ok(self.backup_manager.load_snapshot(&entry)?)
+            }
+            SnapshotSource::Remote => Ok(self.provider.pull().await?),
+        }
+    }
+
+    /// Row-level diff between two snapshots, `from` being the "before" side.
+    pub async fn diff(
+        &self,
+        from: &SnapshotSource,
+        to: &SnapshotSource,
+    ) -> Result<SnapshotDiff, SyncError> {
+        let from = self.load_source(from).await?;
+        let to = self.load_source(to).await?;
+        tokio::task::spawn_blocking(move || diff_snapshots(&from, &to))
+            .await
+            .map_err(|e| SyncError::Io(std::io::Error::other(e)))?
+            .map_err(SyncError::from)
+    }
+
+    /// Structured view of the devices and profiles in a snapshot.
+    pub async fn inspect(&self, source: &SnapshotSource) -> Result<Inspection, SyncError> {
+        let snapshot = self.load_source(source).await?;
+        tokio::task::spawn_blocking(move || inspect_snapshot(&snapshot))
+            .await
+            .map_err(|e| SyncError::Io(std::io::Error::other(e)))?
+            .map_err(SyncError::from)
+    }
+
+    /// Write the snapshot `source` refers to as an archive file.
+    pub async fn export_snapshot(
+        &self,
+        source: &SnapshotSource,
+        path: &std::path::Path,
+    ) -> Result<(), SyncError> {
+        let snapshot = self.load_source(source).await?;
+        fs::write(path, write_archive(&snapshot, self.signing_key()))?;
+        Ok(())
+    }
+
+    /// Import an archive file as a backup and, if `apply` is set, restore it
+    /// through the same guarded path as `restore_backup` (so it can be undone).
+    pub async fn import_snapshot(
+        &self,
+        path: &std::path::Path,
+        apply: bool,
+    ) -> Result<SyncResult, SyncError> {
+        self.import_archive(&fs::read(path)?, apply).await
+    }
+
+    async fn import_archive(&self, data: &[u8], apply: bool) -> Result<SyncResult, SyncError> {
+        if apply && self.kiosk_locked() {
+            return Ok(SyncResult::Skipped(SkipReason::KioskMode));
+        }
+        let backup_id = self.store_archive(data)?;
+        if apply {
+            self.restore_backup(&backup_id).await
+        } else {
+            Ok(SyncResult::Imported { backup_id })
+        }
+    }
+
+    /// Verify an archive and store it as a backup. Returns the backup's name.
+    fn store_archive(&self, data: &[u8]) -> Result<String, SyncError> {
+        let (snapshot, manifest) = read_archive(data, self.signing_key())?;
+        check_integrity(&snapshot).map_err(ArchiveError::Corrupt)?;
+        log::info!(
+            "Importing archive from {} created {}",
+            manifest.source_device,
+            manifest.created
+        );
+        let backup = self
+            .backup_manager
+            .create_backup_from_snapshot(&snapshot, BackupReason::Imported)?;
+        Ok(backup
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default())
+    }
+
+    /// Upload backups of the configured classes that aren't off-site yet.
+    /// Returns how many were uploaded; providers without backup storage are skipped.
+    pub async fn replicate_backups(&self) -> Result<usize, SyncError> {
+        let settings = &self.config.replication;
+        if !settings.pinned && !settings.daily {
+            return Ok(0);
+        }
+        let candidates = self.backup_manager.replication_candidates(
+            settings.pinned,
+            settings.daily,
+            Utc::now(),
+        )?;
+        let mut uploaded = 0;
+        for entry in candidates {
+            let snapshot = match self.backup_manager.load_snapshot(&entry) {
+                Ok(snapshot) => snapshot,
+                Err(e) => {
+                    log::warn!("Not replicating backup {}: {}", entry.name, e);
+                    continue;
+                }
+            };
+            let archive = write_archive(&snapshot, self.signing_key());
+            match self.provider.upload_backup(&entry.name, &archive).await {
+                Ok(()) => {}
+                Err(ProviderError::Unsupported) => {
+                    log::debug!("Provider has no backup storage; skipping replication");
+                    break;
+                }
+                Err(e) => return Err(e.into()),
+            }
+            self.backup_manager.set_replicated(&entry.name)?;
+            uploaded += 1;
+        }
+        Ok(uploaded)
+    }
+
+    /// Backups stored with the provider by any device.
+    pub async fn list_remote_backups(&self) -> Result<Vec<RemoteBackup>, SyncError> {
+        Ok(self.provider.list_backups().await?)
+    }
+
+    /// Timeline of one setting across local backups, backups replicated to the
+    /// provider (when `include_remote` is set and the provider stores them) and
+    /// the live config, oldest change first. Configs that can't be read are
+    /// skipped.
+    pub async fn setting_history(
+        &self,
+        query: SettingQuery,
+        include_remote: bool,
+    ) -> Result<Vec<SettingChange>, SyncError> {
+        let query = Arc::new(query);
+        let mut samples = Vec::new();
+        let mut local_names = std::collections::HashSet::new();
+        for entry in self.backup_manager.list_backups()? {
+            local_names.insert(entry.name.clone());
+            let snapshot = match self.backup_manager.load_snapshot(&entry) {
+                Ok(snapshot) => snapshot,
+                Err(e) => {
+                    log::warn!("Skipping backup {} in setting history: {}", entry.name, e);
+                    continue;
+                }
+            };
+            let device = entry
+                .manifest
+                .as_ref()
+                .map(|m| m.source_device.clone())
+                .unwrap_or_else(|| self.config.device_name.clone());
+            let source = SampleSource {
+                at: entry.created,
+                device,
+                backup_id: Some(entry.name),
+                remote: false,
+            };
+            if let Some(value) = sample_setting(snapshot, &query).await {
+                samples.push((source, value));
+            }
+        }
+
+        let remote_backups = if include_remote {
+            match self.provider.list_backups().await {
+                Ok(backups) => backups,
+                Err(e) => {
+                    log::info!("No remote backups for setting history: {}", e);
+                    Vec::new()
+                }
+            }
+        } else {
+            Vec::new()
+        };
+        for backup in remote_backups {
+            // Our own replicated backups are already covered locally
+            if backup.device_name == self.config.device_name && local_names.contains(&backup.name) {
+                continue;
+            }
+            let archive = self
+                .provider
+                .fetch_backup(&backup.id)
+                .await
+                .map_err(SyncError::from)
+                .and_then(|data| Ok(read_archive(&data, self.signing_key())?));
+            let (snapshot, manifest) = match archive {
+                Ok(archive) => archive,
+                Err(e) => {
+                    log::warn!(
+                        "Skipping remote backup {} in setting history: {}",
+                        backup.id,
+                        e
+                    );
+                    continue;
+                }
+            };
+            let source = SampleSource {
+                at: manifest.created,
+                device: manifest.source_device,
+                backup_id: Some(backup.id),
+                remote: true,
+            };
+            if let Some(value) = sample_setting(snapshot, &query).await {
+                samples.push((source, value));
+            }
+        }
+
+        if let Ok(live) = self.read_local_config() {
+            let source = SampleSource {
+                at: Utc::now(),
+                device: self.config.device_name.clone(),
+                backup_id: None,
+                remote: false,
+            };
+            if let Some(value) = sample_setting(live, &query).await {
+                samples.push((source, value));
+            }
+        }
+
+        samples.sort_by_key(|(source, _)| source.at);
+        let mut timeline = Vec::new();
+        for (source, value) in samples {
+            record_sample(&mut timeline, source, value);
+        }
+        Ok(timeline)
+    }
+
+    /// Download a replicated backup, store it locally and restore it.
+    pub async fn restore_remote_backup(&self, id: &str) -> Result<SyncResult, SyncError> {
+        let data = self.provider.fetch_backup(id).await?;
+        self.import_archive(&data, true).await
+    }
+
+    /// Download a replicated backup and store it as a pinned local backup, to
+    /// use as the kiosk golden backup. Returns the local backup's name.
+    pub async fn import_golden(&self, id: &str) -> Result<String, SyncError> {
+        let data = self.provider.fetch_backup(id).await?;
+        let backup_id = self.store_archive(&data)?;
+        self.backup_manager.set_pinned(&backup_id, true)?;
+        Ok(backup_id)
+    }
+
+    fn signing_key(&self) -> Option<&[u8]> {
+        self.config
+            .archive_signing_key
+            .as_deref()
+            .filter(|k| !k.is_empty())
+            .map(str::as_bytes)
+    }
+
+    /// Take a scheduled backup of the local config, unless it is unchanged since
+    /// the newest backup. Returns the new backup's path, if one was written.
+    pub fn scheduled_backup(&self) -> std::io::Result<Option<std::path::PathBuf>> {
+        let dir = &self.config.steelseries_db_path;
+        if !dir.join("database.db").exists() {
+            return Ok(None);
+        }
+        self.backup_manager
+            .create_backup_if_changed(dir, BackupReason::Scheduled)
+    }
+
+    pub async fn is_gg_running(&self) -> bool {
+        self.safety.lock().await.is_gg_running()
+    }
+
     /// Get remote metadata (for polling).
     pub async fn remote_meta(&self) -> Result<SyncMeta, SyncError> {
         self.provider.remote_meta().await.map_err(SyncError::from)
@@ -185,7 +1206,8 @@ impl SyncEngine {
     /// Check if a pull just happened (and reset the flag).
     /// The watcher should call this before auto-pushing to avoid feedback loops.
     pub fn should_suppress_push(&self) -> bool {
-        self.pull_in_progress.swap(false, std::sync::atomic::Ordering::SeqCst)
+        self.pull_in_progress
+            .swap(false, std::sync::atomic::Ordering::SeqCst)
     }
 
     /// Get a reference to the backup manager (for UI).
@@ -194,10 +1216,653 @@ impl SyncEngine {
     }
 }
 
+/// Ask GG to exit and wait until it is gone. Force-kills only when the user
+/// opted in via `force_kill_on_timeout`. If GG can't be stopped, it is
+/// relaunched before giving up, since some of its processes may already have
+/// exited, so an aborted pull never leaves the user without GG.
+async fn stop_gg<G: GgProcesses>(
+    gg: &Mutex<G>,
+    settings: &GgRestartConfig,
+    poll: Duration,
+) -> Result<(), SkipReason> {
+    let timeout = Duration::from_secs(settings.exit_timeout_secs);
+
+    let signalled = gg.lock().await.request_gg_exit();
+    log::info!("Asked {} GG process(es) to exit", signalled);
+    if wait_for_gg_exit(gg, timeout, poll).await {
+        return Ok(());
+    }
+
+    if settings.force_kill_on_timeout {
+        log::warn!("GG ignored the exit request; force-killing as configured");
+        gg.lock().await.force_kill_gg();
+        if wait_for_gg_exit(gg, poll * 10, poll).await {
+            return Ok(());
+        }
+    }
+    log::warn!(
+        "GG still running after {}s; aborting pull",
+        settings.exit_timeout_secs
+    );
+    relaunch_gg(gg, &settings.executable_path).await;
+    Err(SkipReason::GGStopTimeout)
+}
+
+async fn wait_for_gg_exit<G: GgProcesses>(
+    gg: &Mutex<G>,
+    timeout: Duration,
+    poll: Duration,
+) -> bool {
+    let deadline = tokio::time::Instant::now() + timeout;
+    loop {
+        if !gg.lock().await.is_gg_running() {
+            return true;
+        }
+        if tokio::time::Instant::now() >= deadline {
+            return false;
+        }
+        tokio::time::sleep(poll).await;
+    }
+}
+
+async fn relaunch_gg<G: GgProcesses>(gg: &Mutex<G>, executable: &std::path::Path) -> bool {
+    match gg.lock().await.launch_gg(executable) {
+        Ok(()) => true,
+        Err(e) => {
+            log::error!("Failed to relaunch GG: {}", e);
+            false
+        }
+    }
+}
+
+/// Map a failed disk-space check to the skip reason reported to the user.
+fn space_skip_reason(check: SafetyCheck) -> Option<SkipReason> {
+    match check {
+        SafetyCheck::InsufficientSpace {
+            required,
+            available,
+        } => Some(SkipReason::InsufficientDiskSpace {
+            required,
+            available,
+        }),
+        _ => None,
+    }
+}
+
+/// Wait after the `failures`th wrong override password in a row.
+fn unlock_backoff(failures: u32) -> Duration {
+    Duration::from_secs(1 << failures.saturating_sub(1).min(5)).min(MAX_UNLOCK_BACKOFF)
+}
+
+/// Look a setting up in a snapshot off the async runtime. `None` if the
+/// snapshot can't be inspected.
+async fn sample_setting(
+    snapshot: ConfigSnapshot,
+    query: &Arc<SettingQuery>,
+) -> Option<Option<serde_json::Value>> {
+    let query = Arc::clone(query);
+    match tokio::task::spawn_blocking(move || find_setting(&snapshot, &query)).await {
+        Ok(Ok(value)) => Some(value),
+        Ok(Err(e)) => {
+            log::warn!("Could not inspect config for setting history: {}", e);
+            None
+        }
+        Err(e) => {
+            log::warn!("Setting history task failed: {}", e);
+            None
+        }
+    }
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum SyncError {
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
     #[error("Provider error: {0}")]
     Provider(#[from] crate::providers::ProviderError),
+    #[error("Database error: {0}")]
+    Database(#[from] SnapshotDbError),
+    #[error("Archive error: {0}")]
+    Archive(#[from] ArchiveError),
+    #[error("Profile export error: {0}")]
+    Export(#[from] ExportError),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::folder::FolderProvider;
+    use tempfile::TempDir;
+
+    fn sqlite_bytes(body: &str) -> Vec<u8> {
+        let sql = format!(
+            "CREATE TABLE t (v TEXT); INSERT INTO t VALUES ('{}');",
+            body
+        );
+        crate::snapshot_db::tests::snapshot_from_sql(&sql).db
+    }
+
+    fn test_engine(tmp: &TempDir) -> (SyncEngine, std::path::PathBuf) {
+        let config = AppConfig {
+            steelseries_db_path: tmp.path().join("gg"),
+            backup_dir: tmp.path().join("backups"),
+            device_name: "this-pc".into(),
+            ..AppConfig::default()
+        };
+        let remote_dir = tmp.path().join("remote");
+        let provider = Arc::new(FolderProvider::new(remote_dir.clone(), "other-pc".into()));
+        (SyncEngine::new(config, provider), remote_dir)
+    }
+
+    #[tokio::test]
+    async fn test_pull_replaces_db_and_drops_stale_wal() {
+        let tmp = TempDir::new().unwrap();
+        let (engine, remote_dir) = test_engine(&tmp);
+        let gg_dir = tmp.path().join("gg");
+        fs::create_dir_all(&gg_dir).unwrap();
+        fs::write(gg_dir.join("database.db"), sqlite_bytes("local")).unwrap();
+        fs::write(gg_dir.join("database.db-wal"), b"stale-wal").unwrap();
+
+        let remote = FolderProvider::new(remote_dir, "other-pc".into());
+        remote
+            .push(&ConfigSnapshot {
+                db: sqlite_bytes("remote"),
+                db_shm: None,
+                db_wal: None,
+                meta: SyncMeta {
+                    last_modified: Utc::now(),
+                    device_name: "other-pc".into(),
+                },
+            })
+            .await
+            .unwrap();
+
+        let result = engine.pull_from_remote().await.unwrap();
+
+        assert!(matches!(
+            result,
+            SyncResult::Pulled {
+                gg_restarted: false,
+                ..
+            }
+        ));
+        assert_eq!(
+            fs::read(gg_dir.join("database.db")).unwrap(),
+            sqlite_bytes("remote")
+        );
+        assert!(!gg_dir.join("database.db-wal").exists());
+        assert!(!gg_dir.join("database.db.sync-tmp").exists());
+
+        let history = engine.sync_history();
+        assert_eq!(history[0].direction, SyncDirection::Pull);
+        assert_eq!(history[0].device, "other-pc");
+        assert_eq!(history[0].changes, ["t: 1 row(s) changed"]);
+    }
+
+    #[tokio::test]
+    async fn test_first_push_after_restart_is_summarized() {
+        let tmp = TempDir::new().unwrap();
+        let gg_dir = tmp.path().join("gg");
+        fs::create_dir_all(&gg_dir).unwrap();
+        fs::write(gg_dir.join("database.db"), sqlite_bytes("before")).unwrap();
+        let (engine, _) = test_engine(&tmp);
+        engine.push_to_remote().await.unwrap();
+        drop(engine);
+
+        let (engine, _) = test_engine(&tmp);
+        fs::write(gg_dir.join("database.db"), sqlite_bytes("after")).unwrap();
+        assert_eq!(
+            engine.push_to_remote().await.unwrap(),
+            SyncResult::Pushed {
+                changes: vec!["t: 1 row(s) changed".to_string()]
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_restore_and_undo() {
+        let tmp = TempDir::new().unwrap();
+        let (engine, _) = test_engine(&tmp);
+        let gg_dir = tmp.path().join("gg");
+        fs::create_dir_all(&gg_dir).unwrap();
+        fs::write(gg_dir.join("database.db"), sqlite_bytes("original")).unwrap();
+        let backup = engine
+            .backups()
+            .create_backup(&gg_dir, BackupReason::Manual, None)
+            .unwrap();
+        let backup_id = backup.file_name().unwrap().to_string_lossy().to_string();
+        fs::write(gg_dir.join("database.db"), sqlite_bytes("edited")).unwrap();
+        fs::write(gg_dir.join("database.db-wal"), b"stale-wal").unwrap();
+
+        let result = engine.restore_backup(&backup_id).await.unwrap();
+
+        assert!(matches!(result, SyncResult::Restored { .. }));
+        assert_eq!(
+            fs::read(gg_dir.join("database.db")).unwrap(),
+            sqlite_bytes("original")
+        );
+        assert!(!gg_dir.join("database.db-wal").exists());
+
+        engine.undo_last_restore().await.unwrap();
+        assert_eq!(
+            fs::read(gg_dir.join("database.db")).unwrap(),
+            sqlite_bytes("edited")
+        );
+        assert_eq!(
+            fs::read(gg_dir.join("database.db-wal")).unwrap(),
+            b"stale-wal"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_diff_live_against_backup() {
+        let tmp = TempDir::new().unwrap();
+        let (engine, _) = test_engine(&tmp);
+        let gg_dir = tmp.path().join("gg");
+        fs::create_dir_all(&gg_dir).unwrap();
+        fs::write(gg_dir.join("database.db"), sqlite_bytes("original")).unwrap();
+        let backup = engine
+            .backups()
+            .create_backup(&gg_dir, BackupReason::Manual, None)
+            .unwrap();
+        let name = backup.file_name().unwrap().to_string_lossy().to_string();
+        fs::write(gg_dir.join("database.db"), sqlite_bytes("edited")).unwrap();
+
+        let diff = engine
+            .diff(&SnapshotSource::Backup { name }, &SnapshotSource::Live)
+            .await
+            .unwrap();
+
+        let change = &diff.tables[0].changed[0];
+        assert_eq!(change.fields[0].before, "original");
+        assert_eq!(change.fields[0].after, "edited");
+    }
+
+    #[tokio::test]
+    async fn test_export_then_import_and_apply() {
+        let tmp = TempDir::new().unwrap();
+        let (engine, _) = test_engine(&tmp);
+        let gg_dir = tmp.path().join("gg");
+        fs::create_dir_all(&gg_dir).unwrap();
+        fs::write(gg_dir.join("database.db"), sqlite_bytes("exported")).unwrap();
+        let archive = tmp.path().join("config.ggsync");
+
+        engine
+            .export_snapshot(&SnapshotSource::Live, &archive)
+            .await
+            .unwrap();
+        fs::write(gg_dir.join("database.db"), sqlite_bytes("local")).unwrap();
+        let result = engine.import_snapshot(&archive, true).await.unwrap();
+
+        assert!(matches!(result, SyncResult::Restored { ref backup_id, .. }
+            if backup_id.starts_with("imported-")));
+        assert_eq!(
+            fs::read(gg_dir.join("database.db")).unwrap(),
+            sqlite_bytes("exported")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_replicate_and_restore_remote_backup() {
+        let tmp = TempDir::new().unwrap();
+        let config = AppConfig {
+            steelseries_db_path: tmp.path().join("gg"),
+            backup_dir: tmp.path().join("backups"),
+            device_name: "this-pc".into(),
+            replication: crate::config::ReplicationConfig {
+                pinned: true,
+                daily: false,
+            },
+            ..AppConfig::default()
+        };
+        let provider = Arc::new(FolderProvider::new(
+            tmp.path().join("remote"),
+            "this-pc".into(),
+        ));
+        let engine = SyncEngine::new(config, provider);
+        let gg_dir = tmp.path().join("gg");
+        fs::create_dir_all(&gg_dir).unwrap();
+        fs::write(gg_dir.join("database.db"), sqlite_bytes("pinned")).unwrap();
+        let backup = engine
+            .backups()
+            .create_backup(&gg_dir, BackupReason::Manual, None)
+            .unwrap();
+        let name = backup.file_name().unwrap().to_string_lossy().to_string();
+        engine.backups().set_pinned(&name, true).unwrap();
+
+        assert_eq!(engine.replicate_backups().await.unwrap(), 1);
+        assert_eq!(engine.replicate_backups().await.unwrap(), 0);
+
+        fs::write(gg_dir.join("database.db"), sqlite_bytes("local")).unwrap();
+        let remote = engine.list_remote_backups().await.unwrap();
+        assert_eq!(remote[0].name, name);
+        let result = engine.restore_remote_backup(&remote[0].id).await.unwrap();
+
+        assert!(matches!(result, SyncResult::Restored { .. }));
+        assert_eq!(
+            fs::read(gg_dir.join("database.db")).unwrap(),
+            sqlite_bytes("pinned")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_selective_sync_keeps_local_only_profiles() {
+        use crate::config::SelectiveSyncConfig;
+        use crate::snapshot_db::tests::snapshot_from_sql;
+
+        let tmp = TempDir::new().unwrap();
+        let config = AppConfig {
+            steelseries_db_path: tmp.path().join("gg"),
+            backup_dir: tmp.path().join("backups"),
+            device_name: "this-pc".into(),
+            selective_sync: SelectiveSyncConfig {
+                include: vec![],
+                exclude: vec!["streaming".into()],
+            },
+            ..AppConfig::default()
+        };
+        let provider = Arc::new(FolderProvider::new(
+            tmp.path().join("remote"),
+            "this-pc".into(),
+        ));
+        let engine = SyncEngine::new(config, provider.clone());
+        let schema = "CREATE TABLE profiles (id INTEGER PRIMARY KEY, name TEXT, dpi INTEGER);";
+        let gg_dir = tmp.path().join("gg");
+        fs::create_dir_all(&gg_dir).unwrap();
+        let local = snapshot_from_sql(&format!(
+            "{} INSERT INTO profiles VALUES (1, 'FPS', 800), (2, 'Streaming', 400);",
+            schema
+        ));
+        fs::write(gg_dir.join("database.db"), &local.db).unwrap();
+
+        engine.push_to_remote().await.unwrap();
+        let pushed = SnapshotDb::open(&provider.pull().await.unwrap()).unwrap();
+        let names: Vec<String> = pushed
+            .conn()
+            .prepare("SELECT name FROM profiles")
+            .unwrap()
+            .query_map([], |r| r.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(names, ["FPS"]);
+
+        let mut remote = snapshot_from_sql(&format!(
+            "{} INSERT INTO profiles VALUES (1, 'FPS', 1600), (2, 'Streaming', 3200);",
+            schema
+        ));
+        remote.meta.device_name = "other-pc".into();
+        provider.push(&remote).await.unwrap();
+        engine.pull_from_remote().await.unwrap();
+
+        let live = rusqlite::Connection::open(gg_dir.join("database.db")).unwrap();
+        let dpi: Vec<i64> = live
+            .prepare("SELECT dpi FROM profiles ORDER BY id")
+            .unwrap()
+            .query_map([], |r| r.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(dpi, [1600, 400]);
+    }
+
+    #[tokio::test]
+    async fn test_kiosk_restores_golden_and_blocks_push() {
+        let tmp = TempDir::new().unwrap();
+        let gg_dir = tmp.path().join("gg");
+        fs::create_dir_all(&gg_dir).unwrap();
+        fs::write(gg_dir.join("database.db"), sqlite_bytes("golden")).unwrap();
+        let backups = BackupManager::new(tmp.path().join("backups"), 20);
+        let golden = backups
+            .create_backup(&gg_dir, BackupReason::Manual, None)
+            .unwrap();
+        let mut config = AppConfig {
+            steelseries_db_path: gg_dir.clone(),
+            backup_dir: tmp.path().join("backups"),
+            device_name: "this-pc".into(),
+            ..AppConfig::default()
+        };
+        config.kiosk = KioskConfig {
+            enabled: true,
+            golden_backup: Some(golden.file_name().unwrap().to_string_lossy().to_string()),
+            override_password_hash: Some(crate::kiosk::hash_password("staff")),
+        };
+        let remote_dir = tmp.path().join("remote");
+        let provider = Arc::new(FolderProvider::new(remote_dir.clone(), "this-pc".into()));
+        let engine = SyncEngine::new(config, provider);
+
+        // Unchanged config: nothing to restore
+        assert_eq!(
+            engine.restore_golden().await.unwrap(),
+            Some(SyncResult::Skipped(SkipReason::AlreadyInSync))
+        );
+
+        // A player's changes are rolled back and never pushed
+        fs::write(gg_dir.join("database.db"), sqlite_bytes("player")).unwrap();
+        assert_eq!(
+            engine.push_to_remote().await.unwrap(),
+            SyncResult::Skipped(SkipReason::KioskMode)
+        );
+        assert!(!remote_dir.join("database.db").exists());
+        let result = engine.restore_golden().await.unwrap();
+        assert!(matches!(result, Some(SyncResult::Restored { .. })));
+        assert_eq!(
+            fs::read(gg_dir.join("database.db")).unwrap(),
+            sqlite_bytes("golden")
+        );
+
+        // The override password lifts kiosk mode for the session
+        assert!(!engine.unlock_kiosk("player").await);
+        assert!(engine.kiosk_locked());
+        assert!(engine.unlock_kiosk("staff").await);
+        assert_eq!(engine.restore_golden().await.unwrap(), None);
+        assert!(matches!(
+            engine.push_to_remote().await.unwrap(),
+            SyncResult::Pushed { .. }
+        ));
+        engine.lock_kiosk();
+        assert!(engine.kiosk_locked());
+    }
+
+    #[tokio::test]
+    async fn test_kiosk_blocks_pulls_and_restores() {
+        let tmp = TempDir::new().unwrap();
+        let gg_dir = tmp.path().join("gg");
+        fs::create_dir_all(&gg_dir).unwrap();
+        fs::write(gg_dir.join("database.db"), sqlite_bytes("golden")).unwrap();
+        let remote_dir = tmp.path().join("remote");
+        FolderProvider::new(remote_dir.clone(), "other-pc".into())
+            .push(&ConfigSnapshot {
+                db: sqlite_bytes("remote"),
+                db_shm: None,
+                db_wal: None,
+                meta: SyncMeta {
+                    last_modified: Utc::now(),
+                    device_name: "other-pc".into(),
+                },
+            })
+            .await
+            .unwrap();
+
+        let mut config = AppConfig {
+            steelseries_db_path: gg_dir.clone(),
+            backup_dir: tmp.path().join("backups"),
+            device_name: "this-pc".into(),
+            ..AppConfig::default()
+        };
+        config.kiosk.enabled = true;
+        let provider = Arc::new(FolderProvider::new(remote_dir, "this-pc".into()));
+        let engine = SyncEngine::new(config, provider);
+        let backup = engine
+            .backups()
+            .create_backup(&gg_dir, BackupReason::Manual, None)
+            .unwrap();
+        let backup = backup.file_name().unwrap().to_string_lossy().to_string();
+
+        for result in [
+            engine.pull_from_remote().await.unwrap(),
+            engine.sync().await.unwrap(),
+            engine.restore_backup(&backup).await.unwrap(),
+            engine.restore_units(&backup, &[]).await.unwrap(),
+        ] {
+            assert_eq!(result, SyncResult::Skipped(SkipReason::KioskMode));
+        }
+        assert_eq!(
+            fs::read(gg_dir.join("database.db")).unwrap(),
+            sqlite_bytes("golden")
+        );
+    }
+
+    /// GG processes that exit on request, only when killed, or never.
+    #[derive(Default)]
+    struct FakeGg {
+        running: bool,
+        exits_on_request: bool,
+        dies_on_kill: bool,
+        kills: usize,
+        launches: usize,
+    }
+
+    impl GgProcesses for FakeGg {
+        fn is_gg_running(&mut self) -> bool {
+            self.running
+        }
+
+        fn request_gg_exit(&mut self) -> usize {
+            if self.exits_on_request {
+                self.running = false;
+            }
+            1
+        }
+
+        fn force_kill_gg(&mut self) -> usize {
+            self.kills += 1;
+            if self.dies_on_kill {
+                self.running = false;
+            }
+            1
+        }
+
+        fn launch_gg(&mut self, _executable: &std::path::Path) -> std::io::Result<()> {
+            self.launches += 1;
+            self.running = true;
+            Ok(())
+        }
+    }
+
+    async fn stop_fake(gg: FakeGg, force_kill: bool) -> (Result<(), SkipReason>, FakeGg) {
+        let settings = crate::config::GgRestartConfig {
+            enabled: true,
+            exit_timeout_secs: 0,
+            force_kill_on_timeout: force_kill,
+            ..Default::default()
+        };
+        let gg = Mutex::new(FakeGg {
+            running: true,
+            ..gg
+        });
+        let result = stop_gg(&gg, &settings, Duration::from_millis(1)).await;
+        (result, gg.into_inner())
+    }
+
+    #[tokio::test]
+    async fn test_stop_gg_graceful_exit() {
+        let gg = FakeGg {
+            exits_on_request: true,
+            ..Default::default()
+        };
+        let (result, gg) = stop_fake(gg, true).await;
+        assert_eq!(result, Ok(()));
+        assert_eq!((gg.kills, gg.launches), (0, 0));
+    }
+
+    #[tokio::test]
+    async fn test_stop_gg_force_kill() {
+        let gg = FakeGg {
+            dies_on_kill: true,
+            ..Default::default()
+        };
+        let (result, gg) = stop_fake(gg, true).await;
+        assert_eq!(result, Ok(()));
+        assert_eq!((gg.kills, gg.launches), (1, 0));
+        assert!(!gg.running);
+    }
+
+    #[tokio::test]
+    async fn test_stop_gg_timeout_relaunches() {
+        // Not allowed to force-kill
+        let (result, gg) = stop_fake(FakeGg::default(), false).await;
+        assert_eq!(result, Err(SkipReason::GGStopTimeout));
+        assert_eq!((gg.kills, gg.launches), (0, 1));
+
+        // Killing doesn't help either
+        let (result, gg) = stop_fake(FakeGg::default(), true).await;
+        assert_eq!(result, Err(SkipReason::GGStopTimeout));
+        assert_eq!((gg.kills, gg.launches), (1, 1));
+    }
+
+    #[tokio::test]
+    async fn test_selective_pull_deletes_selected_profiles_removed_remotely() {
+        use crate::config::SelectiveSyncConfig;
+        use crate::snapshot_db::tests::snapshot_from_sql;
+
+        let tmp = TempDir::new().unwrap();
+        let config = AppConfig {
+            steelseries_db_path: tmp.path().join("gg"),
+            backup_dir: tmp.path().join("backups"),
+            device_name: "this-pc".into(),
+            selective_sync: SelectiveSyncConfig {
+                include: vec![],
+                exclude: vec!["streaming".into()],
+            },
+            ..AppConfig::default()
+        };
+        let provider = Arc::new(FolderProvider::new(
+            tmp.path().join("remote"),
+            "this-pc".into(),
+        ));
+        let engine = SyncEngine::new(config, provider.clone());
+        let schema = "CREATE TABLE profiles (id INTEGER PRIMARY KEY, name TEXT);
+                      CREATE TABLE profile_settings (
+                          profile_id INTEGER REFERENCES profiles(id), key TEXT, value TEXT,
+                          PRIMARY KEY (profile_id, key));";
+        let gg_dir = tmp.path().join("gg");
+        fs::create_dir_all(&gg_dir).unwrap();
+        let local = snapshot_from_sql(&format!(
+            "{} INSERT INTO profiles VALUES (1, 'FPS'), (2, 'MOBA'), (3, 'Streaming');
+                INSERT INTO profile_settings VALUES (2, 'dpi', '400');",
+            schema
+        ));
+        fs::write(gg_dir.join("database.db"), &local.db).unwrap();
+
+        // MOBA was deleted on the other machine; Streaming is excluded from sync
+        let mut remote = snapshot_from_sql(&format!(
+            "{} INSERT INTO profiles VALUES (1, 'FPS');",
+            schema
+        ));
+        remote.meta.device_name = "other-pc".into();
+        provider.push(&remote).await.unwrap();
+        engine.pull_from_remote().await.unwrap();
+
+        let live = rusqlite::Connection::open(gg_dir.join("database.db")).unwrap();
+        let names: Vec<String> = live
+            .prepare("SELECT name FROM profiles ORDER BY id")
+            .unwrap()
+            .query_map([], |r| r.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(names, ["FPS", "Streaming"]);
+        let settings: i64 = live
+            .query_row("SELECT COUNT(*) FROM profile_settings", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(settings, 0);
+    }
+
+    #[tokio::test]
+    async fn test_restore_rejects_unknown_backup() {
+        let tmp = TempDir::new().unwrap();
+        let (engine, _) = test_engine(&tmp);
+        assert!(engine.restore_backup("../gg").await.is_err());
+    }
 }
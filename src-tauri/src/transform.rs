@@ -0,0 +1,318 @@
+//! Machine-specific fields in the GG database.
+//!
+//! Some columns only make sense on the machine that wrote them (executable
+//! paths, window state, hardware serials). Before a push they are stripped
+//! (set to NULL) so they never reach other machines; after a pull the values
+//! this machine had before are written back, followed by any fixed local
+//! overrides. Tables or columns a given GG version doesn't have are skipped.
+
+use crate::config::{FieldOverride, FieldRef, TransformConfig};
+use crate::diff::{primary_key, quote_ident, read_table, Row};
+use crate::providers::ConfigSnapshot;
+use crate::snapshot_db::{SnapshotDb, SnapshotDbError};
+use rusqlite::types::Value;
+use rusqlite::Connection;
+use std::path::Path;
+
+/// Copy of `snapshot` with the machine-specific fields set to NULL.
+pub fn strip_machine_fields(
+    snapshot: &ConfigSnapshot,
+    rules: &TransformConfig,
+) -> Result<ConfigSnapshot, SnapshotDbError> {
+    let db = SnapshotDb::open(snapshot)?;
+    for field in &rules.machine_fields {
+        if has_column(db.conn(), &field.table, &field.column)? {
+            db.conn().execute(
+                &format!(
+                    "UPDATE {} SET {} = NULL",
+                    quote_ident(&field.table),
+                    quote_ident(&field.column)
+                ),
+                [],
+            )?;
+        }
+    }
+    db.into_snapshot(snapshot.meta.clone())
+}
+
+/// After a pull, write this machine's previous values of the machine-specific
+/// fields back into the database at `live_db`, then apply the overrides.
+/// Rows are matched by primary key; tables without one are skipped, since
+/// their rowids differ between machines. Returns the number of rows updated.
+pub fn restore_machine_fields(
+    live_db: &Path,
+    previous: Option<&ConfigSnapshot>,
+    rules: &TransformConfig,
+) -> Result<usize, SnapshotDbError> {
+    let previous = previous.map(SnapshotDb::open).transpose()?;
+    let mut live = Connection::open(live_db)?;
+    let tx = live.transaction()?;
+    let mut updated = 0;
+
+    if let Some(previous) = &previous {
+        for field in &rules.machine_fields {
+            if !has_column(previous.conn(), &field.table, &field.column)?
+                || !has_column(&tx, &field.table, &field.column)?
+                || primary_key(previous.conn(), &field.table)?.is_empty()
+            {
+                continue;
+            }
+            for (key, row) in read_table(previous.conn(), &field.table)? {
+                let value = row.get(&field.column).unwrap_or(&Value::Null);
+                updated += update_row(&tx, field, &key, &row, value)?;
+            }
+        }
+    }
+    for field_override in &rules.overrides {
+        updated += apply_override(&tx, field_override)?;
+    }
+    tx.commit()?;
+    Ok(updated)
+}
+
+fn apply_override(
+    conn: &Connection,
+    field_override: &FieldOverride,
+) -> Result<usize, SnapshotDbError> {
+    let field = &field_override.field;
+    if !has_column(conn, &field.table, &field.column)? {
+        return Ok(0);
+    }
+    let value = json_to_sql(&field_override.value);
+    match &field_override.key {
+        Some(key) => {
+            if primary_key(conn, &field.table)?.is_empty() {
+                return Ok(0);
+            }
+            match read_table(conn, &field.table)?.get(key) {
+                Some(row) => update_row(conn, field, key, row, &value),
+                None => Ok(0),
+            }
+        }
+        None => Ok(conn.execute(
+            &format!(
+                "UPDATE {} SET {} = ?1",
+                quote_ident(&field.table),
+                quote_ident(&field.column)
+            ),
+            [&value],
+        )?),
+    }
+}
+
+/// Set one column of a row read by `read_table`, given its key and values.
+/// The row is matched on its primary key values, or on the rowid in `key` for
+/// tables without one; a rowid only identifies a row in the database it was
+/// read from.
+pub(crate) fn update_row(
+    conn: &Connection,
+    field: &FieldRef,
+    key: &str,
+    row: &Row,
+    value: &Value,
+) -> Result<usize, SnapshotDbError> {
+    let pk = primary_key(conn, &field.table)?;
+    let matched: Vec<(String, Value)> = if pk.is_empty() {
+        let Ok(rowid) = key.parse::<i64>() else {
+            return Ok(0);
+        };
+        vec![("rowid".to_string(), Value::Integer(rowid))]
+    } else {
+        let mut matched = Vec::new();
+        for column in &pk {
+            let Some(value) = row.get(column) else {
+                return Ok(0);
+            };
+            matched.push((quote_ident(column), value.clone()));
+        }
+        matched
+    };
+    let condition = matched
+        .iter()
+        .enumerate()
+        .map(|(i, (column, _))| format!("{} IS ?{}", column, i + 2))
+        .collect::<Vec<_>>()
+        .join(" AND ");
+    let sql = format!(
+        "UPDATE {} SET {} = ?1 WHERE {}",
+        quote_ident(&field.table),
+        quote_ident(&field.column),
+        condition
+    );
+    let mut bound: Vec<&dyn rusqlite::ToSql> = vec![value];
+    bound.extend(matched.iter().map(|(_, v)| v as &dyn rusqlite::ToSql));
+    Ok(conn.execute(&sql, bound.as_slice())?)
+}
+
+fn has_column(conn: &Connection, table: &str, column: &str) -> Result<bool, SnapshotDbError> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", quote_ident(table)))?;
+    let names: Vec<String> = stmt
+        .query_map([], |r| r.get("name"))?
+        .collect::<Result<_, _>>()?;
+    Ok(names.iter().any(|name| name.eq_ignore_ascii_case(column)))
+}
+
+fn json_to_sql(value: &serde_json::Value) -> Value {
+    match value {
+        serde_json::Value::Null => Value::Null,
+        serde_json::Value::Bool(b) => Value::Integer(*b as i64),
+        serde_json::Value::Number(n) => match n.as_i64() {
+            Some(i) => Value::Integer(i),
+            None => Value::Real(n.as_f64().unwrap_or_default()),
+        },
+        serde_json::Value::String(s) => Value::Text(s.clone()),
+        other => Value::Text(other.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snapshot_db::tests::snapshot_from_sql;
+    use tempfile::TempDir;
+
+    const SCHEMA: &str = "
+        CREATE TABLE apps (id INTEGER PRIMARY KEY, name TEXT, exe_path TEXT);
+        CREATE TABLE window (x INTEGER, y INTEGER);";
+
+    fn rules() -> TransformConfig {
+        TransformConfig {
+            machine_fields: vec![
+                FieldRef {
+                    table: "apps".into(),
+                    column: "exe_path".into(),
+                },
+                FieldRef {
+                    table: "window".into(),
+                    column: "x".into(),
+                },
+                FieldRef {
+                    table: "missing".into(),
+                    column: "serial".into(),
+                },
+            ],
+            overrides: vec![FieldOverride {
+                field: FieldRef {
+                    table: "window".into(),
+                    column: "y".into(),
+                },
+                key: None,
+                value: serde_json::json!(42),
+            }],
+        }
+    }
+
+    #[test]
+    fn test_strip_before_push() {
+        let snapshot = snapshot_from_sql(&format!(
+            "{} INSERT INTO apps VALUES (1, 'Valorant', 'C:\\Games\\valorant.exe');
+               INSERT INTO window VALUES (100, 200);",
+            SCHEMA
+        ));
+
+        let stripped = strip_machine_fields(&snapshot, &rules()).unwrap();
+
+        let db = SnapshotDb::open(&stripped).unwrap();
+        let (name, path): (String, Option<String>) = db
+            .conn()
+            .query_row("SELECT name, exe_path FROM apps", [], |r| {
+                Ok((r.get(0)?, r.get(1)?))
+            })
+            .unwrap();
+        assert_eq!(name, "Valorant");
+        assert_eq!(path, None);
+    }
+
+    #[test]
+    fn test_restore_local_values_after_pull() {
+        let tmp = TempDir::new().unwrap();
+        let previous = snapshot_from_sql(&format!(
+            "{} INSERT INTO apps VALUES (1, 'Valorant', 'D:\\valorant.exe');
+               INSERT INTO window VALUES (100, 200);",
+            SCHEMA
+        ));
+        let pulled = snapshot_from_sql(&format!(
+            "{} INSERT INTO apps VALUES (1, 'Valorant (renamed)', NULL), (2, 'New', NULL);
+               INSERT INTO window VALUES (NULL, NULL);",
+            SCHEMA
+        ));
+        let live = tmp.path().join("database.db");
+        std::fs::write(&live, &pulled.db).unwrap();
+
+        restore_machine_fields(&live, Some(&previous), &rules()).unwrap();
+
+        let conn = Connection::open(&live).unwrap();
+        let apps: Vec<(String, Option<String>)> = conn
+            .prepare("SELECT name, exe_path FROM apps ORDER BY id")
+            .unwrap()
+            .query_map([], |r| Ok((r.get(0)?, r.get(1)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            apps,
+            [
+                (
+                    "Valorant (renamed)".to_string(),
+                    Some("D:\\valorant.exe".to_string())
+                ),
+                ("New".to_string(), None),
+            ]
+        );
+        // Without a primary key the pulled row can't be matched to this machine's
+        let window: (Option<i64>, i64) = conn
+            .query_row("SELECT x, y FROM window", [], |r| {
+                Ok((r.get(0)?, r.get(1)?))
+            })
+            .unwrap();
+        assert_eq!(window, (None, 42));
+    }
+
+    #[test]
+    fn test_restore_matches_composite_keys_exactly() {
+        let tmp = TempDir::new().unwrap();
+        let schema = "CREATE TABLE bindings (profile TEXT, slot TEXT, serial TEXT,
+                                             PRIMARY KEY (profile, slot));";
+        let previous = snapshot_from_sql(&format!(
+            "{} INSERT INTO bindings VALUES ('FPS/Pro', '1', 'SER-1'), ('FPS', NULL, 'SER-2');",
+            schema
+        ));
+        let pulled = snapshot_from_sql(&format!(
+            "{} INSERT INTO bindings VALUES ('FPS/Pro', '1', NULL), ('FPS', 'Pro/1', NULL),
+                                            ('FPS', NULL, NULL), ('FPS', 'null', NULL);",
+            schema
+        ));
+        let live = tmp.path().join("database.db");
+        std::fs::write(&live, &pulled.db).unwrap();
+        let rules = TransformConfig {
+            machine_fields: vec![FieldRef {
+                table: "bindings".into(),
+                column: "serial".into(),
+            }],
+            overrides: vec![],
+        };
+
+        assert_eq!(
+            restore_machine_fields(&live, Some(&previous), &rules).unwrap(),
+            2
+        );
+
+        let conn = Connection::open(&live).unwrap();
+        let serials: Vec<Option<String>> = conn
+            .prepare("SELECT serial FROM bindings ORDER BY rowid")
+            .unwrap()
+            .query_map([], |r| r.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            serials,
+            [
+                Some("SER-1".to_string()),
+                None,
+                Some("SER-2".to_string()),
+                None
+            ]
+        );
+    }
+}
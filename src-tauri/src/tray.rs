@@ -1,15 +1,31 @@
 //! System tray setup with menu items for quick actions.
 
 use tauri::{
-    menu::{Menu, MenuItem, PredefinedMenuItem},
+    menu::{Menu, MenuItem, PredefinedMenuItem, Submenu},
     tray::TrayIconBuilder,
-    AppHandle, Emitter, Manager,
+    AppHandle, Emitter, Manager, Wry,
 };
 
+/// Menu item ids for config sets are this prefix plus the set's name.
+const CONFIG_SET_PREFIX: &str = "config_set:";
+
+/// The "Switch Config Set" submenu and how many items it currently holds.
+struct ConfigSetMenu {
+    submenu: Submenu<Wry>,
+    len: std::sync::Mutex<usize>,
+}
+
 /// Set up the system tray with menu items.
 /// Call this from the Tauri setup hook in lib.rs.
-pub fn setup_tray(app: &AppHandle) -> tauri::Result<()> {
+pub fn setup_tray(app: &AppHandle, config_sets: &[String]) -> tauri::Result<()> {
     let sync_now = MenuItem::with_id(app, "sync_now", "Sync Now", true, None::<&str>)?;
+    let switch_set =
+        Submenu::with_id_and_items(app, "config_sets", "Switch Config Set", true, &[])?;
+    app.manage(ConfigSetMenu {
+        submenu: switch_set.clone(),
+        len: std::sync::Mutex::new(0),
+    });
+    refresh_config_sets(app, config_sets);
     let open_window = MenuItem::with_id(app, "open_window", "Open Window", true, None::<&str>)?;
     let view_backups =
         MenuItem::with_id(app, "view_backups", "View Backups", true, None::<&str>)?;
@@ -18,7 +34,14 @@ pub fn setup_tray(app: &AppHandle) -> tauri::Result<()> {
 
     let menu = Menu::with_items(
         app,
-        &[&sync_now, &open_window, &view_backups, &separator, &quit],
+        &[
+            &sync_now,
+            &switch_set,
+            &open_window,
+            &view_backups,
+            &separator,
+            &quit,
+        ],
     )?;
 
     TrayIconBuilder::new()
@@ -46,9 +69,55 @@ pub fn setup_tray(app: &AppHandle) -> tauri::Result<()> {
             "quit" => {
                 app.exit(0);
             }
-            _ => {}
+            id => {
+                if let Some(name) = id.strip_prefix(CONFIG_SET_PREFIX) {
+                    let name = name.to_string();
+                    let app = app.clone();
+                    tauri::async_runtime::spawn(async move {
+                        let engine = app.state::<crate::AppState>().engine.clone();
+                        let message = crate::switch_config_set_message(&engine, &name)
+                            .await
+                            .unwrap_or_else(|e| format!("error: {}", e));
+                        let _ = app.emit("sync-status", message);
+                    });
+                }
+            }
         })
         .build(app)?;
 
     Ok(())
 }
+
+/// Replace the config set entries in the tray menu.
+pub fn refresh_config_sets(app: &AppHandle, names: &[String]) {
+    let Some(menu) = app.try_state::<ConfigSetMenu>() else {
+        return;
+    };
+    let mut len = menu.len.lock().unwrap();
+    for _ in 0..*len {
+        let _ = menu.submenu.remove_at(0);
+    }
+    *len = 0;
+    let items: Vec<tauri::Result<MenuItem<Wry>>> = if names.is_empty() {
+        vec![MenuItem::with_id(
+            app,
+            "no_config_sets",
+            "No saved config sets",
+            false,
+            None::<&str>,
+        )]
+    } else {
+        names
+            .iter()
+            .map(|name| {
+                let id = format!("{}{}", CONFIG_SET_PREFIX, name);
+                MenuItem::with_id(app, id, name, true, None::<&str>)
+            })
+            .collect()
+    };
+    for item in items.into_iter().flatten() {
+        if menu.submenu.append(&item).is_ok() {
+            *len += 1;
+        }
+    }
+}
@@ -1,11 +1,170 @@
 import { useEffect, useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
 
+interface BackupFile {
+  name: string;
+  size: number;
+  compressed_size: number | null;
+  sha256: string;
+}
+
+interface BackupManifest {
+  reason:
+    | "pre-pull"
+    | "pre-push"
+    | "manual"
+    | "pre-restore"
+    | "imported"
+    | "scheduled"
+    | "config-set";
+  created: string;
+  source_device: string;
+  remote_device: string | null;
+  files: BackupFile[];
+  app_version: string;
+  gg_schema_version: number | null;
+  pinned: boolean;
+  note: string | null;
+  replicated: boolean;
+  config_set: string | null;
+}
+
+interface RemoteBackup {
+  id: string;
+  name: string;
+  device_name: string;
+  size: number;
+  uploaded: string;
+}
+
+type BackupHealth =
+  | { status: "ok" }
+  | { status: "incomplete"; detail: string }
+  | { status: "corrupt"; detail: string };
+
+interface Verification {
+  checked: string;
+  health: BackupHealth;
+}
+
+interface VerifySummary {
+  checked: number;
+  ok: number;
+  corrupt: string[];
+  incomplete: string[];
+}
+
+type SnapshotSource = { kind: "live" } | { kind: "backup"; name: string } | { kind: "remote" };
+
+type GgConcept = "device" | "app" | "profile" | "setting" | "other";
+
+interface RestoreUnit {
+  table: string;
+  key: string;
+  label: string | null;
+  concept: GgConcept;
+  child_rows: number;
+}
+
+interface DeviceInfo {
+  table: string;
+  key: string;
+  name: string | null;
+  active_profile: string | null;
+  details: Record<string, unknown>;
+}
+
+interface ProfileInfo {
+  table: string;
+  key: string;
+  name: string | null;
+  device: string | null;
+  apps: string[];
+  settings: Record<string, unknown>;
+}
+
+interface Inspection {
+  schema_version: number | null;
+  devices: DeviceInfo[];
+  profiles: ProfileInfo[];
+  tables: { name: string; concept: GgConcept; rows: number; interpreted: boolean }[];
+  warnings: string[];
+}
+
+interface FieldChange {
+  column: string;
+  before: unknown;
+  after: unknown;
+}
+
+interface RowSummary {
+  key: string;
+  label: string | null;
+}
+
+interface TableDiff {
+  table: string;
+  concept: GgConcept;
+  added: RowSummary[];
+  removed: RowSummary[];
+  changed: (RowSummary & { fields: FieldChange[] })[];
+}
+
+interface SnapshotDiff {
+  tables: TableDiff[];
+}
+
+interface SettingQuery {
+  device: string | null;
+  profile: string | null;
+  setting: string;
+}
+
+interface SettingChange {
+  at: string;
+  device: string;
+  backup_id: string | null;
+  remote: boolean;
+  value: unknown;
+  previous: unknown;
+}
+
+interface BackupEntry {
+  name: string;
+  path: string;
+  created: string;
+  manifest: BackupManifest | null;
+  verification: Verification | null;
+  size: number;
+  exclusive_size: number;
+}
+
+interface BackupList {
+  backups: BackupEntry[];
+  total_size: number;
+}
+
 export default function BackupBrowser() {
-  const [backups, setBackups] = useState<string[]>([]);
+  const [backups, setBackups] = useState<BackupEntry[]>([]);
+  const [totalSize, setTotalSize] = useState(0);
   const [loading, setLoading] = useState(true);
   const [restoring, setRestoring] = useState<string | null>(null);
+  const [verifying, setVerifying] = useState(false);
+  const [remoteBackups, setRemoteBackups] = useState<RemoteBackup[] | null>(null);
+  const [units, setUnits] = useState<{
+    name: string;
+    units: RestoreUnit[];
+    selected: Set<string>;
+  } | null>(null);
+  const [inspection, setInspection] = useState<{ title: string; result: Inspection } | null>(
+    null
+  );
+  const [diff, setDiff] = useState<{ name: string; result: SnapshotDiff } | null>(null);
   const [message, setMessage] = useState<{ text: string; error: boolean } | null>(null);
+  const [settingQuery, setSettingQuery] = useState<SettingQuery | null>(null);
+  const [includeRemote, setIncludeRemote] = useState(false);
+  const [settingTimeline, setSettingTimeline] = useState<SettingChange[] | null>(null);
+  const [searching, setSearching] = useState(false);
 
   useEffect(() => {
     fetchBackups();
@@ -14,16 +173,60 @@ export default function BackupBrowser() {
   async function fetchBackups() {
     setLoading(true);
     try {
-      const list = await invoke<string[]>("list_backups");
-      setBackups(list);
+      const list = await invoke<BackupList>("list_backups");
+      setBackups(list.backups);
+      setTotalSize(list.total_size);
     } catch {
       // Backend not ready yet, show empty state
       setBackups([]);
+      setTotalSize(0);
     } finally {
       setLoading(false);
     }
   }
 
+  async function saveConfigSet() {
+    const name = window.prompt("Save the current config as config set:");
+    if (!name?.trim()) return;
+    setMessage(null);
+    try {
+      const result = await invoke<string>("save_config_set", { name });
+      setMessage({ text: result, error: false });
+      await fetchBackups();
+    } catch (err) {
+      setMessage({ text: String(err), error: true });
+    }
+  }
+
+  async function switchConfigSet(name: string) {
+    const confirmed = window.confirm(
+      `Switch to config set "${name}"?\n\nThis replaces your current SteelSeries config. A backup of the current config will be created first.`
+    );
+    if (!confirmed) return;
+    setRestoring(name);
+    setMessage(null);
+    try {
+      const result = await invoke<string>("switch_config_set", { name });
+      setMessage({ text: result, error: false });
+      await fetchBackups();
+    } catch (err) {
+      setMessage({ text: String(err), error: true });
+    } finally {
+      setRestoring(null);
+    }
+  }
+
+  async function deleteConfigSet(name: string) {
+    if (!window.confirm(`Delete config set "${name}"?`)) return;
+    setMessage(null);
+    try {
+      await invoke("delete_config_set", { name });
+      await fetchBackups();
+    } catch (err) {
+      setMessage({ text: String(err), error: true });
+    }
+  }
+
   async function restoreBackup(name: string) {
     const confirmed = window.confirm(
       `Restore backup "${name}"?\n\nThis will overwrite your current SteelSeries config with this backup. A new backup of the current config will be created first.`
@@ -33,8 +236,8 @@ export default function BackupBrowser() {
     setRestoring(name);
     setMessage(null);
     try {
-      await invoke("restore_backup", { backupName: name });
-      setMessage({ text: `Restored backup "${name}" successfully.`, error: false });
+      const result = await invoke<string>("restore_backup", { backupName: name });
+      setMessage({ text: result, error: false });
       await fetchBackups();
     } catch (err) {
       setMessage({ text: String(err), error: true });
@@ -43,14 +246,284 @@ export default function BackupBrowser() {
     }
   }
 
-  function parseBackupDate(name: string): string {
-    // Backup names are like "pre-pull-2026-02-16T14-30-00" or "sync-2026-02-16T14-30-00"
-    const match = name.match(/(\d{4}-\d{2}-\d{2})T(\d{2})-(\d{2})-(\d{2})$/);
-    if (match) {
-      const [, date, h, m, s] = match;
-      return `${date} ${h}:${m}:${s}`;
+  async function undoRestore() {
+    const confirmed = window.confirm(
+      "Undo the last restore?\n\nThis puts back the config that was active before it."
+    );
+    if (!confirmed) return;
+
+    setMessage(null);
+    try {
+      const result = await invoke<string>("undo_last_restore");
+      setMessage({ text: result, error: false });
+      await fetchBackups();
+    } catch (err) {
+      setMessage({ text: String(err), error: true });
+    }
+  }
+
+  async function verifyBackups() {
+    setVerifying(true);
+    setMessage(null);
+    try {
+      const summary = await invoke<VerifySummary>("verify_backups");
+      const bad = summary.corrupt.length + summary.incomplete.length;
+      setMessage({
+        text:
+          bad === 0
+            ? `All ${summary.checked} backups verified OK.`
+            : `Checked ${summary.checked} backups: ${summary.corrupt.length} corrupt, ${summary.incomplete.length} incomplete.`,
+        error: bad > 0,
+      });
+      await fetchBackups();
+    } catch (err) {
+      setMessage({ text: String(err), error: true });
+    } finally {
+      setVerifying(false);
+    }
+  }
+
+  async function compareWithLive(name: string) {
+    setMessage(null);
+    const from: SnapshotSource = { kind: "backup", name };
+    const to: SnapshotSource = { kind: "live" };
+    try {
+      const result = await invoke<SnapshotDiff>("diff_snapshots", { from, to });
+      setDiff({ name, result });
+    } catch (err) {
+      setMessage({ text: String(err), error: true });
+    }
+  }
+
+  async function inspect(source: SnapshotSource, title: string) {
+    setMessage(null);
+    try {
+      const result = await invoke<Inspection>("inspect_snapshot", { source });
+      setInspection({ title, result });
+    } catch (err) {
+      setMessage({ text: String(err), error: true });
+    }
+  }
+
+  function profileName(result: Inspection, key: string | null): string | null {
+    if (key === null) return null;
+    return result.profiles.find((p) => p.key === key)?.name ?? key;
+  }
+
+  function unitId(unit: RestoreUnit): string {
+    return `${unit.table}/${unit.key}`;
+  }
+
+  async function showUnits(name: string) {
+    setMessage(null);
+    try {
+      const list = await invoke<RestoreUnit[]>("list_backup_units", { backupName: name });
+      setUnits({ name, units: list, selected: new Set() });
+    } catch (err) {
+      setMessage({ text: String(err), error: true });
+    }
+  }
+
+  function toggleUnit(unit: RestoreUnit) {
+    if (!units) return;
+    const selected = new Set(units.selected);
+    if (!selected.delete(unitId(unit))) selected.add(unitId(unit));
+    setUnits({ ...units, selected });
+  }
+
+  async function restoreSelectedUnits() {
+    if (!units) return;
+    const chosen = units.units
+      .filter((unit) => units.selected.has(unitId(unit)))
+      .map(({ table, key }) => ({ table, key }));
+    const confirmed = window.confirm(
+      `Restore ${chosen.length} item(s) from "${units.name}"?\n\nOnly the selected devices and profiles are replaced. A backup of the current config will be created first.`
+    );
+    if (!confirmed) return;
+
+    setRestoring(units.name);
+    setMessage(null);
+    try {
+      const result = await invoke<string>("restore_backup_units", {
+        backupName: units.name,
+        units: chosen,
+      });
+      setMessage({ text: result, error: false });
+      setUnits(null);
+      await fetchBackups();
+    } catch (err) {
+      setMessage({ text: String(err), error: true });
+    } finally {
+      setRestoring(null);
     }
-    return "";
+  }
+
+  async function exportSelectedProfiles() {
+    if (!units) return;
+    const chosen = units.units
+      .filter((unit) => unit.concept === "profile" && units.selected.has(unitId(unit)))
+      .map(({ table, key }) => ({ table, key }));
+    const path = window.prompt("Save profiles to (.toml or .json):", "profiles.toml");
+    if (!path) return;
+    setMessage(null);
+    try {
+      const count = await invoke<number>("export_profiles", {
+        source: { kind: "backup", name: units.name },
+        units: chosen,
+        path,
+      });
+      setMessage({ text: `Exported ${count} profile(s) to ${path}`, error: false });
+    } catch (err) {
+      setMessage({ text: String(err), error: true });
+    }
+  }
+
+  async function importProfiles() {
+    const path = window.prompt("Path of the .toml or .json profile export to import:");
+    if (!path) return;
+    const confirmed = window.confirm(
+      "Import these profiles into your current SteelSeries config?\n\nProfiles with the same ID are replaced. A backup is taken first and the import can be undone."
+    );
+    if (!confirmed) return;
+    setMessage(null);
+    try {
+      const result = await invoke<string>("import_profiles", { path });
+      setMessage({ text: result, error: false });
+      await fetchBackups();
+    } catch (err) {
+      setMessage({ text: String(err), error: true });
+    }
+  }
+
+  async function searchSettingHistory() {
+    if (!settingQuery?.setting) return;
+    setSearching(true);
+    setMessage(null);
+    try {
+      setSettingTimeline(
+        await invoke<SettingChange[]>("setting_history", {
+          query: settingQuery,
+          includeRemote,
+        })
+      );
+    } catch (err) {
+      setMessage({ text: String(err), error: true });
+    } finally {
+      setSearching(false);
+    }
+  }
+
+  function formatSettingValue(value: unknown): string {
+    if (value === null || value === undefined) return "(unset)";
+    return typeof value === "string" ? value : JSON.stringify(value);
+  }
+
+  async function fetchRemoteBackups() {
+    setMessage(null);
+    try {
+      setRemoteBackups(await invoke<RemoteBackup[]>("list_remote_backups"));
+    } catch (err) {
+      setMessage({ text: String(err), error: true });
+    }
+  }
+
+  async function restoreRemoteBackup(backup: RemoteBackup) {
+    const confirmed = window.confirm(
+      `Restore "${backup.name}" from ${backup.device_name}?\n\nThis will overwrite your current SteelSeries config. A backup of the current config will be created first.`
+    );
+    if (!confirmed) return;
+
+    setRestoring(backup.id);
+    setMessage(null);
+    try {
+      const result = await invoke<string>("restore_remote_backup", { backupId: backup.id });
+      setMessage({ text: result, error: false });
+      await fetchBackups();
+    } catch (err) {
+      setMessage({ text: String(err), error: true });
+    } finally {
+      setRestoring(null);
+    }
+  }
+
+  async function exportArchive(source: SnapshotSource, suggestedName: string) {
+    const path = window.prompt("Save archive to:", `${suggestedName}.ggsync`);
+    if (!path) return;
+    setMessage(null);
+    try {
+      await invoke("export_snapshot", { source, path });
+      setMessage({ text: `Exported to ${path}`, error: false });
+    } catch (err) {
+      setMessage({ text: String(err), error: true });
+    }
+  }
+
+  async function importArchive() {
+    const path = window.prompt("Path of the .ggsync archive to import:");
+    if (!path) return;
+    const apply = window.confirm(
+      "Apply the imported config now?\n\nOK overwrites your current SteelSeries config (a backup is taken first and the restore can be undone). Cancel only adds it to the backup list."
+    );
+    setMessage(null);
+    try {
+      const result = await invoke<string>("import_snapshot", { path, apply });
+      setMessage({ text: result, error: false });
+      await fetchBackups();
+    } catch (err) {
+      setMessage({ text: String(err), error: true });
+    }
+  }
+
+  function describeRow(row: RowSummary): string {
+    return row.label ? `${row.label} (${row.key})` : row.key;
+  }
+
+  async function togglePin(backup: BackupEntry) {
+    setMessage(null);
+    try {
+      await invoke("pin_backup", { backupName: backup.name, pinned: !backup.manifest?.pinned });
+      await fetchBackups();
+    } catch (err) {
+      setMessage({ text: String(err), error: true });
+    }
+  }
+
+  async function editNote(backup: BackupEntry) {
+    const note = window.prompt("Note for this backup:", backup.manifest?.note ?? "");
+    if (note === null) return;
+    setMessage(null);
+    try {
+      await invoke("annotate_backup", { backupName: backup.name, note: note || null });
+      await fetchBackups();
+    } catch (err) {
+      setMessage({ text: String(err), error: true });
+    }
+  }
+
+  function formatDate(backup: BackupEntry): string {
+    return new Date(backup.manifest?.created ?? backup.created).toLocaleString();
+  }
+
+  function formatSize(bytes: number): string {
+    return bytes >= 1024 * 1024
+      ? `${(bytes / (1024 * 1024)).toFixed(1)} MB`
+      : `${(bytes / 1024).toFixed(0)} KB`;
+  }
+
+  function describe(backup: BackupEntry): string {
+    const usage = `${formatSize(backup.exclusive_size)} on disk`;
+    const manifest = backup.manifest;
+    if (!manifest) return usage;
+    const origin = manifest.remote_device
+      ? `${manifest.source_device} ← ${manifest.remote_device}`
+      : manifest.source_device;
+    return `${manifest.reason} · ${origin} · ${usage}`;
+  }
+
+  function healthLabel(verification: Verification | null): string | null {
+    if (!verification || verification.health.status === "ok") return null;
+    const kind = verification.health.status === "corrupt" ? "Corrupt" : "Incomplete";
+    return `${kind}: ${verification.health.detail}`;
   }
 
   if (loading) {
@@ -66,9 +539,59 @@ export default function BackupBrowser() {
     <div className="page">
       <h2>Backups</h2>
 
+      {backups.length > 0 && (
+        <div className="backup-date" style={{ marginBottom: 12 }}>
+          {backups.length} backups · {formatSize(totalSize)} total
+        </div>
+      )}
+
       <div style={{ marginBottom: 16 }}>
         <button className="btn btn-secondary btn-sm" onClick={fetchBackups}>
           Refresh
+        </button>{" "}
+        <button
+          className="btn btn-secondary btn-sm"
+          onClick={undoRestore}
+          disabled={restoring !== null}
+        >
+          Undo Last Restore
+        </button>{" "}
+        <button
+          className="btn btn-secondary btn-sm"
+          onClick={verifyBackups}
+          disabled={verifying}
+        >
+          {verifying ? "Verifying..." : "Verify Backups"}
+        </button>{" "}
+        <button
+          className="btn btn-secondary btn-sm"
+          onClick={() => exportArchive({ kind: "live" }, "steelseries-config")}
+        >
+          Export Current Config
+        </button>{" "}
+        <button
+          className="btn btn-secondary btn-sm"
+          onClick={() => inspect({ kind: "live" }, "current config")}
+        >
+          Inspect Current Config
+        </button>{" "}
+        <button className="btn btn-secondary btn-sm" onClick={importArchive}>
+          Import Archive
+        </button>{" "}
+        <button className="btn btn-secondary btn-sm" onClick={importProfiles}>
+          Import Profiles
+        </button>{" "}
+        <button className="btn btn-secondary btn-sm" onClick={fetchRemoteBackups}>
+          Remote Backups
+        </button>{" "}
+        <button className="btn btn-secondary btn-sm" onClick={saveConfigSet}>
+          Save as Config Set
+        </button>{" "}
+        <button
+          className="btn btn-secondary btn-sm"
+          onClick={() => setSettingQuery({ device: null, profile: null, setting: "" })}
+        >
+          Setting History
         </button>
       </div>
 
@@ -81,25 +604,358 @@ export default function BackupBrowser() {
         </div>
       )}
 
+      {remoteBackups && (
+        <div className="status-card">
+          <div className="backup-actions" style={{ justifyContent: "space-between" }}>
+            <span className="backup-name">Remote Backups</span>
+            <button className="btn btn-secondary btn-sm" onClick={() => setRemoteBackups(null)}>
+              Close
+            </button>
+          </div>
+          {remoteBackups.length === 0 ? (
+            <div className="backup-date">No backups have been replicated yet.</div>
+          ) : (
+            <div className="backup-list" style={{ marginTop: 12 }}>
+              {remoteBackups.map((backup) => (
+                <div key={backup.id} className="backup-item">
+                  <div className="backup-info">
+                    <span className="backup-name">{backup.name}</span>
+                    <span className="backup-date">
+                      {backup.device_name} · {new Date(backup.uploaded).toLocaleString()} ·{" "}
+                      {(backup.size / 1024).toFixed(0)} KB
+                    </span>
+                  </div>
+                  <button
+                    className="btn btn-danger btn-sm"
+                    onClick={() => restoreRemoteBackup(backup)}
+                    disabled={restoring !== null}
+                  >
+                    {restoring === backup.id ? "Restoring..." : "Restore"}
+                  </button>
+                </div>
+              ))}
+            </div>
+          )}
+        </div>
+      )}
+
+      {inspection && (
+        <div className="status-card">
+          <div className="backup-actions" style={{ justifyContent: "space-between" }}>
+            <span className="backup-name">Contents of {inspection.title}</span>
+            <button className="btn btn-secondary btn-sm" onClick={() => setInspection(null)}>
+              Close
+            </button>
+          </div>
+          <div className="backup-info" style={{ marginTop: 12 }}>
+            <span className="backup-date">
+              Schema version {inspection.result.schema_version ?? "unknown"} ·{" "}
+              {inspection.result.tables.length} tables
+            </span>
+            <span className="backup-name">Devices</span>
+            {inspection.result.devices.length === 0 && (
+              <span className="backup-date">No devices recognized.</span>
+            )}
+            {inspection.result.devices.map((device) => (
+              <span key={`${device.table}/${device.key}`} className="backup-date">
+                {device.name ?? device.key}
+                {device.active_profile !== null &&
+                  ` · active profile: ${profileName(inspection.result, device.active_profile)}`}
+              </span>
+            ))}
+            <span className="backup-name">Profiles</span>
+            {inspection.result.profiles.length === 0 && (
+              <span className="backup-date">No profiles recognized.</span>
+            )}
+            {inspection.result.profiles.map((profile) => (
+              <span key={`${profile.table}/${profile.key}`} className="backup-date">
+                {profile.name ?? profile.key}
+                {profile.apps.length > 0 && ` · apps: ${profile.apps.join(", ")}`}
+                {Object.keys(profile.settings).length > 0 &&
+                  ` · ${Object.entries(profile.settings)
+                    .map(([k, v]) => `${k}=${JSON.stringify(v)}`)
+                    .join(", ")}`}
+              </span>
+            ))}
+            {inspection.result.warnings.map((warning) => (
+              <span key={warning} className="backup-health">
+                {warning}
+              </span>
+            ))}
+          </div>
+        </div>
+      )}
+
+      {backups.some((b) => b.manifest?.config_set) && (
+        <div className="status-card">
+          <span className="backup-name">Config Sets</span>
+          <div className="backup-list" style={{ marginTop: 12 }}>
+            {backups
+              .filter((b) => b.manifest?.config_set)
+              .map((backup) => {
+                const name = backup.manifest!.config_set!;
+                return (
+                  <div key={backup.name} className="backup-item">
+                    <div className="backup-info">
+                      <span className="backup-name">{name}</span>
+                      <span className="backup-date">
+                        Saved {new Date(backup.created).toLocaleString()}
+                      </span>
+                    </div>
+                    <div className="backup-actions">
+                      <button
+                        className="btn btn-primary btn-sm"
+                        onClick={() => switchConfigSet(name)}
+                        disabled={restoring !== null}
+                      >
+                        {restoring === name ? "Switching..." : "Switch"}
+                      </button>
+                      <button
+                        className="btn btn-danger btn-sm"
+                        onClick={() => deleteConfigSet(name)}
+                      >
+                        Delete
+                      </button>
+                    </div>
+                  </div>
+                );
+              })}
+          </div>
+        </div>
+      )}
+
+      {units && (
+        <div className="status-card">
+          <div className="backup-actions" style={{ justifyContent: "space-between" }}>
+            <span className="backup-name">Partial restore from {units.name}</span>
+            <button className="btn btn-secondary btn-sm" onClick={() => setUnits(null)}>
+              Close
+            </button>
+          </div>
+          {units.units.length === 0 ? (
+            <div className="backup-date">No devices or profiles found in this backup.</div>
+          ) : (
+            <div className="backup-info" style={{ marginTop: 12 }}>
+              {units.units.map((unit) => (
+                <label key={unitId(unit)} className="backup-date">
+                  <input
+                    type="checkbox"
+                    checked={units.selected.has(unitId(unit))}
+                    onChange={() => toggleUnit(unit)}
+                  />{" "}
+                  {unit.concept}: {unit.label ?? unit.key}
+                  {unit.child_rows > 0 && ` (+${unit.child_rows} related rows)`}
+                </label>
+              ))}
+              <div style={{ marginTop: 8 }}>
+                <button
+                  className="btn btn-danger btn-sm"
+                  onClick={restoreSelectedUnits}
+                  disabled={units.selected.size === 0 || restoring !== null}
+                >
+                  {restoring === units.name ? "Restoring..." : "Restore Selected"}
+                </button>{" "}
+                <button
+                  className="btn btn-secondary btn-sm"
+                  onClick={exportSelectedProfiles}
+                  disabled={
+                    !units.units.some(
+                      (unit) => unit.concept === "profile" && units.selected.has(unitId(unit))
+                    )
+                  }
+                >
+                  Export Selected Profiles
+                </button>
+              </div>
+            </div>
+          )}
+        </div>
+      )}
+
+      {settingQuery && (
+        <div className="status-card">
+          <div className="backup-actions" style={{ justifyContent: "space-between" }}>
+            <span className="backup-name">Setting History</span>
+            <button
+              className="btn btn-secondary btn-sm"
+              onClick={() => {
+                setSettingQuery(null);
+                setSettingTimeline(null);
+              }}
+            >
+              Close
+            </button>
+          </div>
+          <div className="form-row" style={{ marginTop: 12 }}>
+            <div className="form-group">
+              <label htmlFor="history-device">Device</label>
+              <input
+                id="history-device"
+                type="text"
+                value={settingQuery.device ?? ""}
+                onChange={(e) => setSettingQuery({ ...settingQuery, device: e.target.value || null })}
+                placeholder="e.g. Rival 3 (optional for profiles)"
+              />
+            </div>
+            <div className="form-group">
+              <label htmlFor="history-profile">Profile</label>
+              <input
+                id="history-profile"
+                type="text"
+                value={settingQuery.profile ?? ""}
+                onChange={(e) => setSettingQuery({ ...settingQuery, profile: e.target.value || null })}
+                placeholder="Empty for device settings"
+              />
+            </div>
+            <div className="form-group">
+              <label htmlFor="history-setting">Setting</label>
+              <input
+                id="history-setting"
+                type="text"
+                value={settingQuery.setting}
+                onChange={(e) => setSettingQuery({ ...settingQuery, setting: e.target.value })}
+                placeholder="e.g. Sensitivity"
+              />
+            </div>
+          </div>
+          <label className="backup-date">
+            <input
+              type="checkbox"
+              checked={includeRemote}
+              onChange={(e) => setIncludeRemote(e.target.checked)}
+            />{" "}
+            Include backups from other machines
+          </label>{" "}
+          <button
+            className="btn btn-primary btn-sm"
+            onClick={searchSettingHistory}
+            disabled={!settingQuery.setting || searching}
+          >
+            {searching ? "Searching..." : "Search"}
+          </button>
+          {settingTimeline && (
+            <div className="backup-info" style={{ marginTop: 12 }}>
+              {settingTimeline.length === 0 && (
+                <span className="backup-date">Setting not found in any stored config.</span>
+              )}
+              {settingTimeline.map((change, i) => (
+                <span key={i} className="backup-date">
+                  {new Date(change.at).toLocaleString()} · {change.device}
+                  {change.remote && " (remote)"} · {formatSettingValue(change.previous)} →{" "}
+                  {formatSettingValue(change.value)} · {change.backup_id ?? "current config"}
+                </span>
+              ))}
+            </div>
+          )}
+        </div>
+      )}
+
+      {diff && (
+        <div className="status-card">
+          <div className="backup-actions" style={{ justifyContent: "space-between" }}>
+            <span className="backup-name">Changes since {diff.name}</span>
+            <button className="btn btn-secondary btn-sm" onClick={() => setDiff(null)}>
+              Close
+            </button>
+          </div>
+          {diff.result.tables.length === 0 ? (
+            <div className="backup-date">The live config matches this backup.</div>
+          ) : (
+            diff.result.tables.map((table) => (
+              <div key={table.table} className="backup-info" style={{ marginTop: 12 }}>
+                <span className="backup-name">
+                  {table.table} ({table.concept})
+                </span>
+                {table.added.map((row) => (
+                  <span key={`+${row.key}`} className="backup-date">
+                    + {describeRow(row)}
+                  </span>
+                ))}
+                {table.removed.map((row) => (
+                  <span key={`-${row.key}`} className="backup-date">
+                    − {describeRow(row)}
+                  </span>
+                ))}
+                {table.changed.map((row) => (
+                  <span key={`~${row.key}`} className="backup-date">
+                    ~ {describeRow(row)}:{" "}
+                    {row.fields
+                      .map((f) => `${f.column} ${JSON.stringify(f.before)} → ${JSON.stringify(f.after)}`)
+                      .join(", ")}
+                  </span>
+                ))}
+              </div>
+            ))
+          )}
+        </div>
+      )}
+
       {backups.length === 0 ? (
         <div className="empty-state">
           No backups yet. Backups are created automatically before sync operations.
         </div>
       ) : (
         <div className="backup-list">
-          {backups.map((name) => (
-            <div key={name} className="backup-item">
+          {backups.map((backup) => (
+            <div key={backup.name} className="backup-item">
               <div className="backup-info">
-                <span className="backup-name">{name}</span>
-                <span className="backup-date">{parseBackupDate(name)}</span>
+                <span className="backup-name">{backup.name}</span>
+                <span className="backup-date">{formatDate(backup)}</span>
+                <span className="backup-date">{describe(backup)}</span>
+                {backup.manifest?.note && (
+                  <span className="backup-date">{backup.manifest.note}</span>
+                )}
+                {healthLabel(backup.verification) && (
+                  <span className="backup-health">{healthLabel(backup.verification)}</span>
+                )}
+              </div>
+              <div className="backup-actions">
+                <button
+                  className="btn btn-secondary btn-sm"
+                  onClick={() => togglePin(backup)}
+                  disabled={!backup.manifest}
+                >
+                  {backup.manifest?.pinned ? "Unpin" : "Pin"}
+                </button>
+                <button
+                  className="btn btn-secondary btn-sm"
+                  onClick={() => editNote(backup)}
+                  disabled={!backup.manifest}
+                >
+                  Note
+                </button>
+                <button
+                  className="btn btn-secondary btn-sm"
+                  onClick={() => compareWithLive(backup.name)}
+                >
+                  Compare
+                </button>
+                <button
+                  className="btn btn-secondary btn-sm"
+                  onClick={() => inspect({ kind: "backup", name: backup.name }, backup.name)}
+                >
+                  Inspect
+                </button>
+                <button
+                  className="btn btn-secondary btn-sm"
+                  onClick={() => exportArchive({ kind: "backup", name: backup.name }, backup.name)}
+                >
+                  Export
+                </button>
+                <button
+                  className="btn btn-secondary btn-sm"
+                  onClick={() => showUnits(backup.name)}
+                >
+                  Partial
+                </button>
+                <button
+                  className="btn btn-danger btn-sm"
+                  onClick={() => restoreBackup(backup.name)}
+                  disabled={restoring !== null}
+                >
+                  {restoring === backup.name ? "Restoring..." : "Restore"}
+                </button>
               </div>
-              <button
-                className="btn btn-danger btn-sm"
-                onClick={() => restoreBackup(name)}
-                disabled={restoring !== null}
-              >
-                {restoring === name ? "Restoring..." : "Restore"}
-              </button>
             </div>
           ))}
         </div>
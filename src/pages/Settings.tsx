@@ -5,9 +5,103 @@ interface AppConfig {
   steelseries_db_path: string;
   backup_dir: string;
   max_backups: number;
+  backup_budget_mb: number;
+  retention: RetentionPolicy;
   debounce_secs: number;
   provider: ProviderConfig;
   device_name: string;
+  gg_restart: GgRestartConfig;
+  verify_interval_hours: number;
+  archive_signing_key: string | null;
+  backup_schedule: BackupScheduleConfig;
+  replication: ReplicationConfig;
+  selective_sync: SelectiveSyncConfig;
+  field_transforms: TransformConfig;
+  path_mapping: PathMappingConfig;
+  kiosk: KioskConfig;
+}
+
+interface KioskConfig {
+  enabled: boolean;
+  golden_backup: string | null;
+  override_password_hash: string | null;
+}
+
+interface KioskStatus {
+  enabled: boolean;
+  locked: boolean;
+  golden_backup: string | null;
+}
+
+interface GoldenCandidates {
+  local: { name: string; created: string }[];
+  remote: { id: string; name: string; device_name: string }[];
+}
+
+type PairingStatus = "suggested" | "confirmed" | "rejected";
+
+interface DevicePairing {
+  table: string;
+  remote_key: string;
+  local_key: string;
+  model: string | null;
+  status: PairingStatus;
+}
+
+interface DeviceMap {
+  pairings: DevicePairing[];
+}
+
+interface GameMapping {
+  name: string;
+  windows: string | null;
+  macos: string | null;
+  linux: string | null;
+}
+
+interface PathMappingConfig {
+  enabled: boolean;
+  games: GameMapping[];
+}
+
+interface FieldRef {
+  table: string;
+  column: string;
+}
+
+interface TransformConfig {
+  machine_fields: FieldRef[];
+  overrides: (FieldRef & { key: string | null; value: unknown })[];
+}
+
+interface SelectiveSyncConfig {
+  include: string[];
+  exclude: string[];
+}
+
+interface ReplicationConfig {
+  pinned: boolean;
+  daily: boolean;
+}
+
+interface BackupScheduleConfig {
+  interval: "off" | "hourly" | "daily";
+  on_gg_exit: boolean;
+  max_backups: number;
+  retention: RetentionPolicy;
+}
+
+interface RetentionPolicy {
+  keep_all_hours: number;
+  daily_days: number;
+  weekly_weeks: number;
+}
+
+interface GgRestartConfig {
+  enabled: boolean;
+  executable_path: string;
+  exit_timeout_secs: number;
+  force_kill_on_timeout: boolean;
 }
 
 type ProviderConfig =
@@ -18,29 +112,137 @@ const DEFAULT_CONFIG: AppConfig = {
   steelseries_db_path: "",
   backup_dir: "",
   max_backups: 20,
+  backup_budget_mb: 0,
+  retention: { keep_all_hours: 24, daily_days: 7, weekly_weeks: 4 },
   debounce_secs: 3,
   provider: { type: "Folder", sync_dir: "" },
   device_name: "",
+  gg_restart: {
+    enabled: false,
+    executable_path: "",
+    exit_timeout_secs: 30,
+    force_kill_on_timeout: false,
+  },
+  verify_interval_hours: 0,
+  archive_signing_key: null,
+  backup_schedule: {
+    interval: "off",
+    on_gg_exit: false,
+    max_backups: 24,
+    retention: { keep_all_hours: 24, daily_days: 7, weekly_weeks: 4 },
+  },
+  replication: { pinned: false, daily: false },
+  selective_sync: { include: [], exclude: [] },
+  field_transforms: { machine_fields: [], overrides: [] },
+  path_mapping: { enabled: false, games: [] },
+  kiosk: { enabled: false, golden_backup: null, override_password_hash: null },
 };
 
+// Blank entries are kept while typing and ignored by the backend
+function parseList(value: string): string[] {
+  return value === "" ? [] : value.split(",").map((item) => item.trimStart());
+}
+
+/** One game per line: "Name: path; path; ...". Each path's OS is told from its shape. */
+function parseGameMappings(value: string): GameMapping[] {
+  return value
+    .split("\n")
+    .map((line) => line.split(":"))
+    .filter(([name, ...rest]) => name.trim() && rest.length > 0)
+    .map(([name, ...rest]) => {
+      const game: GameMapping = { name: name.trim(), windows: null, macos: null, linux: null };
+      for (const path of rest.join(":").split(";").map((p) => p.trim()).filter(Boolean)) {
+        if (/^[a-zA-Z]:[\\/]/.test(path) || path.startsWith("\\\\")) game.windows = path;
+        else if (path.startsWith("/Applications/") || path.startsWith("/Users/") || path.includes(".app"))
+          game.macos = path;
+        else game.linux = path;
+      }
+      return game;
+    });
+}
+
+function formatGameMappings(games: GameMapping[]): string {
+  return games
+    .map((game) => `${game.name}: ${[game.windows, game.macos, game.linux].filter(Boolean).join("; ")}`)
+    .join("\n");
+}
+
 export default function Settings() {
   const [config, setConfig] = useState<AppConfig>(DEFAULT_CONFIG);
   const [providerType, setProviderType] = useState<"Folder" | "Hosted">("Folder");
   const [syncDir, setSyncDir] = useState("");
   const [apiUrl, setApiUrl] = useState("");
   const [apiKey, setApiKey] = useState("");
+  const [machineFields, setMachineFields] = useState("");
+  const [gameMappings, setGameMappings] = useState("");
+  const [deviceMap, setDeviceMap] = useState<DeviceMap>({ pairings: [] });
+  const [kioskStatus, setKioskStatus] = useState<KioskStatus | null>(null);
+  const [goldenCandidates, setGoldenCandidates] = useState<GoldenCandidates>({
+    local: [],
+    remote: [],
+  });
+  const [overridePassword, setOverridePassword] = useState("");
+  const [unlockPassword, setUnlockPassword] = useState("");
   const [saving, setSaving] = useState(false);
   const [message, setMessage] = useState<{ text: string; error: boolean } | null>(null);
 
   useEffect(() => {
     loadConfig();
+    invoke<DeviceMap>("get_device_map").then(setDeviceMap).catch(() => {});
+    invoke<KioskStatus>("kiosk_status").then(setKioskStatus).catch(() => {});
   }, []);
 
+  useEffect(() => {
+    if (config.kiosk.enabled) loadGoldenCandidates();
+  }, [config.kiosk.enabled]);
+
+  async function loadGoldenCandidates() {
+    const local = await invoke<{ backups: GoldenCandidates["local"] }>("list_backups")
+      .then((list) => list.backups)
+      .catch(() => []);
+    const remote = await invoke<GoldenCandidates["remote"]>("list_remote_backups").catch(() => []);
+    setGoldenCandidates({ local, remote });
+  }
+
+  async function selectGolden(value: string) {
+    if (!value.startsWith("remote:")) {
+      setConfig({ ...config, kiosk: { ...config.kiosk, golden_backup: value || null } });
+      return;
+    }
+    try {
+      const name = await invoke<string>("import_golden_backup", { id: value.slice("remote:".length) });
+      setConfig({ ...config, kiosk: { ...config.kiosk, golden_backup: name } });
+      await loadGoldenCandidates();
+    } catch (err) {
+      setMessage({ text: String(err), error: true });
+    }
+  }
+
+  async function unlockKiosk() {
+    try {
+      await invoke("unlock_kiosk", { password: unlockPassword });
+      setUnlockPassword("");
+      setKioskStatus(await invoke<KioskStatus>("kiosk_status"));
+      setMessage(null);
+    } catch (err) {
+      setMessage({ text: String(err), error: true });
+    }
+  }
+
+  async function lockKiosk() {
+    await invoke("lock_kiosk");
+    setKioskStatus(await invoke<KioskStatus>("kiosk_status"));
+  }
+
   async function loadConfig() {
     try {
       const json = await invoke<string>("get_config");
       const loaded: AppConfig = JSON.parse(json);
       setConfig(loaded);
+      setMachineFields(
+        loaded.field_transforms.machine_fields.map((f) => `${f.table}.${f.column}`).join(", ")
+      );
+      setGameMappings(formatGameMappings(loaded.path_mapping.games));
       if (loaded.provider.type === "Folder") {
         setProviderType("Folder");
         setSyncDir(loaded.provider.sync_dir);
@@ -63,13 +265,31 @@ export default function Settings() {
         ? { type: "Folder", sync_dir: syncDir }
         : { type: "Hosted", api_url: apiUrl, api_key: apiKey };
 
+    const machine_fields = machineFields
+      .split(",")
+      .map((entry) => entry.trim().split("."))
+      .filter((parts) => parts.length === 2 && parts[0] && parts[1])
+      .map(([table, column]) => ({ table, column }));
+
     const updated: AppConfig = {
       ...config,
       provider,
+      field_transforms: { ...config.field_transforms, machine_fields },
+      path_mapping: { ...config.path_mapping, games: parseGameMappings(gameMappings) },
     };
 
     try {
+      if (overridePassword) {
+        updated.kiosk = {
+          ...updated.kiosk,
+          override_password_hash: await invoke<string>("hash_kiosk_password", {
+            password: overridePassword,
+          }),
+        };
+      }
       await invoke("save_config", { config: updated });
+      setConfig(updated);
+      setOverridePassword("");
       setMessage({ text: "Settings saved.", error: false });
     } catch (err) {
       setMessage({ text: String(err), error: true });
@@ -78,10 +298,62 @@ export default function Settings() {
     }
   }
 
+  async function refreshDeviceMap() {
+    try {
+      setDeviceMap(await invoke<DeviceMap>("refresh_device_map"));
+    } catch (err) {
+      setMessage({ text: String(err), error: true });
+    }
+  }
+
+  async function setPairingStatus(index: number, status: PairingStatus) {
+    const updated: DeviceMap = {
+      pairings: deviceMap.pairings.map((p, i) => (i === index ? { ...p, status } : p)),
+    };
+    try {
+      await invoke("save_device_map", { map: updated });
+      setDeviceMap(updated);
+    } catch (err) {
+      setMessage({ text: String(err), error: true });
+    }
+  }
+
   return (
     <div className="page">
       <h2>Settings</h2>
 
+      {kioskStatus?.enabled && (
+        <div className="status-card" style={{ marginBottom: 24 }}>
+          <span className="backup-name">
+            {kioskStatus.locked ? "Kiosk Mode Is On" : "Kiosk Mode Unlocked"}
+          </span>
+          <p className="backup-date">
+            {kioskStatus.locked
+              ? "The golden backup is restored at every start and whenever GG exits, and nothing is pushed. Settings are read-only until unlocked."
+              : "Pushes and settings are enabled and the golden backup is not restored until you lock kiosk mode again."}
+          </p>
+          {kioskStatus.locked ? (
+            <div className="form-row">
+              <div className="form-group">
+                <input
+                  type="password"
+                  value={unlockPassword}
+                  onChange={(e) => setUnlockPassword(e.target.value)}
+                  placeholder="Override password"
+                />
+              </div>
+              <button className="btn btn-secondary btn-sm" onClick={unlockKiosk}>
+                Unlock
+              </button>
+            </div>
+          ) : (
+            <button className="btn btn-secondary btn-sm" onClick={lockKiosk}>
+              Lock
+            </button>
+          )}
+        </div>
+      )}
+
       <div className="form-group">
         <label htmlFor="ss-path">SteelSeries Config Path</label>
         <input
@@ -172,6 +444,18 @@ export default function Settings() {
             }
           />
         </div>
+        <div className="form-group">
+          <label htmlFor="backup-budget">Backup Budget (MB, 0 = unlimited)</label>
+          <input
+            id="backup-budget"
+            type="number"
+            min={0}
+            value={config.backup_budget_mb}
+            onChange={(e) =>
+              setConfig({ ...config, backup_budget_mb: parseInt(e.target.value) || 0 })
+            }
+          />
+        </div>
         <div className="form-group">
           <label htmlFor="debounce">Debounce (seconds)</label>
           <input
@@ -187,6 +471,376 @@ export default function Settings() {
         </div>
       </div>
 
+      <div className="form-row">
+        <div className="form-group">
+          <label htmlFor="keep-hours">Keep All (hours)</label>
+          <input
+            id="keep-hours"
+            type="number"
+            min={0}
+            value={config.retention.keep_all_hours}
+            onChange={(e) =>
+              setConfig({
+                ...config,
+                retention: { ...config.retention, keep_all_hours: parseInt(e.target.value) || 0 },
+              })
+            }
+          />
+        </div>
+        <div className="form-group">
+          <label htmlFor="daily-days">Daily (days)</label>
+          <input
+            id="daily-days"
+            type="number"
+            min={0}
+            value={config.retention.daily_days}
+            onChange={(e) =>
+              setConfig({
+                ...config,
+                retention: { ...config.retention, daily_days: parseInt(e.target.value) || 0 },
+              })
+            }
+          />
+        </div>
+        <div className="form-group">
+          <label htmlFor="weekly-weeks">Weekly (weeks)</label>
+          <input
+            id="weekly-weeks"
+            type="number"
+            min={0}
+            value={config.retention.weekly_weeks}
+            onChange={(e) =>
+              setConfig({
+                ...config,
+                retention: { ...config.retention, weekly_weeks: parseInt(e.target.value) || 0 },
+              })
+            }
+          />
+        </div>
+      </div>
+
+      <div className="form-row">
+        <div className="form-group">
+          <label htmlFor="schedule-interval">Scheduled Backups</label>
+          <select
+            id="schedule-interval"
+            value={config.backup_schedule.interval}
+            onChange={(e) =>
+              setConfig({
+                ...config,
+                backup_schedule: {
+                  ...config.backup_schedule,
+                  interval: e.target.value as BackupScheduleConfig["interval"],
+                },
+              })
+            }
+          >
+            <option value="off">Off</option>
+            <option value="hourly">Hourly</option>
+            <option value="daily">Daily</option>
+          </select>
+        </div>
+        <div className="form-group">
+          <label htmlFor="schedule-gg-exit">Back Up When GG Exits</label>
+          <select
+            id="schedule-gg-exit"
+            value={config.backup_schedule.on_gg_exit ? "yes" : "no"}
+            onChange={(e) =>
+              setConfig({
+                ...config,
+                backup_schedule: { ...config.backup_schedule, on_gg_exit: e.target.value === "yes" },
+              })
+            }
+          >
+            <option value="no">No</option>
+            <option value="yes">Yes</option>
+          </select>
+        </div>
+        <div className="form-group">
+          <label htmlFor="schedule-max">Max Scheduled Backups</label>
+          <input
+            id="schedule-max"
+            type="number"
+            min={1}
+            value={config.backup_schedule.max_backups}
+            onChange={(e) =>
+              setConfig({
+                ...config,
+                backup_schedule: {
+                  ...config.backup_schedule,
+                  max_backups: parseInt(e.target.value) || 24,
+                },
+              })
+            }
+          />
+        </div>
+      </div>
+
+      <div className="form-row">
+        <div className="form-group">
+          <label htmlFor="replicate-pinned">Copy Pinned Backups Off-Site</label>
+          <select
+            id="replicate-pinned"
+            value={config.replication.pinned ? "yes" : "no"}
+            onChange={(e) =>
+              setConfig({
+                ...config,
+                replication: { ...config.replication, pinned: e.target.value === "yes" },
+              })
+            }
+          >
+            <option value="no">No</option>
+            <option value="yes">Yes</option>
+          </select>
+        </div>
+        <div className="form-group">
+          <label htmlFor="replicate-daily">Copy Daily Backups Off-Site</label>
+          <select
+            id="replicate-daily"
+            value={config.replication.daily ? "yes" : "no"}
+            onChange={(e) =>
+              setConfig({
+                ...config,
+                replication: { ...config.replication, daily: e.target.value === "yes" },
+              })
+            }
+          >
+            <option value="no">No</option>
+            <option value="yes">Yes</option>
+          </select>
+        </div>
+      </div>
+
+      <div className="form-row">
+        <div className="form-group">
+          <label htmlFor="sync-include">Only Sync These Profiles/Devices</label>
+          <input
+            id="sync-include"
+            type="text"
+            value={config.selective_sync.include.join(", ")}
+            onChange={(e) =>
+              setConfig({
+                ...config,
+                selective_sync: { ...config.selective_sync, include: parseList(e.target.value) },
+              })
+            }
+            placeholder="Names or IDs, comma-separated (empty = all)"
+          />
+        </div>
+        <div className="form-group">
+          <label htmlFor="sync-exclude">Keep These Local</label>
+          <input
+            id="sync-exclude"
+            type="text"
+            value={config.selective_sync.exclude.join(", ")}
+            onChange={(e) =>
+              setConfig({
+                ...config,
+                selective_sync: { ...config.selective_sync, exclude: parseList(e.target.value) },
+              })
+            }
+            placeholder="e.g. Streaming"
+          />
+        </div>
+      </div>
+
+      <div className="form-group">
+        <label htmlFor="machine-fields">Machine-Specific Fields</label>
+        <input
+          id="machine-fields"
+          type="text"
+          value={machineFields}
+          onChange={(e) => setMachineFields(e.target.value)}
+          placeholder="table.column, comma-separated; never synced, kept per machine"
+        />
+      </div>
+
+      <div className="form-group">
+        <label htmlFor="path-mapping">Translate Game Paths From Other OSes</label>
+        <select
+          id="path-mapping"
+          value={config.path_mapping.enabled ? "yes" : "no"}
+          onChange={(e) =>
+            setConfig({
+              ...config,
+              path_mapping: { ...config.path_mapping, enabled: e.target.value === "yes" },
+            })
+          }
+        >
+          <option value="yes">Yes</option>
+          <option value="no">No</option>
+        </select>
+      </div>
+
+      <div className="form-group">
+        <label htmlFor="game-mappings">Game Install Paths</label>
+        <textarea
+          id="game-mappings"
+          rows={3}
+          value={gameMappings}
+          onChange={(e) => setGameMappings(e.target.value)}
+          placeholder={"One game per line, e.g.\nValorant: C:\\Riot Games\\VALORANT\\VALORANT.exe; /Applications/Valorant.app"}
+        />
+      </div>
+
+      <div className="form-group">
+        <label htmlFor="verify-interval">Verify Backups Every (hours, 0 = off)</label>
+        <input
+          id="verify-interval"
+          type="number"
+          min={0}
+          value={config.verify_interval_hours}
+          onChange={(e) =>
+            setConfig({ ...config, verify_interval_hours: parseInt(e.target.value) || 0 })
+          }
+        />
+      </div>
+
+      <div className="form-group">
+        <label htmlFor="archive-key">Archive Signing Key (optional)</label>
+        <input
+          id="archive-key"
+          type="password"
+          value={config.archive_signing_key ?? ""}
+          onChange={(e) =>
+            setConfig({ ...config, archive_signing_key: e.target.value || null })
+          }
+          placeholder="Shared secret; imports must then be signed with it"
+        />
+      </div>
+
+      <div className="form-group">
+        <label htmlFor="gg-restart">Apply Pulls While GG Is Running</label>
+        <select
+          id="gg-restart"
+          value={config.gg_restart.enabled ? "restart" : "manual"}
+          onChange={(e) =>
+            setConfig({
+              ...config,
+              gg_restart: { ...config.gg_restart, enabled: e.target.value === "restart" },
+            })
+          }
+        >
+          <option value="manual">Write files, I restart GG myself</option>
+          <option value="restart">Stop GG, apply, then relaunch it</option>
+        </select>
+      </div>
+
+      {config.gg_restart.enabled && (
+        <>
+          <div className="form-group">
+            <label htmlFor="gg-exe">SteelSeries GG Executable</label>
+            <input
+              id="gg-exe"
+              type="text"
+              value={config.gg_restart.executable_path}
+              onChange={(e) =>
+                setConfig({
+                  ...config,
+                  gg_restart: { ...config.gg_restart, executable_path: e.target.value },
+                })
+              }
+              placeholder="/Applications/SteelSeries GG.app"
+            />
+          </div>
+          <div className="form-row">
+            <div className="form-group">
+              <label htmlFor="gg-timeout">Exit Timeout (seconds)</label>
+              <input
+                id="gg-timeout"
+                type="number"
+                min={5}
+                max={300}
+                value={config.gg_restart.exit_timeout_secs}
+                onChange={(e) =>
+                  setConfig({
+                    ...config,
+                    gg_restart: {
+                      ...config.gg_restart,
+                      exit_timeout_secs: parseInt(e.target.value) || 30,
+                    },
+                  })
+                }
+              />
+            </div>
+            <div className="form-group">
+              <label htmlFor="gg-force">If GG Does Not Exit</label>
+              <select
+                id="gg-force"
+                value={config.gg_restart.force_kill_on_timeout ? "force" : "abort"}
+                onChange={(e) =>
+                  setConfig({
+                    ...config,
+                    gg_restart: {
+                      ...config.gg_restart,
+                      force_kill_on_timeout: e.target.value === "force",
+                    },
+                  })
+                }
+              >
+                <option value="abort">Abort the pull</option>
+                <option value="force">Force quit GG</option>
+              </select>
+            </div>
+          </div>
+        </>
+      )}
+
+      <div className="form-group">
+        <label htmlFor="kiosk">Kiosk Mode (Takes Effect on Restart)</label>
+        <select
+          id="kiosk"
+          value={config.kiosk.enabled ? "on" : "off"}
+          onChange={(e) =>
+            setConfig({ ...config, kiosk: { ...config.kiosk, enabled: e.target.value === "on" } })
+          }
+        >
+          <option value="off">Off</option>
+          <option value="on">Restore a golden backup every session, never push</option>
+        </select>
+      </div>
+
+      {config.kiosk.enabled && (
+        <div className="form-row">
+          <div className="form-group">
+            <label htmlFor="kiosk-golden">Golden Backup</label>
+            <select
+              id="kiosk-golden"
+              value={config.kiosk.golden_backup ?? ""}
+              onChange={(e) => selectGolden(e.target.value)}
+            >
+              <option value="">None</option>
+              {goldenCandidates.local.map((backup) => (
+                <option key={backup.name} value={backup.name}>
+                  {backup.name} ({new Date(backup.created).toLocaleString()})
+                </option>
+              ))}
+              {goldenCandidates.remote.length > 0 && (
+                <optgroup label="From the sync provider">
+                  {goldenCandidates.remote.map((backup) => (
+                    <option key={backup.id} value={`remote:${backup.id}`}>
+                      {backup.name} ({backup.device_name})
+                    </option>
+                  ))}
+                </optgroup>
+              )}
+            </select>
+          </div>
+          <div className="form-group">
+            <label htmlFor="kiosk-password">Override Password</label>
+            <input
+              id="kiosk-password"
+              type="password"
+              value={overridePassword}
+              onChange={(e) => setOverridePassword(e.target.value)}
+              placeholder={
+                config.kiosk.override_password_hash ? "Leave blank to keep the current one" : "Not set"
+              }
+            />
+          </div>
+        </div>
+      )}
+
       <button className="btn btn-primary" onClick={saveConfig} disabled={saving}>
         {saving ? "Saving..." : "Save Settings"}
       </button>
@@ -199,6 +853,34 @@ export default function Settings() {
           {message.text}
         </div>
       )}
+
+      <div className="status-card" style={{ marginTop: 24 }}>
+        <span className="backup-name">Device Pairings</span>
+        <p className="backup-date">
+          Devices from other machines matched to yours by model. Confirmed pairings attach
+          pulled profiles to your device.
+        </p>
+        {deviceMap.pairings.map((pairing, i) => (
+          <div key={`${pairing.table}/${pairing.remote_key}`} className="form-row">
+            <div className="form-group">
+              <label>
+                {pairing.model ?? pairing.table}: {pairing.remote_key} → {pairing.local_key}
+              </label>
+              <select
+                value={pairing.status}
+                onChange={(e) => setPairingStatus(i, e.target.value as PairingStatus)}
+              >
+                <option value="suggested">Not confirmed</option>
+                <option value="confirmed">Same device</option>
+                <option value="rejected">Different device</option>
+              </select>
+            </div>
+          </div>
+        ))}
+        <button className="btn btn-secondary btn-sm" onClick={refreshDeviceMap}>
+          Match Remote Devices
+        </button>
+      </div>
     </div>
   );
 }
@@ -1,8 +1,15 @@
-import { useState } from "react";
+import { useEffect, useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
 
 type SyncStatus = "idle" | "syncing" | "error" | "offline";
 
+interface SyncHistoryEntry {
+  at: string;
+  direction: "push" | "pull";
+  device: string;
+  changes: string[];
+}
+
 const STATUS_COLORS: Record<SyncStatus, string> = {
   idle: "#4caf50",
   syncing: "#ff9800",
@@ -23,6 +30,19 @@ export default function Status() {
   const [lastDevice, setLastDevice] = useState<string | null>(null);
   const [message, setMessage] = useState<string>("");
   const [busy, setBusy] = useState(false);
+  const [history, setHistory] = useState<SyncHistoryEntry[]>([]);
+
+  useEffect(() => {
+    fetchHistory();
+  }, []);
+
+  async function fetchHistory() {
+    try {
+      setHistory(await invoke<SyncHistoryEntry[]>("sync_history"));
+    } catch {
+      setHistory([]);
+    }
+  }
 
   async function runCommand(command: string) {
     if (busy) return;
@@ -44,6 +64,7 @@ export default function Status() {
       setMessage(String(err));
     } finally {
       setBusy(false);
+      fetchHistory();
     }
   }
 
@@ -99,10 +120,38 @@ export default function Status() {
       </div>
 
       {message && (
-        <div className={`message ${status === "error" ? "message-error" : "message-success"}`}>
+        <div
+          className={`message ${status === "error" ? "message-error" : "message-success"}`}
+          style={{ whiteSpace: "pre-line" }}
+        >
           {message}
         </div>
       )}
+
+      {history.length > 0 && (
+        <div className="status-card">
+          <span className="backup-name">Recent Syncs</span>
+          <div className="backup-list" style={{ marginTop: 12 }}>
+            {history.slice(0, 10).map((entry) => (
+              <div key={entry.at} className="backup-info">
+                <span className="backup-date">
+                  {new Date(entry.at).toLocaleString()} ·{" "}
+                  {entry.direction === "pull" ? `Pulled from ${entry.device}` : "Pushed"}
+                </span>
+                {entry.changes.length === 0 ? (
+                  <span className="backup-date">No recognizable changes</span>
+                ) : (
+                  entry.changes.map((change, i) => (
+                    <span key={i} className="backup-date">
+                      • {change}
+                    </span>
+                  ))
+                )}
+              </div>
+            ))}
+          </div>
+        </div>
+      )}
     </div>
   );
 }